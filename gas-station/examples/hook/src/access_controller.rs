@@ -1,8 +1,12 @@
 // Copyright (c) 2025 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::Arc;
+
 use axum::Json;
 use axum::http::StatusCode;
+use axum::middleware;
+use iota_types::transaction::TransactionDataAPI;
 use utoipa_axum::router::OpenApiRouter;
 use utoipa_axum::routes;
 
@@ -11,13 +15,24 @@ use crate::endpoint_types::ErrorResponse;
 use crate::endpoint_types::ExecuteTxHookRequest;
 use crate::endpoint_types::ExecuteTxOkResponse;
 use crate::endpoint_types::SkippableDecision;
+use crate::signature::SignatureVerifier;
+use crate::signature::verify_signature;
 
 pub const TEST_ERROR_HEADER: &str = "test-error";
 pub const TEST_RESPONSE_HEADER: &str = "test-response";
 
 /// Get router for access controller endpoint
+///
+/// If a secret is configured, requests have to be signed by the gas station with it.
 pub fn router() -> OpenApiRouter {
-    OpenApiRouter::new().routes(routes!(execute_tx))
+    let router = OpenApiRouter::new().routes(routes!(execute_tx));
+    match SignatureVerifier::from_env() {
+        Some(verifier) => router.layer(middleware::from_fn_with_state(
+            Arc::new(verifier),
+            verify_signature,
+        )),
+        None => router,
+    }
 }
 
 /// Check if a transaction should be executed.
@@ -29,6 +44,10 @@ pub fn router() -> OpenApiRouter {
 #[utoipa::path(
     post,
     path = "/",
+    params(
+        ("x-gas-station-signature" = Option<String>, Header, description = "Signature of the request by the gas station, if a shared secret is configured"),
+        ("x-gas-station-hook-api-version" = Option<String>, Header, description = "Version of the hook API that the request follows, `v1` or `v2`"),
+    ),
     responses(
         (status = OK, body = ExecuteTxOkResponse),
         (status = "4XX", body = ErrorResponse, description = "issues related to request arguments"), 
@@ -38,9 +57,12 @@ pub fn router() -> OpenApiRouter {
 async fn execute_tx(
     Json(tx_data): Json<ExecuteTxHookRequest>,
 ) -> Result<Json<ExecuteTxOkResponse>, RequestError> {
-    // Parsed transaction data can be used to decide if transaction should be executed or not.
-    let transaction_data = tx_data.parse_transaction_data()?;
-    dbg!(&transaction_data);
+    // Decoded transaction can be used to decide if transaction should be executed or not.
+    // Requests of API version `v1` only contain the raw transaction, which has to be parsed.
+    let _sender = match &tx_data.transaction {
+        Some(transaction) => transaction.sender.clone(),
+        None => tx_data.parse_transaction_data()?.sender().to_string(),
+    };
 
     // As this is an example server, this server supports test headers,
     // that contains the response or errors we will return from here.
@@ -20,6 +20,9 @@ use crate::RequestError;
 #[serde(rename_all = "camelCase")]
 pub struct ExecuteTxHookRequest {
     pub execute_tx_request: ExecuteTxGasStationRequest,
+    /// Decoded transaction, since API version `v2`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionSummary>,
 }
 
 /// Original request data and headers sent to Gas Stations `execute_tx` endpoint.
@@ -47,6 +50,28 @@ pub struct ExecuteTxRequestPayload {
     pub request_type: Option<ExecuteTransactionRequestType>,
 }
 
+/// Transaction as decoded by the Gas Station, so that hooks don't need to decode `txBytes`.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[schema(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionSummary {
+    /// Hex encoded address of the sender.
+    pub sender: String,
+    #[schema(format = "uint64")]
+    pub gas_budget: u64,
+    #[schema(format = "uint64")]
+    pub gas_price: u64,
+    /// Move calls of the transaction as `<package>::<module>::<function>`.
+    pub move_call_targets: Vec<String>,
+    /// Number of commands, if the transaction is a programmable transaction.
+    pub ptb_command_count: Option<usize>,
+    /// `TransactionData` as JSON, the same as the input of Rego expressions.
+    #[schema(value_type = Object)]
+    pub transaction_data: serde_json::Value,
+    /// Subject of the client certificate, if the caller authenticated with mTLS.
+    pub client_identity: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum ExecuteTransactionRequestType {
@@ -55,7 +80,8 @@ pub enum ExecuteTransactionRequestType {
 }
 
 impl ExecuteTxHookRequest {
-    /// Helper function to allow accessing transaction data easily.
+    /// Helper function to allow accessing transaction data easily. Since API version `v2`, the
+    /// decoded transaction is also available in `transaction`.
     pub fn parse_transaction_data(&self) -> Result<TransactionData, RequestError> {
         BASE64_STANDARD
             .decode(&self.execute_tx_request.payload.tx_bytes)
@@ -4,6 +4,7 @@
 mod access_controller;
 mod endpoint_types;
 mod error;
+mod signature;
 
 use std::io;
 use std::net::Ipv4Addr;
@@ -20,7 +21,10 @@ const PORT: u16 = 8080;
 const SWAGGER_UI_PATH: &str = "/swagger-ui";
 const SWAGGER_FILE_PATH: &str = "/apidoc/hook-openapi.json";
 
+/// The spec is versioned like the hook API, whose version the gas station sends in the
+/// `x-gas-station-hook-api-version` header.
 #[derive(OpenApi)]
+#[openapi(info(version = "v2"))]
 struct ApiDoc;
 
 #[tokio::main]
@@ -0,0 +1,103 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use hmac::Hmac;
+use hmac::Mac;
+use sha2::Sha256;
+
+use crate::RequestError;
+
+/// Header with the signature of requests sent by the gas station.
+pub const SIGNATURE_HEADER: &str = "x-gas-station-signature";
+/// Environment variable with the secret shared with the gas station. The gas station is
+/// configured with the same secret in the `hmac-secret-env` option of the hook.
+pub const HMAC_SECRET_ENV: &str = "HOOK_HMAC_SECRET";
+/// How far the timestamp of a request may be off, which limits replays of captured requests.
+const MAX_TIMESTAMP_SKEW_SECONDS: i64 = 300;
+/// Maximum size of request bodies, which are buffered to verify their signature.
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Verifies that requests were signed by the gas station with the shared secret.
+pub struct SignatureVerifier {
+    secret: Vec<u8>,
+}
+
+impl SignatureVerifier {
+    /// Get a verifier, if a secret is configured.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(HMAC_SECRET_ENV)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| Self {
+                secret: secret.into_bytes(),
+            })
+    }
+
+    /// Verify the signature header of a request with `body`, received at `now` (in seconds).
+    ///
+    /// The header has the format `t=<timestamp>,v1=<signature>`, where the signature is the hex
+    /// encoded HMAC-SHA256 of `<timestamp>.<body>`.
+    pub fn verify(&self, header: &str, body: &[u8], now: i64) -> anyhow::Result<()> {
+        let mut timestamp = None;
+        let mut signature = None;
+        for part in header.split(',') {
+            match part.split_once('=') {
+                Some(("t", value)) => timestamp = Some(value),
+                Some(("v1", value)) => signature = Some(hex::decode(value)?),
+                _ => {}
+            }
+        }
+        let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+            bail!("malformed {SIGNATURE_HEADER} header");
+        };
+        if (now - timestamp.parse::<i64>()?).abs() > MAX_TIMESTAMP_SKEW_SECONDS {
+            bail!("request timestamp {timestamp} is too far off");
+        }
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)?;
+        mac.update(timestamp.as_bytes());
+        mac.update(b".");
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| anyhow!("invalid request signature"))
+    }
+}
+
+/// Middleware that rejects requests without a valid signature of the gas station.
+pub async fn verify_signature(
+    State(verifier): State<Arc<SignatureVerifier>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, RequestError> {
+    let unauthorized = |err: anyhow::Error| {
+        RequestError::new(err)
+            .with_status(StatusCode::UNAUTHORIZED)
+            .with_user_message("request is not signed by the gas station")
+    };
+    let (parts, body) = request.into_parts();
+    let header = parts
+        .headers
+        .get(SIGNATURE_HEADER)
+        .ok_or_else(|| unauthorized(anyhow!("missing {SIGNATURE_HEADER} header")))?
+        .to_str()
+        .map_err(|err| unauthorized(err.into()))?
+        .to_string();
+    let body = axum::body::to_bytes(body, MAX_BODY_SIZE)
+        .await
+        .map_err(|err| RequestError::new(err.into()).with_status(StatusCode::BAD_REQUEST))?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    verifier.verify(&header, &body, now).map_err(unauthorized)?;
+
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
@@ -45,3 +45,23 @@ impl From<AccessPolicy> for Decision {
         }
     }
 }
+
+/// What the decision of the access controller was taken by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionSource {
+    /// The rule at the given position, starting at 1.
+    Rule(usize),
+    /// The access policy, as no rule decided.
+    Policy(AccessPolicy),
+}
+
+impl std::fmt::Display for DecisionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecisionSource::Rule(position) => write!(f, "rule #{}", position),
+            DecisionSource::Policy(AccessPolicy::Disabled) => write!(f, "policy disabled"),
+            DecisionSource::Policy(AccessPolicy::DenyAll) => write!(f, "policy deny-all"),
+            DecisionSource::Policy(AccessPolicy::AllowAll) => write!(f, "policy allow-all"),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Calls of hooks with retries, a circuit breaker and a policy for unavailable hooks.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use reqwest::header::CONTENT_TYPE;
+use tracing::{debug, warn};
+
+use crate::access_controller::hook::{
+    CircuitBreakerConfig, ExecuteTxOkResponse, HookAction, HOOK_SIGNATURE_HEADER,
+};
+use crate::access_controller::rule::TransactionContext;
+use crate::metrics::GasStationRpcMetrics;
+
+/// Failure of a single request to a hook.
+pub(crate) enum HookRequestError {
+    /// The request could not be built, e.g. because the hook is misconfigured. It is neither
+    /// retried nor subject to the `on-failure` policy.
+    Local(anyhow::Error),
+    /// The hook could not be reached, timed out, or failed with a server error or an invalid
+    /// response.
+    Unavailable(anyhow::Error),
+    /// The hook answered with a client error, e.g. to reject the transaction with a message.
+    Rejected(anyhow::Error),
+}
+
+impl From<reqwest::Error> for HookRequestError {
+    fn from(err: reqwest::Error) -> Self {
+        HookRequestError::Unavailable(err.into())
+    }
+}
+
+/// State of a hook that is shared by all clones of its action.
+#[derive(Default)]
+pub struct HookState {
+    pub(crate) client: OnceCell<reqwest::Client>,
+    /// The secret requests are signed with, if any, read from the environment on first use.
+    pub(crate) secret: OnceCell<Option<Vec<u8>>>,
+    circuit_breaker: Mutex<CircuitBreaker>,
+}
+
+impl std::fmt::Debug for HookState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HookState")
+            .field("circuit_breaker", &*self.circuit_breaker.lock())
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    /// Set while the circuit is open. Once it is over, a single trial request is let through,
+    /// which opens the circuit again for the same duration until it succeeds.
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Returns whether the hook may be called at `now`.
+    fn try_acquire(&mut self, config: &CircuitBreakerConfig, now: Instant) -> bool {
+        match self.open_until {
+            None => true,
+            Some(open_until) if now >= open_until => {
+                // Other requests wait for the outcome of this trial request. If the trial is
+                // cancelled, another one is let through after the open duration.
+                self.open_until = Some(now + config.open_duration());
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    /// Records a failed request and returns whether the circuit was opened by it.
+    fn record_failure(&mut self, config: &CircuitBreakerConfig, now: Instant) -> bool {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if config.failure_threshold == 0 {
+            return false;
+        }
+        if self.open_until.is_some() || self.consecutive_failures >= config.failure_threshold {
+            self.open_until = Some(now + config.open_duration());
+            return true;
+        }
+        false
+    }
+}
+
+impl CircuitBreakerConfig {
+    fn open_duration(&self) -> Duration {
+        Duration::from_secs(self.open_duration_sec)
+    }
+}
+
+impl HookAction {
+    /// Call hook to let it decide about transaction processing.
+    ///
+    /// Requests fail if the hook is unavailable and are retried with an exponential backoff. If
+    /// the hook is still unavailable, the `on-failure` policy of the hook decides, or the error is
+    /// returned if no policy is set. A misconfigured hook always fails.
+    pub async fn call_hook(
+        &self,
+        ctx: &TransactionContext,
+        metrics: Option<&GasStationRpcMetrics>,
+    ) -> Result<ExecuteTxOkResponse, anyhow::Error> {
+        let err = match self.call_hook_with_retries(ctx, metrics).await {
+            Ok(response) => return Ok(response),
+            Err(HookRequestError::Local(err)) | Err(HookRequestError::Rejected(err)) => {
+                return Err(err)
+            }
+            Err(HookRequestError::Unavailable(err)) => err,
+        };
+        let Some(policy) = self.options.on_failure else {
+            return Err(err);
+        };
+        warn!(
+            "Hook {} is unavailable, applying its on-failure policy {:?}: {:#}",
+            self.url, policy, err
+        );
+        Ok(ExecuteTxOkResponse {
+            decision: policy.into(),
+            user_message: None,
+        })
+    }
+
+    async fn call_hook_with_retries(
+        &self,
+        ctx: &TransactionContext,
+        metrics: Option<&GasStationRpcMetrics>,
+    ) -> Result<ExecuteTxOkResponse, HookRequestError> {
+        let config = &self.options.circuit_breaker;
+        let hook = self.metrics_label();
+        let record_error = |kind: &str| {
+            if let Some(metrics) = metrics {
+                metrics
+                    .num_hook_errors
+                    .with_label_values(&[hook.as_str(), kind])
+                    .inc();
+            }
+        };
+        // Usually checked when the access controller is initialized already.
+        if let Err(err) = self.initialize() {
+            record_error("misconfigured");
+            return Err(HookRequestError::Local(err));
+        }
+        let mut backoff = Duration::from_millis(self.options.retry_backoff_ms);
+        let mut retries = 0;
+        loop {
+            if !self
+                .state
+                .circuit_breaker
+                .lock()
+                .try_acquire(config, Instant::now())
+            {
+                record_error("circuit-open");
+                return Err(HookRequestError::Unavailable(anyhow!(
+                    "hook {} is unavailable after repeated failures",
+                    self.url
+                )));
+            }
+
+            let start = Instant::now();
+            let result = self.request_hook(ctx).await;
+            if let Some(metrics) = metrics {
+                metrics
+                    .hook_request_latency_ms
+                    .with_label_values(&[hook.as_str()])
+                    .observe(start.elapsed().as_millis() as f64);
+            }
+            let err = match result {
+                Ok(response) => {
+                    self.state.circuit_breaker.lock().record_success();
+                    return Ok(response);
+                }
+                Err(HookRequestError::Rejected(err)) => {
+                    // The hook is available, it only did not accept the request.
+                    self.state.circuit_breaker.lock().record_success();
+                    record_error("rejected");
+                    return Err(HookRequestError::Rejected(err));
+                }
+                Err(HookRequestError::Local(err)) => {
+                    // The hook was not called, so the circuit breaker is not affected.
+                    record_error("misconfigured");
+                    return Err(HookRequestError::Local(err));
+                }
+                Err(HookRequestError::Unavailable(err)) => err,
+            };
+            record_error("unavailable");
+            if self
+                .state
+                .circuit_breaker
+                .lock()
+                .record_failure(config, Instant::now())
+            {
+                warn!(
+                    "Not calling hook {} for {}s after repeated failures: {:#}",
+                    self.url, config.open_duration_sec, err
+                );
+                return Err(HookRequestError::Unavailable(err));
+            }
+            if retries >= self.options.max_retries {
+                return Err(HookRequestError::Unavailable(err));
+            }
+            retries += 1;
+            debug!(
+                "Retrying hook {} in {:?} ({}/{}): {:#}",
+                self.url, backoff, retries, self.options.max_retries, err
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+
+    /// Posts a notification with the JSON `body` to the hook, e.g. about an executed
+    /// transaction. Unlike calls of the hook, it is neither retried nor subject to the circuit
+    /// breaker, as notifications are retried from the outbox.
+    pub(crate) async fn send_notification(&self, body: Vec<u8>) -> anyhow::Result<()> {
+        let mut request = self
+            .client()?
+            .post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(signature) = self.signature_header(&body)? {
+            request = request.header(HOOK_SIGNATURE_HEADER, signature);
+        }
+        let res = request.body(body).send().await?;
+        if !res.status().is_success() {
+            bail!(
+                "notification failed with status {}; {}",
+                res.status(),
+                res.text().await.unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+
+    /// Label of the hook in metrics. The query is left out, as it may contain credentials.
+    fn metrics_label(&self) -> String {
+        format!(
+            "{}{}",
+            self.url.origin().ascii_serialization(),
+            self.url.path()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            open_duration_sec: 10,
+        };
+        let mut breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        assert!(breaker.try_acquire(&config, now));
+        assert!(!breaker.record_failure(&config, now));
+        // A success resets the count of consecutive failures.
+        breaker.record_success();
+        assert!(!breaker.record_failure(&config, now));
+        assert!(breaker.record_failure(&config, now));
+        assert!(!breaker.try_acquire(&config, now));
+        assert!(!breaker.try_acquire(&config, now + Duration::from_secs(9)));
+
+        // A single trial request is let through after the open duration.
+        let later = now + Duration::from_secs(10);
+        assert!(breaker.try_acquire(&config, later));
+        assert!(!breaker.try_acquire(&config, later));
+        // A failed trial opens the circuit again.
+        assert!(breaker.record_failure(&config, later));
+        assert!(!breaker.try_acquire(&config, later + Duration::from_secs(9)));
+
+        let later = later + Duration::from_secs(10);
+        assert!(breaker.try_acquire(&config, later));
+        breaker.record_success();
+        assert!(breaker.try_acquire(&config, later));
+    }
+
+    #[test]
+    fn test_disabled_circuit_breaker() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 0,
+            open_duration_sec: 10,
+        };
+        let mut breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        for _ in 0..10 {
+            assert!(!breaker.record_failure(&config, now));
+            assert!(breaker.try_acquire(&config, now));
+        }
+    }
+}
@@ -0,0 +1,365 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::access_controller::hook::{
+    sign_hook_request, HookApiVersion, HookState, SkippableDecision,
+};
+use crate::config::ClientTlsConfig;
+
+const DEFAULT_HOOK_TIMEOUT_MS: u64 = 60_000;
+const DEFAULT_HOOK_RETRY_BACKOFF_MS: u64 = 200;
+const DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_CIRCUIT_BREAKER_OPEN_DURATION_SEC: u64 = 30;
+
+/// A hook that decides about transactions. It is configured either by its URL alone, or by a map
+/// with the URL and the options of the hook.
+///
+/// Clones of a hook share its connection pool and circuit breaker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "HookActionRepr", into = "HookActionRepr")]
+pub struct HookAction {
+    pub(crate) url: Url,
+    pub(crate) options: HookOptions,
+    pub(crate) state: Arc<HookState>,
+}
+
+impl PartialEq for HookAction {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url && self.options == other.options
+    }
+}
+
+impl Eq for HookAction {}
+
+/// Options of a hook to authenticate the gas station and to handle failed calls.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct HookOptions {
+    /// Name of the environment variable with the secret shared with the hook. If set, requests
+    /// are signed in the `x-gas-station-signature` header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmac_secret_env: Option<String>,
+    /// Headers added to every request, e.g. a static authorization header.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Optional CA certificates to trust and client certificate for mutual TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<ClientTlsConfig>,
+    /// Timeout of a single request to the hook, in milliseconds.
+    #[serde(default = "default_hook_timeout_ms")]
+    pub timeout_ms: u64,
+    /// How often a request is retried if the hook is unavailable, i.e. it cannot be reached, times
+    /// out or fails with a server error. Requests that the hook rejects are not retried.
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Delay before the first retry, in milliseconds. It doubles with every further retry.
+    #[serde(default = "default_hook_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// Stops calling the hook for a while after repeated failures.
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Decision taken if the hook is unavailable. If not set, the transaction fails with an
+    /// error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<HookFailurePolicy>,
+    /// Version of the requests sent to the hook. `v2` adds the decoded transaction.
+    #[serde(default)]
+    pub api_version: HookApiVersion,
+}
+
+impl Default for HookOptions {
+    fn default() -> Self {
+        Self {
+            hmac_secret_env: None,
+            headers: BTreeMap::new(),
+            tls: None,
+            timeout_ms: DEFAULT_HOOK_TIMEOUT_MS,
+            max_retries: 0,
+            retry_backoff_ms: DEFAULT_HOOK_RETRY_BACKOFF_MS,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            on_failure: None,
+            api_version: HookApiVersion::default(),
+        }
+    }
+}
+
+/// Circuit breaker of a hook. After `failure-threshold` consecutive failed requests, the hook is
+/// not called for `open-duration-sec` seconds. Then a single trial request decides whether the
+/// hook is called again. A threshold of 0 disables the circuit breaker.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct CircuitBreakerConfig {
+    #[serde(default = "default_circuit_breaker_failure_threshold")]
+    pub failure_threshold: u32,
+    #[serde(default = "default_circuit_breaker_open_duration_sec")]
+    pub open_duration_sec: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD,
+            open_duration_sec: DEFAULT_CIRCUIT_BREAKER_OPEN_DURATION_SEC,
+        }
+    }
+}
+
+/// Decision taken instead of the decision of an unavailable hook.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookFailurePolicy {
+    Allow,
+    Deny,
+    /// Proceed with the next rules, as if the hook did not decide.
+    NoDecision,
+}
+
+impl From<HookFailurePolicy> for SkippableDecision {
+    fn from(policy: HookFailurePolicy) -> Self {
+        match policy {
+            HookFailurePolicy::Allow => SkippableDecision::Allow,
+            HookFailurePolicy::Deny => SkippableDecision::Deny,
+            HookFailurePolicy::NoDecision => SkippableDecision::NoDecision,
+        }
+    }
+}
+
+fn default_hook_timeout_ms() -> u64 {
+    DEFAULT_HOOK_TIMEOUT_MS
+}
+
+fn default_hook_retry_backoff_ms() -> u64 {
+    DEFAULT_HOOK_RETRY_BACKOFF_MS
+}
+
+fn default_circuit_breaker_failure_threshold() -> u32 {
+    DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD
+}
+
+fn default_circuit_breaker_open_duration_sec() -> u64 {
+    DEFAULT_CIRCUIT_BREAKER_OPEN_DURATION_SEC
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum HookActionRepr {
+    Url(Url),
+    Hook {
+        url: Url,
+        #[serde(flatten)]
+        options: HookOptions,
+    },
+}
+
+impl From<HookActionRepr> for HookAction {
+    fn from(repr: HookActionRepr) -> Self {
+        match repr {
+            HookActionRepr::Url(url) => HookAction::new(url),
+            HookActionRepr::Hook { url, options } => HookAction::new(url).with_options(options),
+        }
+    }
+}
+
+impl From<HookAction> for HookActionRepr {
+    fn from(hook: HookAction) -> Self {
+        // Hooks without options are written in the short form.
+        if hook.options == HookOptions::default() {
+            HookActionRepr::Url(hook.url)
+        } else {
+            HookActionRepr::Hook {
+                url: hook.url,
+                options: hook.options,
+            }
+        }
+    }
+}
+
+impl HookAction {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            options: HookOptions::default(),
+            state: Arc::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: HookOptions) -> Self {
+        self.options = options;
+        // The client and the circuit breaker depend on the options.
+        self.state = Arc::default();
+        self
+    }
+
+    /// Returns the client to call the hook with. It is built on first use and then shared, so that
+    /// connections to the hook are reused.
+    pub(crate) fn client(&self) -> anyhow::Result<&reqwest::Client> {
+        self.state.client.get_or_try_init(|| self.build_client())
+    }
+
+    /// Builds the client to call the hook with, including the configured headers, timeout and TLS
+    /// options.
+    pub(crate) fn build_client(&self) -> anyhow::Result<reqwest::Client> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.options.headers {
+            let mut value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value of the hook header {}", name))?;
+            value.set_sensitive(true);
+            headers.insert(HeaderName::from_bytes(name.as_bytes())?, value);
+        }
+        let mut builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(Duration::from_millis(self.options.timeout_ms));
+        if let Some(tls) = &self.options.tls {
+            builder = tls.configure(builder)?;
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Builds the client and reads the secret of the hook, so that a misconfigured hook is
+    /// detected before it is called.
+    pub fn initialize(&self) -> anyhow::Result<()> {
+        self.client()
+            .and(self.secret())
+            .with_context(|| format!("Invalid configuration of the hook {}", self.url))?;
+        Ok(())
+    }
+
+    /// Returns the secret requests are signed with, if requests to the hook are signed.
+    fn secret(&self) -> anyhow::Result<Option<&[u8]>> {
+        let secret = self.state.secret.get_or_try_init(|| {
+            let Some(env_name) = &self.options.hmac_secret_env else {
+                return Ok(None);
+            };
+            std::env::var(env_name)
+                .ok()
+                .filter(|secret| !secret.is_empty())
+                .map(|secret| Some(secret.into_bytes()))
+                .with_context(|| format!("The hook secret is not set in {}", env_name))
+        })?;
+        Ok(secret.as_deref())
+    }
+
+    /// Returns the signature header of a request with `body`, if requests to the hook are signed.
+    pub(crate) fn signature_header(&self, body: &[u8]) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .secret()?
+            .map(|secret| sign_hook_request(secret, chrono::Utc::now().timestamp(), body)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_hook_action() {
+        let hook: HookAction = serde_yaml::from_str("http://example.org/").unwrap();
+        assert_eq!(
+            hook,
+            HookAction::new(Url::parse("http://example.org/").unwrap())
+        );
+
+        let hook: HookAction = serde_yaml::from_str(
+            r#"
+url: https://example.org/hook
+hmac-secret-env: HOOK_SECRET
+headers:
+  authorization: Bearer token
+tls:
+  ca-cert-path: /etc/hook/ca.pem
+"#,
+        )
+        .unwrap();
+        assert_eq!(hook.url.as_str(), "https://example.org/hook");
+        assert_eq!(hook.options.hmac_secret_env.as_deref(), Some("HOOK_SECRET"));
+        assert_eq!(hook.options.headers["authorization"], "Bearer token");
+        assert!(hook.options.tls.is_some());
+        assert_eq!(hook.options.timeout_ms, DEFAULT_HOOK_TIMEOUT_MS);
+        assert_eq!(hook.options.on_failure, None);
+        assert_eq!(hook.options.api_version, HookApiVersion::V1);
+
+        // Hooks with options are written in the long form, and read back the same.
+        let serialized = serde_yaml::to_string(&hook).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<HookAction>(&serialized).unwrap(),
+            hook
+        );
+    }
+
+    #[test]
+    fn test_deserialize_hook_failure_options() {
+        let hook: HookAction = serde_yaml::from_str(
+            r#"
+url: https://example.org/hook
+timeout-ms: 500
+max-retries: 2
+retry-backoff-ms: 50
+circuit-breaker:
+  failure-threshold: 3
+on-failure: no-decision
+api-version: v2
+"#,
+        )
+        .unwrap();
+        assert_eq!(hook.options.timeout_ms, 500);
+        assert_eq!(hook.options.max_retries, 2);
+        assert_eq!(hook.options.retry_backoff_ms, 50);
+        assert_eq!(
+            hook.options.circuit_breaker,
+            CircuitBreakerConfig {
+                failure_threshold: 3,
+                open_duration_sec: DEFAULT_CIRCUIT_BREAKER_OPEN_DURATION_SEC,
+            }
+        );
+        assert_eq!(hook.options.on_failure, Some(HookFailurePolicy::NoDecision));
+        assert_eq!(hook.options.api_version, HookApiVersion::V2);
+        assert!(serde_yaml::from_str::<HookAction>(
+            "{url: 'https://example.org/hook', on-failure: maybe}"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_hook_request_options() {
+        let env_name = "GAS_STATION_TEST_HOOK_SECRET";
+        let hook = HookAction::new(Url::parse("http://example.org/").unwrap());
+        assert!(hook.signature_header(b"{}").unwrap().is_none());
+        assert!(hook.build_client().is_ok());
+        // Clones of the hook share its client.
+        assert!(std::ptr::eq(
+            hook.client().unwrap(),
+            hook.clone().client().unwrap()
+        ));
+
+        let hook = hook.with_options(HookOptions {
+            hmac_secret_env: Some(env_name.to_string()),
+            headers: BTreeMap::from([("x-api-key".to_string(), "key".to_string())]),
+            ..Default::default()
+        });
+        assert!(hook.signature_header(b"{}").is_err());
+        std::env::set_var(env_name, "secret");
+        let header = hook.signature_header(b"{}").unwrap().unwrap();
+        let (timestamp, _) = header.strip_prefix("t=").unwrap().split_once(',').unwrap();
+        assert_eq!(
+            header,
+            sign_hook_request(b"secret", timestamp.parse().unwrap(), b"{}")
+        );
+        std::env::remove_var(env_name);
+        assert!(hook.build_client().is_ok());
+
+        let hook = hook.with_options(HookOptions {
+            headers: BTreeMap::from([("x-api-key".to_string(), "in\nvalid".to_string())]),
+            ..Default::default()
+        });
+        assert!(hook.build_client().is_err());
+    }
+}
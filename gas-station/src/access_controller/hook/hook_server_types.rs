@@ -5,16 +5,80 @@
 
 use std::collections::HashMap;
 
+use iota_types::base_types::IotaAddress;
 use serde::{Deserialize, Serialize};
 
+use crate::access_controller::rule::TransactionContext;
 use crate::rpc::rpc_types::ExecuteTransactionRequestType;
 
+/// Header with the version of the hook API that a request follows.
+pub const HOOK_API_VERSION_HEADER: &str = "x-gas-station-hook-api-version";
+
+/// Version of the requests sent to a hook.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookApiVersion {
+    /// Contains the original request for Gas Stations `execute_tx` endpoint only. The default,
+    /// so that existing hooks keep receiving the requests they were written for.
+    #[default]
+    V1,
+    /// Adds the transaction as decoded by the Gas Station.
+    V2,
+}
+
+impl HookApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookApiVersion::V1 => "v1",
+            HookApiVersion::V2 => "v2",
+        }
+    }
+}
+
 /// Input for hook to check if transaction should be executed.
 /// Contains original request for Gas Stations `execute_tx` endpoint.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExecuteTxHookRequest {
     pub execute_tx_request: ExecuteTxGasStationRequest,
+    /// Decoded transaction, since API version `v2`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionSummary>,
+}
+
+impl ExecuteTxHookRequest {
+    /// Builds the request for a hook that follows `api_version`.
+    pub fn new(ctx: &TransactionContext, api_version: HookApiVersion) -> Self {
+        let transaction = match api_version {
+            HookApiVersion::V1 => None,
+            HookApiVersion::V2 => Some(TransactionSummary::from(ctx)),
+        };
+        Self {
+            execute_tx_request: ExecuteTxGasStationRequest {
+                payload: ExecuteTxRequestPayload {
+                    reservation_id: ctx.reservation_id,
+                    tx_bytes: ctx.tx_bytes.encoded(),
+                    user_sig: ctx.user_sig.encoded(),
+                    request_type: ctx.request_type.clone(),
+                },
+                headers: convert_header_map_to_vec(ctx),
+            },
+            transaction,
+        }
+    }
+}
+
+fn convert_header_map_to_vec(ctx: &TransactionContext) -> HashMap<String, Vec<String>> {
+    let mut header_hashmap: HashMap<String, Vec<String>> = HashMap::new();
+    for (k, v) in ctx.headers.iter() {
+        let v = String::from_utf8_lossy(v.as_bytes()).into_owned();
+        header_hashmap
+            .entry(k.to_string())
+            .or_insert_with(Vec::new)
+            .push(v);
+    }
+
+    header_hashmap
 }
 
 /// Original request data and headers sent to Gas Stations `execute_tx` endpoint.
@@ -39,6 +103,37 @@ pub struct ExecuteTxRequestPayload {
     pub request_type: Option<ExecuteTransactionRequestType>,
 }
 
+/// Transaction as decoded by the Gas Station, so that hooks don't need to decode `txBytes`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionSummary {
+    pub sender: IotaAddress,
+    pub gas_budget: u64,
+    pub gas_price: u64,
+    /// Move calls of the transaction as `<package>::<module>::<function>`.
+    pub move_call_targets: Vec<String>,
+    /// Number of commands, if the transaction is a programmable transaction.
+    pub ptb_command_count: Option<usize>,
+    /// `TransactionData` as JSON, the same as the input of Rego expressions.
+    pub transaction_data: serde_json::Value,
+    /// Subject of the client certificate, if the caller authenticated with mTLS.
+    pub client_identity: Option<String>,
+}
+
+impl From<&TransactionContext> for TransactionSummary {
+    fn from(ctx: &TransactionContext) -> Self {
+        Self {
+            sender: ctx.sender_address,
+            gas_budget: ctx.transaction_budget,
+            gas_price: ctx.gas_price,
+            move_call_targets: ctx.move_call_targets.clone(),
+            ptb_command_count: ctx.ptb_command_count,
+            transaction_data: ctx.transaction_data.clone(),
+            client_identity: ctx.client_identity.clone(),
+        }
+    }
+}
+
 /// Result of checking if transaction should be executed.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -58,3 +153,54 @@ pub enum SkippableDecision {
     Deny,
     NoDecision,
 }
+
+#[cfg(test)]
+mod test {
+    use axum::http::HeaderMap;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_hook_request_versions() {
+        let sender = IotaAddress::new([1; 32]);
+        let mut headers = HeaderMap::new();
+        headers.append("x-custom", "a".parse().unwrap());
+        headers.append("x-custom", "b".parse().unwrap());
+        let ctx = TransactionContext::default()
+            .with_sender_address(sender)
+            .with_gas_budget(1000)
+            .with_gas_price(100)
+            .with_move_call_targets(vec!["0x2::coin::split".to_string()])
+            .with_ptb_command_count(2)
+            .with_transaction_data(json!({"kind": "ProgrammableTransaction"}))
+            .with_client_identity("CN=client".to_string())
+            .with_reservation_id(7)
+            .with_headers(headers);
+
+        let request =
+            serde_json::to_value(ExecuteTxHookRequest::new(&ctx, HookApiVersion::V1)).unwrap();
+        assert!(request.get("transaction").is_none());
+        assert_eq!(request["executeTxRequest"]["payload"]["reservationId"], 7);
+        assert_eq!(
+            request["executeTxRequest"]["headers"]["x-custom"],
+            json!(["a", "b"])
+        );
+
+        let request =
+            serde_json::to_value(ExecuteTxHookRequest::new(&ctx, HookApiVersion::V2)).unwrap();
+        assert_eq!(request["executeTxRequest"]["payload"]["reservationId"], 7);
+        assert_eq!(
+            request["transaction"],
+            json!({
+                "sender": sender.to_string(),
+                "gasBudget": 1000,
+                "gasPrice": 100,
+                "moveCallTargets": ["0x2::coin::split"],
+                "ptbCommandCount": 2,
+                "transactionData": {"kind": "ProgrammableTransaction"},
+                "clientIdentity": "CN=client",
+            })
+        );
+    }
+}
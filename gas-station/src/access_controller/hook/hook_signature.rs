@@ -0,0 +1,39 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signature of hook requests with a secret shared by the gas station and the hook.
+
+use fastcrypto::encoding::{Encoding, Hex};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Header with the signature of a hook request, in the format `t=<timestamp>,v1=<signature>`.
+/// The timestamp is in seconds since the Unix epoch. The signature is the hex encoded
+/// HMAC-SHA256 of `<timestamp>.<body>`, so that a hook can reject replayed requests.
+pub const HOOK_SIGNATURE_HEADER: &str = "x-gas-station-signature";
+
+/// Returns the value of the signature header of a request with `body`, sent at `timestamp`.
+pub fn sign_hook_request(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!(
+        "t={},v1={}",
+        timestamp,
+        Hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sign_hook_request() {
+        assert_eq!(
+            sign_hook_request(b"secret", 1700000000, br#"{"a":1}"#),
+            "t=1700000000,v1=49f24e537407743fa4a0242bb63b94b9a47ee99cbbe071ccd8a22550ae411686"
+        );
+    }
+}
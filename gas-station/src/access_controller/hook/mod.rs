@@ -3,7 +3,13 @@
 
 #[cfg_attr(test, path = "hook_action_test.rs")]
 mod hook_action;
+mod hook_client;
+mod hook_config;
 mod hook_server_types;
+mod hook_signature;
 
 pub use hook_action::*;
+pub use hook_client::*;
+pub use hook_config::*;
 pub use hook_server_types::*;
+pub use hook_signature::*;
@@ -13,8 +13,8 @@ pub mod rule;
 use std::{collections::HashMap, fmt::Formatter, sync::Arc};
 
 use anyhow::{anyhow, Context, Result};
-use decision::Decision;
-use hook::SkippableDecision;
+use decision::{Decision, DecisionSource};
+use hook::{HookAction, SkippableDecision};
 use iota_types::digests::TransactionDigest;
 use policy::AccessPolicy;
 use predicates::Action;
@@ -23,6 +23,8 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tracing::debug;
 
+use crate::errors::GasStationError;
+use crate::metrics::GasStationRpcMetrics;
 use crate::tracker::StatsTracker;
 
 #[derive(Clone, Serialize, Deserialize, Default)]
@@ -34,6 +36,9 @@ pub struct AccessController {
 
     #[serde(skip)]
     confirmation_requests: Arc<Mutex<HashMap<TransactionDigest, Vec<GasUsageConfirmationRequest>>>>,
+    /// Metrics to export the latency and errors of hook calls to.
+    #[serde(skip)]
+    metrics: Option<Arc<GasStationRpcMetrics>>,
 }
 
 impl std::fmt::Debug for AccessController {
@@ -52,9 +57,15 @@ impl AccessController {
             access_policy,
             rules: rules.into_iter().collect(),
             confirmation_requests: Arc::new(Mutex::new(HashMap::new())),
+            metrics: None,
         }
     }
 
+    /// Sets the metrics to export the latency and errors of hook calls to.
+    pub fn set_metrics(&mut self, metrics: Arc<GasStationRpcMetrics>) {
+        self.metrics = Some(metrics);
+    }
+
     /// Initializes the access controller by loading the rules from the external sources
     pub async fn initialize(&mut self) -> Result<()> {
         for (i, rule) in &mut self.rules.iter_mut().enumerate() {
@@ -68,8 +79,18 @@ impl AccessController {
     // If a rule matches, the corresponding action is applied. If no rule matches, the next rule is checked.
     // If none match, the default policy is applied.
     pub async fn check_access(&self, ctx: &TransactionContext) -> Result<Decision> {
+        self.check_access_with_source(ctx)
+            .await
+            .map(|(decision, _)| decision)
+    }
+
+    /// Same as `check_access`, but also returns the rule or policy that took the decision.
+    pub async fn check_access_with_source(
+        &self,
+        ctx: &TransactionContext,
+    ) -> Result<(Decision, DecisionSource)> {
         if self.is_disabled() {
-            return Ok(Decision::Allow);
+            return Ok((Decision::Allow, DecisionSource::Policy(self.access_policy)));
         }
 
         for (i, rule) in self.rules.iter().enumerate() {
@@ -89,20 +110,27 @@ impl AccessController {
                 // if the rule matches and also matches the global limits, invoke the action
                 if matching_result.0 {
                     match &rule.action {
-                        Action::Allow => return Ok(Decision::Allow),
-                        Action::Deny => return Ok(Decision::Deny),
+                        Action::Allow => return Ok((Decision::Allow, DecisionSource::Rule(i + 1))),
+                        Action::Deny => return Ok((Decision::Deny, DecisionSource::Rule(i + 1))),
                         Action::HookAction(hook_action) => {
                             // call hook and take defined result or continue with next rule
-                            let response = hook_action.call_hook(ctx).await?;
+                            let response = hook_action
+                                .call_hook(ctx, self.metrics.as_deref())
+                                .await
+                                .map_err(|err| GasStationError::HookError(err.to_string()))?;
                             debug!("Called hook: {}, for transaction with digest: {}. Got decision: {:?}, with user message: {:?}",
-                                    hook_action.0,
+                                    hook_action.url,
                                     ctx.transaction_digest,
                                     response.decision,
                                     response.user_message,
                                 );
                             match response.decision {
-                                SkippableDecision::Allow => return Ok(Decision::Allow),
-                                SkippableDecision::Deny => return Ok(Decision::Deny),
+                                SkippableDecision::Allow => {
+                                    return Ok((Decision::Allow, DecisionSource::Rule(i + 1)))
+                                }
+                                SkippableDecision::Deny => {
+                                    return Ok((Decision::Deny, DecisionSource::Rule(i + 1)))
+                                }
                                 _ => (),
                             };
                         }
@@ -111,11 +139,12 @@ impl AccessController {
             }
         }
 
-        match self.access_policy {
-            AccessPolicy::AllowAll => Ok(Decision::Allow),
-            AccessPolicy::DenyAll => Ok(Decision::Deny),
-            AccessPolicy::Disabled => Ok(Decision::Allow),
-        }
+        let decision = match self.access_policy {
+            AccessPolicy::AllowAll => Decision::Allow,
+            AccessPolicy::DenyAll => Decision::Deny,
+            AccessPolicy::Disabled => Decision::Allow,
+        };
+        Ok((decision, DecisionSource::Policy(self.access_policy)))
     }
 
     pub async fn confirm_transaction(
@@ -146,6 +175,14 @@ impl AccessController {
         Ok(())
     }
 
+    /// Returns the webhook that is sent the outcome of a transaction allowed by `source`.
+    pub fn notification_hook(&self, source: DecisionSource) -> Option<&HookAction> {
+        match source {
+            DecisionSource::Rule(number) => self.rules.get(number - 1)?.notify.as_ref(),
+            DecisionSource::Policy(_) => None,
+        }
+    }
+
     /// Adds a new rule to the access controller.
     pub fn add_rule(&mut self, rule: AccessRule) {
         self.rules.push(rule);
@@ -188,9 +225,11 @@ impl TransactionExecutionResult {
 #[cfg(test)]
 mod test {
     use iota_types::base_types::IotaAddress;
+    use url::Url;
 
     use crate::access_controller::{
-        decision::Decision,
+        decision::{Decision, DecisionSource},
+        hook::HookAction,
         predicates::{Action, ValueIotaAddress},
         AccessController,
     };
@@ -241,6 +280,64 @@ mod test {
         ));
     }
 
+    #[tokio::test]
+    async fn test_decision_source() {
+        let sender_address = IotaAddress::new([1; 32]);
+        let other_address = IotaAddress::new([2; 32]);
+        let ac = AccessController::new(
+            AccessPolicy::DenyAll,
+            [
+                AccessRuleBuilder::new()
+                    .sender_address(other_address)
+                    .deny()
+                    .build(),
+                AccessRuleBuilder::new()
+                    .sender_address(sender_address)
+                    .allow()
+                    .build(),
+            ],
+        );
+        let tx = TransactionContext {
+            sender_address,
+            ..Default::default()
+        };
+        let (decision, source) = ac.check_access_with_source(&tx).await.unwrap();
+        assert_eq!(decision, Decision::Allow);
+        assert_eq!(source, DecisionSource::Rule(2));
+        assert_eq!(source.to_string(), "rule #2");
+
+        let tx = TransactionContext {
+            sender_address: IotaAddress::new([3; 32]),
+            ..Default::default()
+        };
+        let (decision, source) = ac.check_access_with_source(&tx).await.unwrap();
+        assert_eq!(decision, Decision::Deny);
+        assert_eq!(source.to_string(), "policy deny-all");
+    }
+
+    #[test]
+    fn test_notification_hook() {
+        let webhook = Url::parse("https://example.org/notify").unwrap();
+        let ac = AccessController::new(
+            AccessPolicy::DenyAll,
+            [
+                AccessRuleBuilder::new().allow().build(),
+                AccessRuleBuilder::new()
+                    .allow()
+                    .notify(webhook.clone())
+                    .build(),
+            ],
+        );
+        assert!(ac.notification_hook(DecisionSource::Rule(1)).is_none());
+        assert_eq!(
+            ac.notification_hook(DecisionSource::Rule(2)),
+            Some(&HookAction::new(webhook))
+        );
+        assert!(ac
+            .notification_hook(DecisionSource::Policy(AccessPolicy::DenyAll))
+            .is_none());
+    }
+
     #[tokio::test]
     async fn test_allow_policy_rules_should_block() {
         let blocked_address = IotaAddress::new([1; 32]);
@@ -636,8 +733,12 @@ rules:
         use url::Url;
 
         use crate::access_controller::hook::{
-            ExecuteTxOkResponse, SkippableDecision, TEST_ERROR_HEADER, TEST_RESPONSE_HEADER,
+            ExecuteTxOkResponse, HookAction, HookFailurePolicy, HookOptions, SkippableDecision,
+            TEST_ERROR_HEADER, TEST_RESPONSE_HEADER, TEST_UNAVAILABLE_HEADER,
         };
+        use crate::access_controller::predicates::Action;
+        use crate::access_controller::rule::AccessRule;
+        use crate::metrics::GasStationRpcMetrics;
 
         use super::*;
 
@@ -854,5 +955,181 @@ rules:
                 Ok(Decision::Deny)
             ));
         }
+
+        fn hook_rule_with_options(options: HookOptions) -> AccessRule {
+            let mut rule = AccessRuleBuilder::new().build();
+            rule.action = Action::HookAction(
+                HookAction::new(Url::parse("https://example.net").unwrap()).with_options(options),
+            );
+            rule
+        }
+
+        fn get_headers_with_unavailable_hook() -> HeaderMap {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                TEST_UNAVAILABLE_HEADER,
+                HeaderValue::from_static("hook is down"),
+            );
+            headers
+        }
+
+        #[tokio::test]
+        async fn test_hook_failure_policy() {
+            let unavailable_ctx =
+                TransactionContext::default().with_headers(get_headers_with_unavailable_hook());
+            let rejected_ctx = TransactionContext::default().with_headers({
+                let mut headers = HeaderMap::new();
+                headers.insert(TEST_ERROR_HEADER, HeaderValue::from_static("rejected"));
+                headers
+            });
+
+            let hook_rule = hook_rule_with_options(HookOptions::default());
+            let ac = AccessController::new(AccessPolicy::AllowAll, [hook_rule]);
+            assert_eq!(
+                ac.check_access(&unavailable_ctx)
+                    .await
+                    .unwrap_err()
+                    .to_string(),
+                "hook call failed with status 503 Service Unavailable; hook is down"
+            );
+
+            for (policy, access_policy, expected) in [
+                (
+                    HookFailurePolicy::Allow,
+                    AccessPolicy::DenyAll,
+                    Decision::Allow,
+                ),
+                (
+                    HookFailurePolicy::Deny,
+                    AccessPolicy::AllowAll,
+                    Decision::Deny,
+                ),
+                (
+                    HookFailurePolicy::NoDecision,
+                    AccessPolicy::AllowAll,
+                    Decision::Allow,
+                ),
+                (
+                    HookFailurePolicy::NoDecision,
+                    AccessPolicy::DenyAll,
+                    Decision::Deny,
+                ),
+            ] {
+                let hook_rule = hook_rule_with_options(HookOptions {
+                    on_failure: Some(policy),
+                    ..Default::default()
+                });
+                let ac = AccessController::new(access_policy, [hook_rule]);
+                assert_eq!(ac.check_access(&unavailable_ctx).await.unwrap(), expected);
+                // The policy does not apply to requests that the hook rejects.
+                assert!(ac.check_access(&rejected_ctx).await.is_err());
+            }
+        }
+
+        #[tokio::test]
+        async fn test_misconfigured_hook() {
+            let allow_ctx = TransactionContext::default().with_headers(
+                get_headers_with_test_response(SkippableDecision::Allow, None),
+            );
+            let hook_rule = hook_rule_with_options(HookOptions {
+                hmac_secret_env: Some("GAS_STATION_TEST_MISSING_HOOK_SECRET".to_string()),
+                on_failure: Some(HookFailurePolicy::Allow),
+                ..Default::default()
+            });
+            let metrics = GasStationRpcMetrics::new_for_testing();
+            let mut ac = AccessController::new(AccessPolicy::AllowAll, [hook_rule]);
+            ac.set_metrics(metrics.clone());
+
+            assert!(ac.initialize().await.is_err());
+            // The failure policy only applies to unavailable hooks, not to misconfigured ones.
+            let err = ac.check_access(&allow_ctx).await.unwrap_err();
+            assert!(format!("{:#}", err).contains("GAS_STATION_TEST_MISSING_HOOK_SECRET"));
+            assert_eq!(
+                metrics
+                    .num_hook_errors
+                    .with_label_values(&["https://example.net/", "misconfigured"])
+                    .get(),
+                1
+            );
+            assert_eq!(
+                metrics
+                    .num_hook_errors
+                    .with_label_values(&["https://example.net/", "unavailable"])
+                    .get(),
+                0
+            );
+        }
+
+        #[tokio::test]
+        async fn test_hook_retries() {
+            let unavailable_ctx =
+                TransactionContext::default().with_headers(get_headers_with_unavailable_hook());
+            let hook_rule = hook_rule_with_options(HookOptions {
+                max_retries: 2,
+                retry_backoff_ms: 1,
+                ..Default::default()
+            });
+            let metrics = GasStationRpcMetrics::new_for_testing();
+            let mut ac = AccessController::new(AccessPolicy::DenyAll, [hook_rule]);
+            ac.set_metrics(metrics.clone());
+
+            assert!(ac.check_access(&unavailable_ctx).await.is_err());
+            assert_eq!(
+                metrics
+                    .num_hook_errors
+                    .with_label_values(&["https://example.net/", "unavailable"])
+                    .get(),
+                3
+            );
+            assert_eq!(
+                metrics
+                    .hook_request_latency_ms
+                    .with_label_values(&["https://example.net/"])
+                    .get_sample_count(),
+                3
+            );
+        }
+
+        #[tokio::test]
+        async fn test_hook_circuit_breaker() {
+            let unavailable_ctx =
+                TransactionContext::default().with_headers(get_headers_with_unavailable_hook());
+            let allow_ctx = TransactionContext::default().with_headers(
+                get_headers_with_test_response(SkippableDecision::Allow, None),
+            );
+            let mut hook_options = HookOptions {
+                on_failure: Some(HookFailurePolicy::Deny),
+                ..Default::default()
+            };
+            hook_options.circuit_breaker.failure_threshold = 2;
+            let metrics = GasStationRpcMetrics::new_for_testing();
+            let mut ac = AccessController::new(
+                AccessPolicy::DenyAll,
+                [hook_rule_with_options(hook_options)],
+            );
+            ac.set_metrics(metrics.clone());
+
+            assert_eq!(ac.check_access(&allow_ctx).await.unwrap(), Decision::Allow);
+            for _ in 0..2 {
+                assert_eq!(
+                    ac.check_access(&unavailable_ctx).await.unwrap(),
+                    Decision::Deny
+                );
+            }
+            // The hook is not called anymore, although it would allow the transaction now.
+            assert_eq!(ac.check_access(&allow_ctx).await.unwrap(), Decision::Deny);
+            // Clones of the access controller share the circuit breaker.
+            assert_eq!(
+                ac.clone().check_access(&allow_ctx).await.unwrap(),
+                Decision::Deny
+            );
+            assert_eq!(
+                metrics
+                    .num_hook_errors
+                    .with_label_values(&["https://example.net/", "circuit-open"])
+                    .get(),
+                2
+            );
+        }
     }
 }
@@ -21,6 +21,7 @@ mod test {
     use url::Url;
 
     use super::{Action, HookAction};
+    use crate::access_controller::hook::HookOptions;
 
     #[test]
     fn test_deserialize_valid_actions() {
@@ -29,7 +30,18 @@ mod test {
             (r#""deny""#, Action::Deny),
             (
                 r#""http://example.org/""#,
-                Action::HookAction(HookAction(Url::parse("http://example.org/").unwrap())),
+                Action::HookAction(HookAction::new(Url::parse("http://example.org/").unwrap())),
+            ),
+            (
+                r#"{"url":"http://example.org/","headers":{"x-api-key":"key"}}"#,
+                Action::HookAction(
+                    HookAction::new(Url::parse("http://example.org/").unwrap()).with_options(
+                        HookOptions {
+                            headers: [("x-api-key".to_string(), "key".to_string())].into(),
+                            ..Default::default()
+                        },
+                    ),
+                ),
             ),
         ];
 
@@ -56,7 +68,7 @@ mod test {
             (Action::Allow, r#""allow""#),
             (Action::Deny, r#""deny""#),
             (
-                Action::HookAction(HookAction(Url::parse("http://example.org/").unwrap())),
+                Action::HookAction(HookAction::new(Url::parse("http://example.org/").unwrap())),
                 r#""http://example.org/""#,
             ),
         ];
@@ -0,0 +1,94 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+impl ValueClientIdentity {
+    /// Returns true if the identity of the caller is included. Callers without a client
+    /// certificate are never included.
+    pub fn includes(&self, client_identity: Option<&str>) -> bool {
+        let Some(client_identity) = client_identity else {
+            return false;
+        };
+        match self {
+            ValueClientIdentity::All => true,
+            ValueClientIdentity::Single(single) => single == client_identity,
+            ValueClientIdentity::List(list) => list.iter().any(|item| item == client_identity),
+        }
+    }
+}
+
+/// The ValueClientIdentity enum represents a single subject of a client certificate, a list of
+/// subjects or any authenticated client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueClientIdentity {
+    All,
+    Single(String),
+    List(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ValueClientIdentityRepr {
+    Single(String),
+    List(Vec<String>),
+}
+
+impl Serialize for ValueClientIdentity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        match self {
+            ValueClientIdentity::All => serializer.serialize_str("*"),
+            ValueClientIdentity::Single(identity) => identity.serialize(serializer),
+            ValueClientIdentity::List(identities) => identities.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueClientIdentity {
+    fn deserialize<D>(deserializer: D) -> Result<ValueClientIdentity, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        Ok(match ValueClientIdentityRepr::deserialize(deserializer)? {
+            ValueClientIdentityRepr::Single(identity) if identity == "*" => {
+                ValueClientIdentity::All
+            }
+            ValueClientIdentityRepr::Single(identity) => ValueClientIdentity::Single(identity),
+            ValueClientIdentityRepr::List(identities) => ValueClientIdentity::List(identities),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ValueClientIdentity;
+
+    #[test]
+    fn test_includes_client_identity() {
+        let single = ValueClientIdentity::Single("CN=client-1".to_string());
+        assert!(single.includes(Some("CN=client-1")));
+        assert!(!single.includes(Some("CN=client-2")));
+        assert!(!single.includes(None));
+
+        assert!(ValueClientIdentity::All.includes(Some("CN=client-2")));
+        assert!(!ValueClientIdentity::All.includes(None));
+    }
+
+    #[test]
+    fn test_serde_client_identity() {
+        let all: ValueClientIdentity = serde_yaml::from_str("\"*\"").unwrap();
+        assert_eq!(all, ValueClientIdentity::All);
+
+        let list: ValueClientIdentity =
+            serde_yaml::from_str("- CN=client-1\n- CN=client-2\n").unwrap();
+        assert_eq!(
+            list,
+            ValueClientIdentity::List(vec!["CN=client-1".to_string(), "CN=client-2".to_string()])
+        );
+        let data = serde_yaml::to_string(&list).unwrap();
+        assert_eq!(list, serde_yaml::from_str(&data).unwrap());
+    }
+}
@@ -3,12 +3,14 @@
 
 mod action;
 mod aggregate;
+mod client_identity;
 mod iota_address;
 mod number;
 mod rego_expression;
 mod source;
 pub use action::Action;
 pub use aggregate::{LimitBy, ValueAggregate};
+pub use client_identity::ValueClientIdentity;
 pub use iota_address::ValueIotaAddress;
 pub use number::ValueNumber;
 pub use rego_expression::RegoExpression;
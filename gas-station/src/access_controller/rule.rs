@@ -18,7 +18,10 @@ use url::Url;
 
 use super::{
     hook::HookAction,
-    predicates::{Action, LimitBy, RegoExpression, ValueAggregate, ValueIotaAddress, ValueNumber},
+    predicates::{
+        Action, LimitBy, RegoExpression, ValueAggregate, ValueClientIdentity, ValueIotaAddress,
+        ValueNumber,
+    },
 };
 use crate::{
     rpc::rpc_types::ExecuteTransactionRequestType,
@@ -74,7 +77,7 @@ impl AccessRuleBuilder {
 
     /// Sets the action of the AccessRule to call hook.
     pub fn hook(mut self, url: Url) -> Self {
-        self.rule.action = Action::HookAction(HookAction(url));
+        self.rule.action = Action::HookAction(HookAction::new(url));
         self
     }
 
@@ -118,6 +121,17 @@ impl AccessRuleBuilder {
         self.rule.rego_expression = Some(rego_expression);
         self
     }
+
+    pub fn client_identity(mut self, client_identity: ValueClientIdentity) -> Self {
+        self.rule.client_identity = Some(client_identity);
+        self
+    }
+
+    /// Sets the webhook that is sent the outcome of the transactions allowed by the rule.
+    pub fn notify(mut self, url: Url) -> Self {
+        self.rule.notify = Some(HookAction::new(url));
+        self
+    }
 }
 
 #[skip_serializing_none]
@@ -131,8 +145,11 @@ pub struct AccessRule {
     pub ptb_command_count: Option<ValueNumber<usize>>,
     pub gas_usage: Option<ValueAggregate>,
     pub rego_expression: Option<RegoExpression>,
+    pub client_identity: Option<ValueClientIdentity>,
 
     pub action: Action,
+    /// Optional webhook that is sent the outcome of the transactions allowed by this rule.
+    pub notify: Option<HookAction>,
 }
 
 #[derive(Clone, Default)]
@@ -148,6 +165,12 @@ impl AccessRule {
         if let Some(rego_expression) = self.rego_expression.as_mut() {
             rego_expression.reload_source().await?;
         }
+        if let Action::HookAction(hook) = &self.action {
+            hook.initialize()?;
+        }
+        if let Some(notify) = &self.notify {
+            notify.initialize()?;
+        }
         Ok(())
     }
 
@@ -166,6 +189,12 @@ impl AccessRule {
             && self
                 .move_call_package_address.as_ref().map(|address| address.includes_any(&data.move_call_package_addresses)).unwrap_or(true)
             && self.ptb_command_count_matches_or_not_applicable(data)
+            // Client Identity
+            && self
+                .client_identity
+                .as_ref()
+                .map(|identity| identity.includes(data.client_identity.as_deref()))
+                .unwrap_or(true)
             // Rego expression
             && self.match_rego_expression(data)?)
     }
@@ -272,12 +301,15 @@ impl AccessRule {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RegoInputPayload {
     pub transaction_data: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_identity: Option<String>,
 }
 
 impl RegoInputPayload {
     pub fn from_context(ctx: &TransactionContext) -> Self {
         Self {
             transaction_data: ctx.transaction_data.clone(),
+            client_identity: ctx.client_identity.clone(),
         }
     }
 }
@@ -288,7 +320,10 @@ pub struct TransactionContext {
     pub transaction_digest: TransactionDigest,
     pub sender_address: IotaAddress,
     pub transaction_budget: u64,
+    pub gas_price: u64,
     pub move_call_package_addresses: Vec<IotaAddress>,
+    /// Move calls of the transaction as `<package>::<module>::<function>`.
+    pub move_call_targets: Vec<String>,
     pub ptb_command_count: Option<usize>,
     pub transaction_data: Value,
 
@@ -298,6 +333,8 @@ pub struct TransactionContext {
     pub user_sig: Base64,
     pub request_type: Option<ExecuteTransactionRequestType>,
     pub headers: HeaderMap,
+    /// Subject of the client certificate, if the caller authenticated with mTLS.
+    pub client_identity: Option<String>,
 }
 
 #[cfg(test)]
@@ -306,7 +343,9 @@ impl Default for TransactionContext {
         Self {
             sender_address: IotaAddress::default(),
             transaction_budget: 0,
+            gas_price: 0,
             move_call_package_addresses: vec![],
+            move_call_targets: vec![],
             ptb_command_count: None,
             stats_tracker: crate::test_env::mocked_stats_tracker(),
             transaction_digest: TransactionDigest::default(),
@@ -318,6 +357,7 @@ impl Default for TransactionContext {
                 .expect("empty string should be valid base64"),
             request_type: None,
             headers: HeaderMap::default(),
+            client_identity: None,
         }
     }
 }
@@ -347,7 +387,9 @@ impl TransactionContext {
             transaction_digest: transaction_data.digest(),
             sender_address: transaction_data.sender().clone(),
             transaction_budget: transaction_data.gas_budget(),
+            gas_price: transaction_data.gas_price(),
             move_call_package_addresses: get_move_call_package_addresses(transaction_data),
+            move_call_targets: get_move_call_targets(transaction_data),
             ptb_command_count,
             stats_tracker,
             transaction_data: transaction_value,
@@ -356,6 +398,7 @@ impl TransactionContext {
             user_sig,
             request_type,
             headers,
+            client_identity: None,
         }
     }
 
@@ -377,6 +420,16 @@ impl TransactionContext {
         self
     }
 
+    pub fn with_gas_price(mut self, gas_price: u64) -> Self {
+        self.gas_price = gas_price;
+        self
+    }
+
+    pub fn with_move_call_targets(mut self, move_call_targets: Vec<String>) -> Self {
+        self.move_call_targets = move_call_targets;
+        self
+    }
+
     pub fn with_ptb_command_count(mut self, ptb_count: usize) -> Self {
         self.ptb_command_count = Some(ptb_count);
         self
@@ -416,6 +469,11 @@ impl TransactionContext {
         self.headers = headers;
         self
     }
+
+    pub fn with_client_identity(mut self, client_identity: String) -> Self {
+        self.client_identity = Some(client_identity);
+        self
+    }
 }
 
 fn get_move_call_package_addresses(transaction_data: &TransactionData) -> Vec<IotaAddress> {
@@ -427,6 +485,15 @@ fn get_move_call_package_addresses(transaction_data: &TransactionData) -> Vec<Io
         .collect()
 }
 
+fn get_move_call_targets(transaction_data: &TransactionData) -> Vec<String> {
+    let TransactionData::V1(data_v1) = transaction_data;
+    data_v1
+        .move_calls()
+        .iter()
+        .map(|(package, module, function)| format!("{}::{}::{}", package, module, function))
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
 
@@ -444,7 +511,7 @@ mod test {
         access_controller::{
             predicates::{
                 Action, LimitBy, Location, RegoExpression, SourceWithData, ValueAggregate,
-                ValueIotaAddress, ValueNumber,
+                ValueClientIdentity, ValueIotaAddress, ValueNumber,
             },
             rule::{AccessRule, AccessRuleBuilder, TransactionContext},
         },
@@ -554,6 +621,23 @@ mod test {
             .unwrap());
     }
 
+    #[tokio::test]
+    async fn test_constraint_client_identity() {
+        let rule = AccessRuleBuilder::new()
+            .client_identity(ValueClientIdentity::Single("CN=client-1".to_string()))
+            .allow()
+            .build();
+
+        let matched_data =
+            TransactionContext::default().with_client_identity("CN=client-1".to_string());
+        let unmatched_data =
+            TransactionContext::default().with_client_identity("CN=client-2".to_string());
+
+        assert!(rule.matches(&matched_data).await.unwrap());
+        assert!(!rule.matches(&unmatched_data).await.unwrap());
+        assert!(!rule.matches(&TransactionContext::default()).await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_constraint_mix_ups_sender_package_address() {
         let sender_address = IotaAddress::new([1; 32]);
@@ -0,0 +1,377 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::tx_signer::TxSigner;
+use crate::types::ReservationID;
+use anyhow::{anyhow, bail, Context};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::hash::{HashFunction, Sha256};
+use iota_types::base_types::IotaAddress;
+use iota_types::crypto::PublicKey;
+use iota_types::digests::TransactionDigest;
+use iota_types::signature::GenericSignature;
+use iota_types::transaction::{TransactionData, TransactionDataAPI};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::error;
+
+/// Previous hash of the first entry of an audit log.
+const GENESIS_HASH: [u8; 32] = [0; 32];
+
+tokio::task_local! {
+    static SIGNING_CONTEXT: SigningContext;
+}
+
+/// Why a transaction is signed, recorded along with its signature.
+#[derive(Debug, Clone, Default)]
+pub struct SigningContext {
+    pub reservation_id: Option<ReservationID>,
+    /// The access rule or policy that allowed the transaction.
+    pub allowed_by: Option<String>,
+}
+
+/// Runs `f` with `context` attached to the signatures it produces. Signatures produced outside
+/// of a context, like those of the coin management of the station, are recorded without one.
+pub async fn with_signing_context<F: Future>(context: SigningContext, f: F) -> F::Output {
+    SIGNING_CONTEXT.scope(context, f).await
+}
+
+/// An entry of the signing audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp_ms: i64,
+    pub digest: TransactionDigest,
+    pub sender: IotaAddress,
+    pub sponsor: IotaAddress,
+    pub gas_budget: u64,
+    pub gas_price: u64,
+    pub reservation_id: Option<ReservationID>,
+    pub allowed_by: Option<String>,
+    /// Hash of the previous entry, hex encoded.
+    pub prev_hash: String,
+}
+
+/// A line of the audit log file.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct AuditRecord {
+    #[serde(flatten)]
+    entry: AuditEntry,
+    /// SHA-256 of the BCS encoded entry, hex encoded.
+    hash: String,
+}
+
+impl AuditEntry {
+    fn hash(&self) -> String {
+        let bytes = bcs::to_bytes(self).expect("Audit entries are always serializable");
+        Hex::encode(Sha256::digest(bytes).digest)
+    }
+}
+
+/// The result of verifying an audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditLogSummary {
+    pub entries: u64,
+    /// Hash of the last entry, hex encoded.
+    pub last_hash: String,
+}
+
+/// Verifies that every entry of the audit log at `path` is chained to the previous one, so that
+/// no entry was changed, removed or inserted, apart from entries removed at the end.
+pub fn verify_audit_log(path: &Path) -> anyhow::Result<AuditLogSummary> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open the audit log {}", path.display()))?;
+    let mut summary = AuditLogSummary {
+        entries: 0,
+        last_hash: Hex::encode(GENESIS_HASH),
+    };
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line_number = index + 1;
+        let line = line?;
+        let record: AuditRecord = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid entry at line {}", line_number))?;
+        if record.entry.seq != summary.entries {
+            bail!(
+                "Entry {} at line {} is out of sequence, expected entry {}",
+                record.entry.seq,
+                line_number,
+                summary.entries
+            );
+        }
+        if record.entry.prev_hash != summary.last_hash {
+            bail!(
+                "Entry {} at line {} is not chained to the previous entry",
+                record.entry.seq,
+                line_number
+            );
+        }
+        let hash = record.entry.hash();
+        if hash != record.hash {
+            bail!(
+                "Entry {} at line {} was modified, its hash doesn't match",
+                record.entry.seq,
+                line_number
+            );
+        }
+        summary.entries += 1;
+        summary.last_hash = hash;
+    }
+    Ok(summary)
+}
+
+/// Append-only log of the transactions signed by the station. Every entry includes the hash of
+/// the previous one, so that changes to the log can be detected with `verify_audit_log`.
+pub struct SigningAuditLog {
+    path: PathBuf,
+    state: Mutex<AuditLogState>,
+}
+
+struct AuditLogState {
+    file: tokio::fs::File,
+    len: u64,
+    summary: AuditLogSummary,
+}
+
+impl SigningAuditLog {
+    /// Opens the audit log at `path`, or creates it. The existing entries are verified first, so
+    /// that new entries are never chained to a log that was tampered with.
+    pub fn open(path: &Path) -> anyhow::Result<Arc<Self>> {
+        let summary = if path.exists() {
+            verify_audit_log(path)?
+        } else {
+            AuditLogSummary {
+                entries: 0,
+                last_hash: Hex::encode(GENESIS_HASH),
+            }
+        };
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("Failed to open the audit log {}", path.display()))?;
+        let len = file.metadata()?.len();
+        Ok(Arc::new(Self {
+            path: path.to_path_buf(),
+            state: Mutex::new(AuditLogState {
+                file: tokio::fs::File::from_std(file),
+                len,
+                summary,
+            }),
+        }))
+    }
+
+    /// Records the signature of `tx_data`. The entry is flushed to disk before returning.
+    pub async fn append(
+        &self,
+        tx_data: &TransactionData,
+        context: SigningContext,
+    ) -> anyhow::Result<AuditEntry> {
+        let mut state = self.state.lock().await;
+        let entry = AuditEntry {
+            seq: state.summary.entries,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            digest: tx_data.digest(),
+            sender: tx_data.sender(),
+            sponsor: tx_data.gas_data().owner,
+            gas_budget: tx_data.gas_data().budget,
+            gas_price: tx_data.gas_data().price,
+            reservation_id: context.reservation_id,
+            allowed_by: context.allowed_by,
+            prev_hash: state.summary.last_hash.clone(),
+        };
+        let hash = entry.hash();
+        let mut line = serde_json::to_string(&AuditRecord {
+            entry: entry.clone(),
+            hash: hash.clone(),
+        })?;
+        line.push('\n');
+        if let Err(err) = Self::write_line(&mut state.file, &line).await {
+            // Remove a partially written entry, which would break the chain.
+            let len = state.len;
+            if let Err(err) = state.file.set_len(len).await {
+                error!(
+                    "Failed to truncate the audit log {}: {:?}",
+                    self.path.display(),
+                    err
+                );
+            }
+            return Err(err.context(format!(
+                "Failed to write to the audit log {}",
+                self.path.display()
+            )));
+        }
+        state.len += line.len() as u64;
+        state.summary.entries += 1;
+        state.summary.last_hash = hash;
+        Ok(entry)
+    }
+
+    async fn write_line(file: &mut tokio::fs::File, line: &str) -> anyhow::Result<()> {
+        file.write_all(line.as_bytes()).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+}
+
+/// Records every signature of `inner` in the audit log. A signature that cannot be recorded is
+/// not returned, so that no transaction is submitted without its entry.
+pub struct AuditedTxSigner {
+    inner: Arc<dyn TxSigner>,
+    audit_log: Arc<SigningAuditLog>,
+}
+
+impl AuditedTxSigner {
+    pub fn new(inner: Arc<dyn TxSigner>, audit_log: Arc<SigningAuditLog>) -> Arc<Self> {
+        Arc::new(Self { inner, audit_log })
+    }
+}
+
+#[async_trait::async_trait]
+impl TxSigner for AuditedTxSigner {
+    async fn sign_transaction(
+        &self,
+        tx_data: &TransactionData,
+    ) -> anyhow::Result<GenericSignature> {
+        let signature = self.inner.sign_transaction(tx_data).await?;
+        let context = SIGNING_CONTEXT
+            .try_with(|context| context.clone())
+            .unwrap_or_default();
+        self.audit_log
+            .append(tx_data, context)
+            .await
+            .map_err(|err| anyhow!("Failed to record the signature: {:?}", err))?;
+        Ok(signature)
+    }
+
+    fn get_address(&self) -> IotaAddress {
+        self.inner.get_address()
+    }
+
+    fn is_valid_address(&self, address: &IotaAddress) -> bool {
+        self.inner.is_valid_address(address)
+    }
+
+    fn get_public_key(&self) -> Option<PublicKey> {
+        self.inner.get_public_key()
+    }
+
+    async fn check_health(&self) -> anyhow::Result<()> {
+        self.inner.check_health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_signer::TestTxSigner;
+    use iota_types::crypto::{get_key_pair, AccountKeyPair, IotaKeyPair};
+    use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use iota_types::transaction::TransactionKind;
+
+    fn new_tx_data(sponsor: IotaAddress, budget: u64) -> TransactionData {
+        TransactionData::new_with_gas_coins_allow_sponsor(
+            TransactionKind::programmable(ProgrammableTransactionBuilder::new().finish()),
+            IotaAddress::random_for_testing_only(),
+            vec![],
+            budget,
+            1000,
+            sponsor,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_audited_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let audit_log = SigningAuditLog::open(&path).unwrap();
+        let (_, keypair): (_, AccountKeyPair) = get_key_pair();
+        let signer = AuditedTxSigner::new(
+            TestTxSigner::new(IotaKeyPair::Ed25519(keypair)),
+            audit_log.clone(),
+        );
+        let sponsor = signer.get_address();
+
+        let tx_data = new_tx_data(sponsor, 100);
+        signer.sign_transaction(&tx_data).await.unwrap();
+        let context = SigningContext {
+            reservation_id: Some(7),
+            allowed_by: Some("rule #1".to_string()),
+        };
+        with_signing_context(context, signer.sign_transaction(&new_tx_data(sponsor, 200)))
+            .await
+            .unwrap();
+
+        let summary = verify_audit_log(&path).unwrap();
+        assert_eq!(summary.entries, 2);
+        let content = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<AuditRecord> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records[0].entry.digest, tx_data.digest());
+        assert_eq!(records[0].entry.sender, tx_data.sender());
+        assert_eq!(records[0].entry.sponsor, sponsor);
+        assert_eq!(records[0].entry.gas_budget, 100);
+        assert_eq!(records[0].entry.reservation_id, None);
+        assert_eq!(records[1].entry.reservation_id, Some(7));
+        assert_eq!(records[1].entry.allowed_by.as_deref(), Some("rule #1"));
+        assert_eq!(records[1].entry.prev_hash, records[0].hash);
+
+        // Reopening the log continues the chain.
+        drop(signer);
+        drop(audit_log);
+        let audit_log = SigningAuditLog::open(&path).unwrap();
+        let entry = audit_log
+            .append(&new_tx_data(sponsor, 300), SigningContext::default())
+            .await
+            .unwrap();
+        assert_eq!(entry.seq, 2);
+        assert_eq!(entry.prev_hash, summary.last_hash);
+        assert_eq!(verify_audit_log(&path).unwrap().entries, 3);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_audit_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let audit_log = SigningAuditLog::open(&path).unwrap();
+        let sponsor = IotaAddress::random_for_testing_only();
+        for budget in [100, 200, 300] {
+            audit_log
+                .append(&new_tx_data(sponsor, budget), SigningContext::default())
+                .await
+                .unwrap();
+        }
+        drop(audit_log);
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+
+        // A modified entry.
+        std::fs::write(
+            &path,
+            content.replace("\"gas-budget\":200", "\"gas-budget\":20"),
+        )
+        .unwrap();
+        let err = verify_audit_log(&path).unwrap_err();
+        assert!(err.to_string().contains("was modified"));
+        assert!(SigningAuditLog::open(&path).is_err());
+
+        // A removed entry.
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let err = verify_audit_log(&path).unwrap_err();
+        assert!(err.to_string().contains("out of sequence"));
+
+        // Reordered entries.
+        std::fs::write(&path, format!("{}\n{}\n", lines[1], lines[0])).unwrap();
+        assert!(verify_audit_log(&path).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::{CoinSelectionStrategy, GasStationStorageConfig};
+use crate::metrics::StorageMetrics;
+use crate::storage::connect_storage;
+use crate::types::GasCoin;
+use iota_types::base_types::{IotaAddress, ObjectID, SequenceNumber};
+use iota_types::digests::ObjectDigest;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Seed of the random pool and budgets, so that every strategy runs the same workload.
+const BENCHMARK_SEED: u64 = 42;
+
+/// Replays the same sequence of reservations against a fresh pool for every coin selection
+/// strategy and reports how many coins each reservation took.
+/// Every reservation is executed right away: the reserved coins are merged into the first one,
+/// which is returned to the pool after paying for a random fraction of the budget.
+/// Each strategy uses a new random sponsor address, so the Redis instance can be shared.
+pub async fn run_coin_selection_benchmark(
+    redis_url: String,
+    num_coins: usize,
+    num_reservations: usize,
+    max_budget: u64,
+) {
+    for strategy in CoinSelectionStrategy::ALL {
+        let storage = connect_storage(
+            &GasStationStorageConfig::Redis {
+                redis_url: redis_url.clone(),
+                coin_selection_strategy: strategy,
+            },
+            IotaAddress::random_for_testing_only(),
+            StorageMetrics::new(&prometheus::Registry::new()),
+        )
+        .await;
+        let mut rng = StdRng::seed_from_u64(BENCHMARK_SEED);
+        let coins: Vec<_> = (0..num_coins)
+            .map(|_| GasCoin {
+                object_ref: (
+                    ObjectID::random(),
+                    SequenceNumber::from_u64(1),
+                    ObjectDigest::random(),
+                ),
+                balance: rng.gen_range(1..=max_budget),
+            })
+            .collect();
+        for chunk in coins.chunks(5000) {
+            storage.add_new_coins(chunk.to_vec()).await.unwrap();
+        }
+
+        let mut num_successes = 0;
+        let mut num_failures = 0;
+        let mut num_reserved_coins = 0;
+        let mut max_reserved_coins = 0;
+        for _ in 0..num_reservations {
+            let budget = rng.gen_range(1..=max_budget);
+            let gas_used = rng.gen_range(0..=budget);
+            let (reservation_id, reserved_coins) =
+                match storage.reserve_gas_coins(budget, 60_000).await {
+                    Ok(reservation) => reservation,
+                    Err(_) => {
+                        num_failures += 1;
+                        continue;
+                    }
+                };
+            num_successes += 1;
+            num_reserved_coins += reserved_coins.len();
+            max_reserved_coins = max_reserved_coins.max(reserved_coins.len());
+
+            let payment: Vec<_> = reserved_coins.iter().map(|c| c.object_ref.0).collect();
+            storage
+                .ready_for_execution(reservation_id, &payment, 60_000)
+                .await
+                .unwrap();
+            let total_balance: u64 = reserved_coins.iter().map(|c| c.balance).sum();
+            let (object_id, version, _) = reserved_coins[0].object_ref;
+            let updated_coin = GasCoin {
+                object_ref: (
+                    object_id,
+                    SequenceNumber::from_u64(version.value() + 1),
+                    ObjectDigest::random(),
+                ),
+                balance: total_balance - gas_used,
+            };
+            storage
+                .complete_execution(reservation_id, vec![updated_coin])
+                .await
+                .unwrap();
+        }
+
+        println!("Strategy: {}", strategy.as_str());
+        println!(" - Successful reservations: {}", num_successes);
+        println!(" - Failed reservations: {}", num_failures);
+        println!(
+            " - Average coins per reservation: {:.2}",
+            num_reserved_coins as f64 / num_successes.max(1) as f64
+        );
+        println!(" - Max coins per reservation: {}", max_reserved_coins);
+        println!(
+            " - Coins left in the pool: {}",
+            storage.get_available_coin_count().await.unwrap()
+        );
+    }
+}
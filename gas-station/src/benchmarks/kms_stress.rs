@@ -1,7 +1,9 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::tx_signer::{SidecarTxSigner, TxSigner};
+use crate::config::SidecarSignerConfig;
+use crate::sidecar_signer::SidecarTxSigner;
+use crate::tx_signer::TxSigner;
 use iota_types::base_types::{random_object_ref, IotaAddress};
 use iota_types::transaction::{ProgrammableTransaction, TransactionData, TransactionKind};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -9,7 +11,9 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 pub async fn run_kms_stress_test(kms_url: String, num_tasks: usize) {
-    let signer = SidecarTxSigner::new(kms_url).await;
+    let signer = SidecarTxSigner::new(SidecarSignerConfig::new(kms_url))
+        .await
+        .unwrap();
     let test_tx_data = TransactionData::new(
         TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
             inputs: vec![],
@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod coin_selection;
 pub mod kms_stress;
 
 use crate::rpc::client::GasStationRpcClient;
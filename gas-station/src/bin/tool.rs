@@ -4,15 +4,35 @@
 
 use clap::*;
 use iota_config::Config;
+use iota_gas_station::audit_log::{verify_audit_log, AuditedTxSigner, SigningAuditLog};
+use iota_gas_station::benchmarks::coin_selection::run_coin_selection_benchmark;
 use iota_gas_station::benchmarks::kms_stress::run_kms_stress_test;
 use iota_gas_station::benchmarks::BenchmarkMode;
-use iota_gas_station::config::{GasStationConfig, GasStationStorageConfig, TxSignerConfig};
+use iota_gas_station::config::{
+    GasStationConfig, GasStationStorageConfig, SidecarSignerConfig, TxSignerConfig,
+};
+use iota_gas_station::iota_client::IotaClient;
+use iota_gas_station::keystore::{load_keypair, read_passphrase, EncryptedKeyFile};
+use iota_gas_station::metrics::StorageMetrics;
+use iota_gas_station::pool_drainer::{cancel_drain, drain_pool};
+use iota_gas_station::pool_reconciler::{max_adopted_balance, reconcile_pool};
 use iota_gas_station::rpc::client::GasStationRpcClient;
+use iota_gas_station::rpc::openapi::openapi_spec;
+use iota_gas_station::storage::{connect_storage, migrate_storage, STORAGE_VERSION};
+use iota_gas_station::tx_signer::TxSigner;
+use iota_gas_station::KEYSTORE_PASSPHRASE_ENV_NAME;
 use iota_sdk::{IOTA_DEVNET_URL, IOTA_MAINNET_URL, IOTA_TESTNET_URL};
 use iota_types::base_types::IotaAddress;
 use iota_types::crypto::{get_account_key_pair, EncodeDecodeBase64, IotaKeyPair};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// File name of the key file written by `generate-sample-config`, next to the config.
+const DEFAULT_KEYSTORE_FILE: &str = "sponsor.key";
+/// Directory the config is mounted to in the docker compose environment.
+const DOCKER_CONFIG_DIR: &str = "/app";
 
 #[derive(Parser)]
 #[command(
@@ -42,6 +62,32 @@ pub enum ToolCommand {
         #[arg(long, help = "Benchmark mode.", default_value = "reserve-only")]
         benchmark_mode: BenchmarkMode,
     },
+    /// Compare the coin selection strategies by running the same reservations against a fresh
+    /// pool for each of them. Every strategy uses a new random sponsor address in the Redis
+    /// instance.
+    #[clap(name = "benchmark-coin-selection")]
+    BenchmarkCoinSelection {
+        #[arg(
+            long,
+            help = "Redis connection URL",
+            default_value = "redis://127.0.0.1:6379"
+        )]
+        redis_url: String,
+        #[arg(long, help = "Number of coins in the pool", default_value_t = 10_000)]
+        num_coins: usize,
+        #[arg(
+            long,
+            help = "Number of reservations to run for each strategy",
+            default_value_t = 10_000
+        )]
+        num_reservations: usize,
+        #[arg(
+            long,
+            help = "Upper bound of the coin balances and the reserved budgets",
+            default_value_t = 100_000_000
+        )]
+        max_budget: u64,
+    },
     #[clap(name = "stress-kms")]
     StressKMS {
         #[arg(long, help = "Full URL to the KMS signer")]
@@ -74,6 +120,108 @@ pub enum ToolCommand {
         network: Network,
         #[arg(long, help = "Custom gas station address (optional)")]
         gas_station_address: Option<String>,
+        #[arg(
+            long,
+            help = "Path of the encrypted key file the new key is written to, sponsor.key next to the config by default. The passphrase is read from GAS_STATION_KEYSTORE_PASSPHRASE"
+        )]
+        keystore_path: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Write the new private key to the config in clear instead of a key file. Only meant for local testing",
+            conflicts_with = "keystore_path"
+        )]
+        plaintext_key: bool,
+    },
+    /// Write the OpenAPI specification of the gas station RPC server to the specified path.
+    #[clap(name = "generate-openapi")]
+    GenerateOpenapi {
+        #[arg(long, help = "Path to write the OpenAPI document to")]
+        output_path: PathBuf,
+    },
+    /// Compare the gas coins in the pool with the coins owned by the sponsor on chain and fix the
+    /// differences.
+    #[clap(name = "reconcile")]
+    Reconcile {
+        #[arg(long, help = "Path to the config file of the gas station")]
+        config_path: PathBuf,
+        #[arg(long, help = "Only report the differences without fixing them")]
+        dry_run: bool,
+    },
+    /// Convert the storage of the sponsor written by older versions of the gas station. All
+    /// instances of older versions must be stopped first.
+    #[clap(name = "migrate-storage")]
+    MigrateStorage {
+        #[arg(long, help = "Path to the config file of the gas station")]
+        config_path: PathBuf,
+    },
+    /// Retire the sponsor account: stop new reservations, wait for the outstanding ones, then
+    /// merge all coins of the sponsor and transfer them to the target address. With --cancel,
+    /// end the drain mode of an interrupted drain instead.
+    #[clap(name = "drain")]
+    Drain {
+        #[arg(long, help = "Path to the config file of the gas station")]
+        config_path: PathBuf,
+        #[arg(
+            long,
+            help = "Address that receives the funds of the sponsor",
+            required_unless_present = "cancel"
+        )]
+        target_address: Option<String>,
+        #[arg(
+            long,
+            help = "End the drain mode, so that gas coins are reserved again",
+            conflicts_with = "target_address"
+        )]
+        cancel: bool,
+        #[arg(
+            long,
+            help = "How long to wait for outstanding reservations, in seconds",
+            default_value = "600"
+        )]
+        wait_timeout_sec: u64,
+    },
+    /// Check that the entries of a signing audit log are intact and chained to each other.
+    #[clap(name = "verify-audit-log")]
+    VerifyAuditLog {
+        #[arg(long, help = "Path to the audit log file")]
+        path: PathBuf,
+    },
+    /// Create a passphrase-encrypted key file for the keystore signer. A new key is generated,
+    /// unless one is given, or taken from an IOTA CLI keystore or the local signer of a config
+    /// file. The passphrase is read from GAS_STATION_KEYSTORE_PASSPHRASE or from a file
+    /// descriptor.
+    #[clap(name = "create-keystore")]
+    CreateKeystore {
+        #[arg(long, help = "Path to write the key file to")]
+        output_path: PathBuf,
+        #[arg(
+            long,
+            short,
+            help = "bech32 or base64 encoded key to convert",
+            conflicts_with_all = ["iota_keystore", "config_path"]
+        )]
+        key: Option<String>,
+        #[arg(
+            long,
+            help = "IOTA CLI keystore to convert a key from",
+            conflicts_with = "config_path"
+        )]
+        iota_keystore: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Address of the key to convert, required when the IOTA CLI keystore holds several keys",
+            requires = "iota_keystore"
+        )]
+        address: Option<String>,
+        #[arg(
+            long,
+            help = "Config file whose local signer is converted. The config is rewritten to use the key file"
+        )]
+        config_path: Option<PathBuf>,
+        #[arg(long, help = "File descriptor to read the passphrase from")]
+        passphrase_fd: Option<i32>,
+        #[arg(long, short, help = "Overwrite the existing key file")]
+        force: bool,
     },
     /// Converts the Bech32 key to Base64 encoded
     #[clap(name = "convert-key")]
@@ -105,6 +253,12 @@ pub enum CliCommand {
         #[clap(long, help = "Full URL of the station RPC server")]
         station_rpc_url: String,
     },
+    /// Ask the station to add new coins of the sponsor address to the pool right away, e.g.
+    /// after topping it up.
+    RefreshCoinPool {
+        #[clap(long, help = "Full URL of the station RPC server")]
+        station_rpc_url: String,
+    },
 }
 
 impl ToolCommand {
@@ -124,6 +278,15 @@ impl ToolCommand {
                     .run_benchmark(gas_station_url, reserve_duration_sec, num_clients)
                     .await
             }
+            ToolCommand::BenchmarkCoinSelection {
+                redis_url,
+                num_coins,
+                num_reservations,
+                max_budget,
+            } => {
+                run_coin_selection_benchmark(redis_url, num_coins, num_reservations, max_budget)
+                    .await;
+            }
             ToolCommand::StressKMS { kms_url, num_tasks } => {
                 run_kms_stress_test(kms_url, num_tasks).await;
             }
@@ -134,17 +297,40 @@ impl ToolCommand {
                 force,
                 network,
                 gas_station_address,
+                keystore_path,
+                plaintext_key,
             } => {
+                if config_path.exists() && !force {
+                    eprintln!("Config file already exists. Use --force (-f) to overwrite.");
+                    std::process::exit(1);
+                }
                 let mut new_iota_address: Option<IotaAddress> = None;
                 let signer_config = if with_sidecar_signer {
-                    TxSignerConfig::Sidecar {
-                        sidecar_url: "http://localhost:3000".to_string(),
-                    }
+                    TxSignerConfig::Sidecar(SidecarSignerConfig::new(
+                        "http://localhost:3000".to_string(),
+                    ))
                 } else {
                     let (iota_address, keypair) = get_account_key_pair();
                     new_iota_address = Some(iota_address);
-                    TxSignerConfig::Local {
-                        keypair: keypair.into(),
+                    let keypair: IotaKeyPair = keypair.into();
+                    if plaintext_key {
+                        eprintln!("Warning: the private key is written to the config in clear.");
+                        TxSignerConfig::Local { keypair }
+                    } else {
+                        let keystore_path = keystore_path
+                            .unwrap_or_else(|| config_path.with_file_name(DEFAULT_KEYSTORE_FILE));
+                        let mut path = write_key_file(&keypair, &keystore_path, None, force);
+                        if docker_compose {
+                            // The key file is mounted next to the config in the container.
+                            path = Path::new(DOCKER_CONFIG_DIR).join(path.file_name().unwrap());
+                        }
+                        println!("Wrote the key to {}", keystore_path.display());
+                        TxSignerConfig::Keystore {
+                            path,
+                            address: None,
+                            passphrase_env: KEYSTORE_PASSPHRASE_ENV_NAME.to_string(),
+                            passphrase_fd: None,
+                        }
                     }
                 };
                 let redis_url = if docker_compose {
@@ -170,15 +356,14 @@ impl ToolCommand {
 
                 let config = GasStationConfig {
                     signer_config,
-                    storage_config: GasStationStorageConfig::Redis { redis_url },
+                    storage_config: GasStationStorageConfig::Redis {
+                        redis_url,
+                        coin_selection_strategy: Default::default(),
+                    },
                     fullnode_url,
                     gas_station_address: custom_gas_station_address,
                     ..Default::default()
                 };
-                if config_path.exists() && !force {
-                    eprintln!("Config file already exists. Use --force (-f) to overwrite.");
-                    std::process::exit(1);
-                }
                 if let Some(iota_address) = new_iota_address {
                     println!(
                         "Generated a new IOTA address. If you plan to use it, please make sure it has enough funds: '{}'",
@@ -216,7 +401,219 @@ impl ToolCommand {
                     let version = station_client.version().await.unwrap();
                     println!("Station server version: {}", version);
                 }
+                CliCommand::RefreshCoinPool { station_rpc_url } => {
+                    let station_client = GasStationRpcClient::new(station_rpc_url);
+                    if let Err(e) = station_client.refresh_coin_pool().await {
+                        eprintln!("Failed to refresh the coin pool: {}", e);
+                        std::process::exit(1);
+                    }
+                    println!("Coin pool refresh started");
+                }
             },
+            ToolCommand::GenerateOpenapi { output_path } => {
+                let spec = serde_json::to_string_pretty(&openapi_spec()).unwrap();
+                std::fs::write(&output_path, spec).unwrap();
+                println!("OpenAPI specification written to {}", output_path.display());
+            }
+            ToolCommand::Reconcile {
+                config_path,
+                dry_run,
+            } => {
+                let config =
+                    GasStationConfig::load(&config_path).expect("Failed to load config file");
+                let sponsor_address = match config.gas_station_address {
+                    Some(address) => address,
+                    None => config.signer_config.new_signer().await.get_address(),
+                };
+                let storage = connect_storage(
+                    &config.storage_config,
+                    sponsor_address,
+                    StorageMetrics::new(&prometheus::Registry::new()),
+                )
+                .await;
+                let iota_client =
+                    IotaClient::new(&config.fullnode_url, config.fullnode_basic_auth).await;
+                let reconciliation = reconcile_pool(
+                    &iota_client,
+                    &storage,
+                    sponsor_address,
+                    max_adopted_balance(config.coin_init_config.as_ref()),
+                    dry_run,
+                )
+                .await
+                .unwrap();
+                let Some(reconciliation) = reconciliation else {
+                    eprintln!(
+                        "The pool is not initialized or is being initialized. Try again later."
+                    );
+                    std::process::exit(1);
+                };
+                if dry_run {
+                    println!("Differences between the pool and the coins owned on chain:");
+                } else {
+                    println!("Fixed differences between the pool and the coins owned on chain:");
+                }
+                println!("  Outdated coins: {}", reconciliation.updated);
+                println!("  Coins no longer owned: {}", reconciliation.removed);
+                println!("  Untracked coins: {}", reconciliation.adopted);
+                println!("  Duplicate coins: {}", reconciliation.duplicates);
+            }
+            ToolCommand::MigrateStorage { config_path } => {
+                let config =
+                    GasStationConfig::load(&config_path).expect("Failed to load config file");
+                let sponsor_address = match config.gas_station_address {
+                    Some(address) => address,
+                    None => config.signer_config.new_signer().await.get_address(),
+                };
+                match migrate_storage(
+                    &config.storage_config,
+                    sponsor_address,
+                    StorageMetrics::new(&prometheus::Registry::new()),
+                )
+                .await
+                {
+                    Ok(version) if version == STORAGE_VERSION => {
+                        println!("The storage of {} is up to date", sponsor_address);
+                    }
+                    Ok(version) => println!(
+                        "Migrated the storage of {} from version {} to {}",
+                        sponsor_address, version, STORAGE_VERSION
+                    ),
+                    Err(err) => {
+                        eprintln!("Failed to migrate the storage: {:?}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            ToolCommand::Drain {
+                config_path,
+                target_address,
+                cancel,
+                wait_timeout_sec,
+            } => {
+                let config =
+                    GasStationConfig::load(&config_path).expect("Failed to load config file");
+                let signer = config.signer_config.new_signer().await;
+                let sponsor_address = config
+                    .gas_station_address
+                    .unwrap_or_else(|| signer.get_address());
+                let storage = connect_storage(
+                    &config.storage_config,
+                    sponsor_address,
+                    StorageMetrics::new(&prometheus::Registry::new()),
+                )
+                .await;
+                if cancel {
+                    if let Err(err) = cancel_drain(&storage).await {
+                        eprintln!("Failed to cancel the drain: {:?}", err);
+                        std::process::exit(1);
+                    }
+                    println!("Gas coins of {} are reserved again", sponsor_address);
+                    return;
+                }
+                let target_address = IotaAddress::from_str(&target_address.unwrap())
+                    .expect("Invalid target address");
+                // The transactions of the drain are recorded like those of the gas station.
+                let signer = match &config.audit_log {
+                    Some(audit_log) => AuditedTxSigner::new(
+                        signer,
+                        SigningAuditLog::open(&audit_log.path)
+                            .expect("Failed to open the signing audit log"),
+                    ) as Arc<dyn TxSigner>,
+                    None => signer,
+                };
+                let iota_client =
+                    IotaClient::new(&config.fullnode_url, config.fullnode_basic_auth).await;
+                let summary = match drain_pool(
+                    &iota_client,
+                    &storage,
+                    &signer,
+                    target_address,
+                    Duration::from_secs(wait_timeout_sec),
+                )
+                .await
+                {
+                    Ok(summary) => summary,
+                    Err(err) => {
+                        eprintln!("Failed to drain the pool: {:?}", err);
+                        std::process::exit(1);
+                    }
+                };
+                println!("Drained the pool of {}:", sponsor_address);
+                println!("  Merged coins: {}", summary.merged_coins);
+                println!("  Transferred balance: {}", summary.transferred_balance);
+                if let Some(digest) = summary.transfer_digest {
+                    println!("  Transfer transaction: {}", digest);
+                }
+            }
+            ToolCommand::VerifyAuditLog { path } => match verify_audit_log(&path) {
+                Ok(summary) => {
+                    println!("The audit log {} is intact", path.display());
+                    println!("  Entries: {}", summary.entries);
+                    println!("  Last hash: {}", summary.last_hash);
+                }
+                Err(err) => {
+                    eprintln!("The audit log {} is not intact: {:?}", path.display(), err);
+                    std::process::exit(1);
+                }
+            },
+            ToolCommand::CreateKeystore {
+                output_path,
+                key,
+                iota_keystore,
+                address,
+                config_path,
+                passphrase_fd,
+                force,
+            } => {
+                let keypair = if let Some(key) = key {
+                    IotaKeyPair::decode(&key)
+                        .or_else(|_| IotaKeyPair::decode_base64(&key))
+                        .unwrap_or_else(|err| {
+                            eprintln!("Invalid key: {}", err);
+                            std::process::exit(1);
+                        })
+                } else if let Some(iota_keystore) = iota_keystore {
+                    let address = address.map(|address| {
+                        IotaAddress::from_str(&address).unwrap_or_else(|err| {
+                            eprintln!("Invalid address '{}': {}", address, err);
+                            std::process::exit(1);
+                        })
+                    });
+                    load_keypair(&iota_keystore, address, || {
+                        anyhow::bail!("The IOTA CLI keystore is expected to be unencrypted")
+                    })
+                    .unwrap_or_else(|err| {
+                        eprintln!("Failed to load the keystore: {:?}", err);
+                        std::process::exit(1);
+                    })
+                } else if let Some(config_path) = &config_path {
+                    let config = GasStationConfig::load(config_path).unwrap();
+                    match config.signer_config {
+                        TxSignerConfig::Local { keypair } => keypair,
+                        _ => {
+                            eprintln!("The config does not use a local signer");
+                            std::process::exit(1);
+                        }
+                    }
+                } else {
+                    get_account_key_pair().1.into()
+                };
+                let address = IotaAddress::from(&keypair.public());
+                let output_path = write_key_file(&keypair, &output_path, passphrase_fd, force);
+                println!("Wrote the key of {} to {}", address, output_path.display());
+                if let Some(config_path) = config_path {
+                    let mut config = GasStationConfig::load(&config_path).unwrap();
+                    config.signer_config = TxSignerConfig::Keystore {
+                        path: output_path,
+                        address: None,
+                        passphrase_env: KEYSTORE_PASSPHRASE_ENV_NAME.to_string(),
+                        passphrase_fd,
+                    };
+                    config.save(&config_path).unwrap();
+                    println!("Updated {} to use the key file", config_path.display());
+                }
+            }
             ToolCommand::ConvertKeyConfig { key } => {
                 let key = IotaKeyPair::decode(&key).unwrap();
                 println!("{}", key.encode_base64());
@@ -225,6 +622,27 @@ impl ToolCommand {
     }
 }
 
+/// Encrypts the key pair with the passphrase and writes it to `path`. Returns the absolute path
+/// of the key file, so that it can be referenced from a config elsewhere.
+fn write_key_file(
+    keypair: &IotaKeyPair,
+    path: &Path,
+    passphrase_fd: Option<i32>,
+    force: bool,
+) -> PathBuf {
+    let passphrase =
+        read_passphrase(KEYSTORE_PASSPHRASE_ENV_NAME, passphrase_fd).unwrap_or_else(|err| {
+            eprintln!("Failed to read the passphrase: {:?}", err);
+            std::process::exit(1);
+        });
+    let key_file = EncryptedKeyFile::encrypt(keypair, &passphrase).unwrap();
+    if let Err(err) = key_file.save(path, force) {
+        eprintln!("{:?}. Use --force (-f) to overwrite.", err);
+        std::process::exit(1);
+    }
+    std::fs::canonicalize(path).unwrap()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
 pub enum Network {
     Local,
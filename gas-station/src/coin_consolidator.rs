@@ -0,0 +1,331 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::CoinConsolidationConfig;
+use crate::gas_station_initializer::GasStationInitializer;
+use crate::iota_client::IotaClient;
+use crate::metrics::CoinConsolidatorMetrics;
+use crate::retry_forever;
+use crate::storage::{run_with_init_lock, Storage, INIT_LOCK_LEASE_SEC, MAX_GAS_PER_QUERY};
+use crate::tx_signer::TxSigner;
+use crate::types::GasCoin;
+use anyhow::bail;
+use iota_json_rpc_types::IotaTransactionBlockEffectsAPI;
+use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use iota_types::transaction::{Argument, Command, ObjectArg, Transaction, TransactionData};
+use std::sync::Arc;
+use std::time::Duration;
+use tap::TapFallible;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+/// Upper bound of the number of merge transactions in one run.
+const MAX_CONSOLIDATION_ROUNDS: usize = 10;
+
+/// The dust coins are put under an execution lease right after they are reserved.
+const DUST_RESERVATION_DURATION: Duration = Duration::from_secs(60);
+
+/// How long the dust coins stay leased to the merge and split transactions. If the gas station
+/// stops in between, the coins are recovered once it expires.
+const CONSOLIDATION_LEASE_DURATION: Duration = Duration::from_secs(60 * 10);
+
+/// Periodically merges low-balance coins of the pool into a larger coin, which is then split
+/// into coins of the target balance again. Every transaction pays for gas with a single coin
+/// whose balance goes down, so over time the pool fills with coins that cover little budget.
+pub struct CoinConsolidator {
+    task_handle: JoinHandle<()>,
+    // This is always Some. It is None only after the drop or shutdown method is called.
+    cancel_sender: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl Drop for CoinConsolidator {
+    fn drop(&mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            cancel_sender.send(()).unwrap();
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ConsolidationEnv {
+    iota_client: IotaClient,
+    storage: Arc<dyn Storage>,
+    signer: Arc<dyn TxSigner>,
+    config: CoinConsolidationConfig,
+    target_init_coin_balance: u64,
+    metrics: Arc<CoinConsolidatorMetrics>,
+}
+
+impl CoinConsolidator {
+    pub fn start(
+        iota_client: IotaClient,
+        storage: Arc<dyn Storage>,
+        signer: Arc<dyn TxSigner>,
+        config: CoinConsolidationConfig,
+        target_init_coin_balance: u64,
+        metrics: Arc<CoinConsolidatorMetrics>,
+    ) -> Self {
+        let env = ConsolidationEnv {
+            iota_client,
+            storage,
+            signer,
+            config,
+            target_init_coin_balance,
+            metrics,
+        };
+        let (cancel_sender, mut cancel_receiver) = tokio::sync::oneshot::channel();
+        let task_handle = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(env.config.interval_sec)) => {}
+                    _ = &mut cancel_receiver => {
+                        info!("Coin consolidation task is cancelled");
+                        break;
+                    }
+                }
+                env.run_once().await;
+            }
+        });
+        Self {
+            task_handle,
+            cancel_sender: Some(cancel_sender),
+        }
+    }
+
+    /// Cancels the consolidation task and waits for it to stop. A merge that is already running
+    /// is completed first, so that its coins are returned to the pool.
+    pub async fn shutdown(mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            let _ = cancel_sender.send(());
+        }
+        if let Err(err) = (&mut self.task_handle).await {
+            error!("Coin consolidation task failed: {:?}", err);
+        }
+    }
+}
+
+impl ConsolidationEnv {
+    async fn run_once(&self) {
+        match self.storage.is_initialized().await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                error!("Failed to check whether the pool is initialized: {:?}", err);
+                return;
+            }
+        }
+        // Consolidation holds the init lock so that the coins it splits are not mistaken for
+        // untracked coins by the pool reconciliation.
+        match self.storage.acquire_init_lock(INIT_LOCK_LEASE_SEC).await {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("The pool is being initialized. Skipping coin consolidation");
+                return;
+            }
+            Err(err) => {
+                error!("Failed to acquire the init lock: {:?}", err);
+                return;
+            }
+        }
+        let consolidate = async {
+            for _ in 0..MAX_CONSOLIDATION_ROUNDS {
+                if !self.consolidate_once().await? {
+                    break;
+                }
+            }
+            anyhow::Ok(())
+        };
+        if let Err(err) = run_with_init_lock(&self.storage, INIT_LOCK_LEASE_SEC, consolidate).await
+        {
+            error!("Failed to consolidate dust coins: {:?}", err);
+        }
+        if let Err(err) = self.storage.release_init_lock().await {
+            error!("Failed to release the init lock: {:?}", err);
+        }
+    }
+
+    /// Merges one batch of dust coins. Returns false if there was nothing worth merging.
+    async fn consolidate_once(&self) -> anyhow::Result<bool> {
+        let (reservation_id, coins) = self
+            .storage
+            .reserve_dust_coins(
+                self.config.dust_balance,
+                self.config.min_coin_count.max(2),
+                MAX_GAS_PER_QUERY,
+                DUST_RESERVATION_DURATION.as_millis() as u64,
+            )
+            .await?;
+        if coins.is_empty() {
+            debug!("Not enough dust coins in the pool to consolidate");
+            return Ok(false);
+        }
+        let object_ids: Vec<_> = coins.iter().map(|coin| coin.object_ref.0).collect();
+        self.storage
+            .ready_for_execution(
+                reservation_id,
+                &object_ids,
+                CONSOLIDATION_LEASE_DURATION.as_millis() as u64,
+            )
+            .await?;
+
+        let coin_count = coins.len();
+        self.metrics.num_coin_consolidations.inc();
+        let (updated_coins, merged) = match self.merge_coins(coins.clone()).await {
+            Ok(Some(merged_coin)) => {
+                info!(
+                    ?reservation_id,
+                    "Merged {} dust coins into {:?}", coin_count, merged_coin
+                );
+                self.metrics
+                    .num_consolidated_gas_coins
+                    .with_label_values(&[&self.signer.get_address().to_string()])
+                    .inc_by(coin_count as u64);
+                let split_coins = GasStationInitializer::split_coins(
+                    self.iota_client.clone(),
+                    &self.signer,
+                    self.target_init_coin_balance,
+                    vec![merged_coin],
+                )
+                .await;
+                (split_coins, true)
+            }
+            Ok(None) => {
+                info!(
+                    ?reservation_id,
+                    "The balance of {} dust coins does not cover merging them", coin_count
+                );
+                (coins, false)
+            }
+            Err(err) => {
+                error!(?reservation_id, "Failed to merge dust coins: {:?}", err);
+                self.metrics.num_failed_coin_consolidations.inc();
+                let latest_coins = self
+                    .iota_client
+                    .get_latest_gas_objects(object_ids)
+                    .await
+                    .into_values()
+                    .flatten()
+                    .collect();
+                (latest_coins, false)
+            }
+        };
+        retry_forever!(async {
+            self.storage
+                .complete_execution(reservation_id, updated_coins.clone())
+                .await
+                .tap_err(|err| error!("Failed to call complete_execution on storage: {:?}", err))
+        })?;
+        Ok(merged)
+    }
+
+    /// Merges the coins into one. The largest coins pay for gas and the others are merged into
+    /// the gas coin with `MergeCoins`. Returns None if merging would cost more than it is worth.
+    async fn merge_coins(&self, mut coins: Vec<GasCoin>) -> anyhow::Result<Option<GasCoin>> {
+        let sponsor_address = self.signer.get_address();
+        coins.sort_by(|a, b| b.balance.cmp(&a.balance));
+        let total_balance: u64 = coins.iter().map(|coin| coin.balance).sum();
+
+        let mut inspect_builder = ProgrammableTransactionBuilder::new();
+        let inspect_args = coins
+            .iter()
+            .map(|coin| inspect_builder.obj(ObjectArg::ImmOrOwnedObject(coin.object_ref)))
+            .collect::<Result<Vec<_>, _>>()?;
+        inspect_builder.command(Command::MergeCoins(
+            inspect_args[0],
+            inspect_args[1..].to_vec(),
+        ));
+        let budget = self
+            .iota_client
+            .estimate_gas_budget(sponsor_address, inspect_builder.finish())
+            .await?;
+        if total_balance < budget * 2 {
+            return Ok(None);
+        }
+
+        let mut payment = vec![];
+        let mut payment_balance = 0;
+        let mut merged = vec![];
+        for coin in coins {
+            if payment_balance < budget {
+                payment_balance += coin.balance;
+                payment.push(coin.object_ref);
+            } else {
+                merged.push(coin.object_ref);
+            }
+        }
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        if !merged.is_empty() {
+            let merged_args = merged
+                .into_iter()
+                .map(|object_ref| pt_builder.obj(ObjectArg::ImmOrOwnedObject(object_ref)))
+                .collect::<Result<Vec<_>, _>>()?;
+            pt_builder.command(Command::MergeCoins(Argument::GasCoin, merged_args));
+        }
+        let rgp = self.iota_client.get_reference_gas_price().await;
+        let tx_data = TransactionData::new_programmable(
+            sponsor_address,
+            payment,
+            pt_builder.finish(),
+            budget,
+            rgp,
+        );
+        let sig = self.signer.sign_transaction(&tx_data).await?;
+        let tx = Transaction::from_generic_sig_data(tx_data, vec![sig]);
+        let effects = self.iota_client.execute_transaction(tx, 3, None).await?;
+        if !effects.status().is_ok() {
+            bail!("Merge transaction failed: {:?}", effects.status());
+        }
+        let balance = total_balance as i64 - effects.gas_cost_summary().net_gas_usage();
+        Ok(Some(GasCoin {
+            object_ref: effects.gas_object().reference.to_object_ref(),
+            balance: balance as u64,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coin_consolidator::ConsolidationEnv;
+    use crate::config::CoinConsolidationConfig;
+    use crate::iota_client::IotaClient;
+    use crate::metrics::CoinConsolidatorMetrics;
+    use crate::storage::connect_storage_for_testing;
+    use crate::test_env::start_iota_cluster;
+    use iota_types::gas_coin::NANOS_PER_IOTA;
+
+    #[tokio::test]
+    async fn test_consolidate_dust_coins() {
+        telemetry_subscribers::init_for_testing();
+        let (cluster, signer) = start_iota_cluster(vec![NANOS_PER_IOTA / 10; 100]).await;
+        let fullnode_url = cluster.fullnode_handle.rpc_url;
+        let storage = connect_storage_for_testing(signer.get_address()).await;
+        let iota_client = IotaClient::new(&fullnode_url, None).await;
+        let coins = iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(signer.get_address(), 0)
+            .await;
+        let total_balance: u64 = coins.iter().map(|coin| coin.balance).sum();
+        storage.add_new_coins(coins).await.unwrap();
+
+        let env = ConsolidationEnv {
+            iota_client,
+            storage: storage.clone(),
+            signer,
+            config: CoinConsolidationConfig {
+                interval_sec: 200,
+                dust_balance: NANOS_PER_IOTA,
+                min_coin_count: 50,
+            },
+            target_init_coin_balance: 2 * NANOS_PER_IOTA,
+            metrics: CoinConsolidatorMetrics::new_for_testing(),
+        };
+        env.run_once().await;
+
+        // The coins were merged and split into a few coins with the target balance.
+        let coin_count = storage.get_available_coin_count().await.unwrap();
+        assert!(coin_count < 10, "coin_count: {}", coin_count);
+        let new_total_balance = storage.get_available_coin_total_balance().await;
+        assert!(new_total_balance < total_balance);
+        assert!(new_total_balance > total_balance - NANOS_PER_IOTA);
+        assert_eq!(storage.get_reserved_coin_count().await, 0);
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::AdaptiveCoinSizingConfig;
+use crate::metrics::CoinSizingMetrics;
+use crate::storage::Storage;
+use crate::types::CoinDenomination;
+use iota_types::base_types::IotaAddress;
+use prometheus::core::Metric;
+use prometheus::Histogram;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+/// Derives the balances of new gas coins from the budgets requested by clients, so that most
+/// reservations are covered by exactly one coin. The budgets observed by each instance are added
+/// to statistics in storage, so that the denominations reflect the budgets requested from all
+/// instances, whichever one splits the new coins.
+pub struct AdaptiveCoinSizing {
+    config: AdaptiveCoinSizingConfig,
+    budget_distribution: Histogram,
+    // Number of budgets per bucket that were already added to the statistics in storage.
+    flushed_counts: Mutex<Vec<u64>>,
+    metrics: Arc<CoinSizingMetrics>,
+}
+
+impl AdaptiveCoinSizing {
+    pub fn new(
+        config: AdaptiveCoinSizingConfig,
+        budget_distribution: Histogram,
+        metrics: Arc<CoinSizingMetrics>,
+    ) -> Self {
+        Self {
+            config,
+            budget_distribution,
+            flushed_counts: Default::default(),
+            metrics,
+        }
+    }
+
+    /// Adds the budgets observed since the previous call to the statistics in storage.
+    pub async fn flush_budget_samples(&self, storage: &Arc<dyn Storage>) {
+        let mut flushed_counts = self.flushed_counts.lock().await;
+        let counts = self.bucket_counts();
+        flushed_counts.resize(counts.len(), 0);
+        let samples: Vec<_> = counts
+            .iter()
+            .zip(flushed_counts.iter())
+            .filter(|((_, count), flushed_count)| count > flushed_count)
+            .map(|((upper_bound, count), flushed_count)| (*upper_bound, count - flushed_count))
+            .collect();
+        match storage.add_budget_samples(&samples).await {
+            Ok(()) => {
+                *flushed_counts = counts.into_iter().map(|(_, count)| count).collect();
+            }
+            Err(err) => error!("Failed to store the budget samples: {:?}", err),
+        }
+    }
+
+    /// Returns the number of budgets observed by this instance per bucket, as (upper bound,
+    /// count) pairs. Budgets above the largest bucket are counted with `u64::MAX`.
+    fn bucket_counts(&self) -> Vec<(u64, u64)> {
+        let metric = self.budget_distribution.metric();
+        let histogram = metric.get_histogram();
+        let mut counts = vec![];
+        let mut previous_count = 0;
+        for bucket in histogram.get_bucket() {
+            let count = bucket.get_cumulative_count();
+            counts.push((bucket.get_upper_bound() as u64, count - previous_count));
+            previous_count = count;
+        }
+        counts.push((u64::MAX, histogram.get_sample_count() - previous_count));
+        counts
+    }
+
+    /// Returns the denominations new coins should be split into. Once enough budgets were
+    /// observed by all instances, they are derived from the budgets and persisted. Otherwise the
+    /// persisted denominations are returned, which may be empty.
+    pub async fn denominations(
+        &self,
+        storage: &Arc<dyn Storage>,
+        sponsor_address: IotaAddress,
+    ) -> Vec<CoinDenomination> {
+        self.flush_budget_samples(storage).await;
+        let samples = storage.get_budget_samples().await.unwrap_or_else(|err| {
+            error!("Failed to get the budget samples: {:?}", err);
+            vec![]
+        });
+        let sample_count: u64 = samples.iter().map(|(_, count)| count).sum();
+        let denominations = if sample_count >= self.config.min_sample_count {
+            let mut buckets = vec![];
+            let mut cumulative_count = 0;
+            for (upper_bound, count) in samples {
+                if upper_bound != u64::MAX {
+                    cumulative_count += count;
+                    buckets.push((upper_bound, cumulative_count));
+                }
+            }
+            let denominations =
+                compute_denominations(&buckets, sample_count, self.config.max_denominations);
+            if let Err(err) = storage.set_coin_denominations(&denominations).await {
+                error!("Failed to persist the coin denominations: {:?}", err);
+            }
+            denominations
+        } else {
+            storage
+                .get_coin_denominations()
+                .await
+                .unwrap_or_else(|err| {
+                    error!("Failed to get the persisted coin denominations: {:?}", err);
+                    vec![]
+                })
+        };
+
+        let sponsor = sponsor_address.to_string();
+        self.metrics.coin_denomination_share.reset();
+        for denomination in &denominations {
+            self.metrics
+                .coin_denomination_share
+                .with_label_values(&[&sponsor, &denomination.balance.to_string()])
+                .set(denomination.share);
+        }
+        info!("Coin denominations: {:?}", denominations);
+        denominations
+    }
+}
+
+/// Picks the `max_denominations` buckets with the most budgets. The budgets of the other buckets
+/// are counted towards the next larger picked bucket, whose upper bound still covers them with
+/// one coin, or towards the largest one. `cumulative_buckets` are (upper bound, cumulative count)
+/// pairs sorted by upper bound, as reported by a prometheus histogram.
+fn compute_denominations(
+    cumulative_buckets: &[(u64, u64)],
+    sample_count: u64,
+    max_denominations: usize,
+) -> Vec<CoinDenomination> {
+    let mut counts = Vec::with_capacity(cumulative_buckets.len());
+    let mut previous_count = 0;
+    for &(upper_bound, cumulative_count) in cumulative_buckets {
+        counts.push((upper_bound, cumulative_count - previous_count));
+        previous_count = cumulative_count;
+    }
+    // Budgets above the largest bucket are covered by several coins of the largest denomination.
+    if let Some(last) = counts.last_mut() {
+        last.1 += sample_count - previous_count;
+    }
+
+    let mut picked: Vec<_> = counts.iter().filter(|(_, count)| *count > 0).collect();
+    if picked.is_empty() {
+        return vec![];
+    }
+    picked.sort_by(|a, b| b.1.cmp(&a.1));
+    picked.truncate(max_denominations.max(1));
+    picked.sort_by_key(|(upper_bound, _)| *upper_bound);
+
+    let mut picked_counts = vec![0; picked.len()];
+    for (upper_bound, count) in &counts {
+        let index = picked
+            .iter()
+            .position(|(picked_bound, _)| picked_bound >= upper_bound)
+            .unwrap_or(picked.len() - 1);
+        picked_counts[index] += count;
+    }
+    picked
+        .into_iter()
+        .zip(picked_counts)
+        .map(|((balance, _), count)| CoinDenomination {
+            balance: *balance,
+            share: count as f64 / sample_count as f64,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::connect_storage_for_testing;
+    use prometheus::HistogramOpts;
+
+    fn new_coin_sizing(min_sample_count: u64) -> AdaptiveCoinSizing {
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("budgets", "budgets").buckets(vec![1000.0, 2000.0, 4000.0]),
+        )
+        .unwrap();
+        AdaptiveCoinSizing::new(
+            AdaptiveCoinSizingConfig {
+                min_sample_count,
+                max_denominations: 2,
+            },
+            histogram,
+            CoinSizingMetrics::new_for_testing(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_shared_budget_samples() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let coin_sizing1 = new_coin_sizing(10);
+        let coin_sizing2 = new_coin_sizing(10);
+        for _ in 0..3 {
+            coin_sizing1.budget_distribution.observe(800.0);
+        }
+        coin_sizing1.flush_budget_samples(&storage).await;
+        // Budgets that were flushed already are not added again.
+        coin_sizing1.flush_budget_samples(&storage).await;
+        assert!(coin_sizing1
+            .denominations(&storage, sponsor)
+            .await
+            .is_empty());
+
+        // The budgets observed by another instance count as well.
+        for _ in 0..7 {
+            coin_sizing2.budget_distribution.observe(3000.0);
+        }
+        coin_sizing2.flush_budget_samples(&storage).await;
+        assert_eq!(
+            coin_sizing1.denominations(&storage, sponsor).await,
+            vec![
+                CoinDenomination {
+                    balance: 1000,
+                    share: 0.3,
+                },
+                CoinDenomination {
+                    balance: 4000,
+                    share: 0.7,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compute_denominations() {
+        assert!(compute_denominations(&[(1, 0), (2, 0)], 0, 2).is_empty());
+
+        let buckets = [(1, 0), (2, 50), (4, 60), (8, 90), (16, 95)];
+        assert_eq!(
+            compute_denominations(&buckets, 100, 2),
+            vec![
+                CoinDenomination {
+                    balance: 2,
+                    share: 0.5,
+                },
+                // Includes the budgets up to 4, up to 16 and above 16.
+                CoinDenomination {
+                    balance: 8,
+                    share: 0.5,
+                },
+            ]
+        );
+        assert_eq!(
+            compute_denominations(&buckets, 100, 1),
+            vec![CoinDenomination {
+                balance: 2,
+                share: 1.0,
+            }]
+        );
+        assert_eq!(compute_denominations(&buckets, 100, 10).len(), 4);
+    }
+}
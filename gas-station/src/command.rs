@@ -2,15 +2,29 @@
 // Modifications Copyright (c) 2025 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::config::GasStationConfig;
+use crate::audit_log::{AuditedTxSigner, SigningAuditLog};
+use crate::coin_consolidator::CoinConsolidator;
+use crate::coin_sizing::AdaptiveCoinSizing;
+use crate::config::{GasStationConfig, DEFAULT_INIT_COIN_BALANCE};
+use crate::execution_notifier::{ExecutionNotifier, NotificationDelivery};
 use crate::gas_station::gas_station_core::GasStationContainer;
-use crate::gas_station_initializer::GasStationInitializer;
+use crate::gas_station_initializer::{GasStationInitializer, NEW_COIN_BALANCE_FACTOR_THRESHOLD};
 use crate::iota_client::IotaClient;
-use crate::metrics::{GasStationCoreMetrics, GasStationRpcMetrics, StorageMetrics};
+use crate::low_balance_monitor::{LowBalanceMonitor, Refill};
+use crate::metrics::{
+    CoinConsolidatorMetrics, CoinSizingMetrics, ExecutionNotificationMetrics,
+    GasStationCoreMetrics, GasStationRpcMetrics, LowBalanceMonitorMetrics, PoolReconcilerMetrics,
+    StorageMetrics,
+};
+use crate::pool_reconciler::{max_adopted_balance, PoolReconciler};
+use crate::rpc::browser_auth::BrowserAuth;
+use crate::rpc::tls::TlsServer;
 use crate::rpc::GasStationServer;
+use crate::signer_rotation::{RotatingTxSigner, SignerRotation};
 use crate::storage::connect_storage;
 use crate::tracker::stats_tracker_storage::redis::connect_stats_storage;
 use crate::tracker::StatsTracker;
+use crate::tx_signer::TxSigner;
 use crate::{TRANSACTION_LOGGING_ENV_NAME, TRANSACTION_LOGGING_TARGET_NAME, VERSION};
 use arc_swap::ArcSwap;
 use clap::*;
@@ -18,7 +32,8 @@ use iota_config::Config;
 use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[derive(Parser)]
 #[command(
@@ -46,10 +61,25 @@ impl Command {
             metrics_port,
             coin_init_config,
             daily_gas_usage_cap,
+            execution,
             mut access_controller,
             gas_station_address,
+            cors,
+            browser_auth,
+            tls,
+            shutdown_timeout_sec,
+            pool_reconciliation,
+            coin_consolidation,
+            low_balance_alert,
+            signer_rotation,
+            audit_log,
+            execution_notifications,
         } = config;
 
+        execution
+            .validate()
+            .expect("Invalid execution configuration");
+
         let metric_address = SocketAddr::new(IpAddr::V4(rpc_host_ip), metrics_port);
         let registry_service = iota_metrics::start_prometheus_server(metric_address);
         let prometheus_registry = registry_service.default_registry();
@@ -64,37 +94,153 @@ impl Command {
         let _guard = telemetry_config.init();
         info!("Metrics server started at {:?}", metric_address);
 
-        let signer = signer_config.new_signer().await;
+        let audit_log = audit_log.map(|config| {
+            let audit_log =
+                SigningAuditLog::open(&config.path).expect("Failed to open the signing audit log");
+            info!("Recording signatures in {}", config.path.display());
+            audit_log
+        });
+        let audited = |signer: Arc<dyn TxSigner>| -> Arc<dyn TxSigner> {
+            match &audit_log {
+                Some(audit_log) => {
+                    AuditedTxSigner::new(signer, audit_log.clone()) as Arc<dyn TxSigner>
+                }
+                None => signer,
+            }
+        };
+        let signer = audited(signer_config.new_signer().await);
         let storage_metrics = StorageMetrics::new(&prometheus_registry);
         let sponsor_address = gas_station_address.unwrap_or_else(|| signer.get_address());
         info!("Gas Station address: {:?}", sponsor_address);
 
-        let storage = connect_storage(&gas_station_config, sponsor_address, storage_metrics).await;
+        let storage = connect_storage(
+            &gas_station_config,
+            sponsor_address,
+            storage_metrics.clone(),
+        )
+        .await;
         let iota_client = IotaClient::new(&fullnode_url, fullnode_basic_auth).await;
-        let _coin_init_task = if let Some(coin_init_config) = coin_init_config {
+        let max_adopted_balance = max_adopted_balance(coin_init_config.as_ref());
+        let target_init_coin_balance = coin_init_config
+            .as_ref()
+            .map(|config| config.target_init_balance)
+            .unwrap_or(DEFAULT_INIT_COIN_BALANCE);
+        let rpc_metrics = GasStationRpcMetrics::new(&prometheus_registry);
+        let coin_init_task = if let Some(coin_init_config) = coin_init_config {
+            let coin_sizing = coin_init_config.adaptive_sizing.clone().map(|config| {
+                AdaptiveCoinSizing::new(
+                    config,
+                    rpc_metrics.target_gas_budget_distribution.clone(),
+                    CoinSizingMetrics::new(&prometheus_registry),
+                )
+            });
             let task = GasStationInitializer::start(
                 iota_client.clone(),
                 storage.clone(),
                 coin_init_config,
                 signer.clone(),
+                coin_sizing,
             )
             .await;
             Some(task)
         } else {
             None
         };
+        let pool_reconciler = pool_reconciliation.map(|config| {
+            PoolReconciler::start(
+                iota_client.clone(),
+                storage.clone(),
+                sponsor_address,
+                config,
+                max_adopted_balance,
+                PoolReconcilerMetrics::new(&prometheus_registry),
+            )
+        });
+        let coin_consolidator = coin_consolidation.map(|config| {
+            CoinConsolidator::start(
+                iota_client.clone(),
+                storage.clone(),
+                signer.clone(),
+                config,
+                target_init_coin_balance,
+                CoinConsolidatorMetrics::new(&prometheus_registry),
+            )
+        });
+        let low_balance_monitor = match low_balance_alert {
+            Some(mut config) => {
+                let refill = match config.refill.take() {
+                    Some(refill) => {
+                        if coin_init_task.is_some()
+                            && refill.amount
+                                < target_init_coin_balance * NEW_COIN_BALANCE_FACTOR_THRESHOLD
+                        {
+                            warn!(
+                                "Refill amount {} is too small to be split by the coin init refresh",
+                                refill.amount
+                            );
+                        }
+                        Some(Refill {
+                            iota_client: iota_client.clone(),
+                            signer: audited(refill.treasury_signer_config.new_signer().await),
+                            amount: refill.amount,
+                            cooldown: Duration::from_secs(refill.cooldown_sec),
+                        })
+                    }
+                    None => None,
+                };
+                Some(LowBalanceMonitor::start(
+                    storage.clone(),
+                    sponsor_address,
+                    config,
+                    refill,
+                    coin_init_task.as_ref().map(|task| task.refresh_notifier()),
+                    LowBalanceMonitorMetrics::new(&prometheus_registry),
+                ))
+            }
+            None => None,
+        };
+        let notification_storage = storage.clone();
+        let (gas_station_signer, previous_sponsor_store, signer_rotation) = match signer_rotation {
+            Some(rotation) => {
+                let previous_signer = audited(rotation.previous_signer_config.new_signer().await);
+                let previous_address = previous_signer.get_address();
+                assert_ne!(
+                    previous_address, sponsor_address,
+                    "The previous signer must have a different address than the current one"
+                );
+                info!("Rotating the sponsor from {:?}", previous_address);
+                let previous_storage =
+                    connect_storage(&gas_station_config, previous_address, storage_metrics).await;
+                let rotation_task = SignerRotation::start(
+                    iota_client.clone(),
+                    previous_storage.clone(),
+                    previous_signer.clone(),
+                    sponsor_address,
+                    Duration::from_secs(rotation.wait_timeout_sec),
+                    coin_init_task.as_ref().map(|task| task.refresh_notifier()),
+                );
+                (
+                    RotatingTxSigner::new(signer.clone(), previous_signer) as Arc<dyn TxSigner>,
+                    Some((previous_address, previous_storage)),
+                    Some(rotation_task),
+                )
+            }
+            None => (signer, None, None),
+        };
         let core_metrics = GasStationCoreMetrics::new(&prometheus_registry);
         let stats_storage = connect_stats_storage(&gas_station_config, sponsor_address).await;
         let stats_tracker = StatsTracker::new(Arc::new(stats_storage));
         let container = GasStationContainer::new(
-            signer,
+            gas_station_signer,
             storage,
             iota_client,
             daily_gas_usage_cap,
+            execution,
             core_metrics,
+            previous_sponsor_store,
         )
         .await;
-        let rpc_metrics = GasStationRpcMetrics::new(&prometheus_registry);
+        access_controller.set_metrics(rpc_metrics.clone());
         access_controller
             .initialize()
             .await
@@ -104,8 +250,41 @@ impl Command {
             access_controller.rules.len()
         );
         let access_controller = Arc::new(ArcSwap::new(Arc::new(access_controller)));
+        let cors_layer = cors.map(|cors| cors.to_layer().expect("Invalid CORS configuration"));
+        let browser_auth = match browser_auth {
+            Some(browser_auth) => Some(
+                BrowserAuth::new(browser_auth, rpc_metrics.clone())
+                    .await
+                    .expect("Failed to initialize browser authentication"),
+            ),
+            None => None,
+        };
+        let execution_notifications = execution_notifications.unwrap_or_default();
+        if let Some(webhook) = &execution_notifications.webhook {
+            webhook
+                .initialize()
+                .expect("Invalid execution notification webhook");
+        }
+        let execution_notifier = ExecutionNotifier::new(
+            notification_storage,
+            execution_notifications.webhook,
+            access_controller.clone(),
+            browser_auth
+                .as_ref()
+                .map(|browser_auth| browser_auth.notification_hooks())
+                .unwrap_or_default(),
+            execution.lease(),
+        );
+        let notification_delivery = NotificationDelivery::start(
+            execution_notifier.clone(),
+            Duration::from_secs(execution_notifications.max_retry_interval_sec),
+            execution_notifications.max_attempts,
+            ExecutionNotificationMetrics::new(&prometheus_registry),
+        );
+        let tls_server = tls.map(|tls| TlsServer::new(tls).expect("Invalid TLS configuration"));
+        let coin_init_refresh = coin_init_task.as_ref().map(|task| task.refresh_notifier());
 
-        let server = GasStationServer::new(
+        let mut server = GasStationServer::new(
             container.get_gas_station_arc(),
             rpc_host_ip,
             rpc_port,
@@ -113,8 +292,73 @@ impl Command {
             access_controller,
             stats_tracker,
             self.config_path.clone(),
+            cors_layer,
+            browser_auth,
+            tls_server,
+            coin_init_refresh,
+            Some(execution_notifier),
         )
         .await;
-        server.handle.await.unwrap();
+        tokio::select! {
+            result = &mut server.handle => {
+                result.unwrap();
+                return;
+            }
+            _ = wait_for_shutdown_signal() => {}
+        }
+
+        info!("Shutting down the gas station");
+        let shutdown_timeout = Duration::from_secs(shutdown_timeout_sec);
+        if !server.shutdown(shutdown_timeout).await {
+            warn!(
+                "Transactions were still being executed after {:?}",
+                shutdown_timeout
+            );
+        }
+        notification_delivery.shutdown().await;
+        let gas_station = container.get_gas_station_arc();
+        gas_station.release_in_flight_coins().await;
+        container.shutdown().await;
+        if let Some(signer_rotation) = signer_rotation {
+            signer_rotation.shutdown().await;
+        }
+        if let Some(pool_reconciler) = pool_reconciler {
+            pool_reconciler.shutdown().await;
+        }
+        if let Some(low_balance_monitor) = low_balance_monitor {
+            low_balance_monitor.shutdown().await;
+        }
+        if let Some(coin_consolidator) = coin_consolidator {
+            if tokio::time::timeout(shutdown_timeout, coin_consolidator.shutdown())
+                .await
+                .is_err()
+            {
+                warn!(
+                    "Coin consolidation was still running after {:?}",
+                    shutdown_timeout
+                );
+            }
+        }
+        if let Some(coin_init_task) = coin_init_task {
+            if tokio::time::timeout(shutdown_timeout, coin_init_task.shutdown())
+                .await
+                .is_err()
+            {
+                warn!(
+                    "Coin initialization was still running after {:?}",
+                    shutdown_timeout
+                );
+            }
+        }
+        info!("Gas station shut down");
+    }
+}
+
+async fn wait_for_shutdown_signal() {
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("Failed to install SIGTERM handler");
+    tokio::select! {
+        _ = sigterm.recv() => info!("Received SIGTERM"),
+        _ = tokio::signal::ctrl_c() => info!("Received Ctrl+C"),
     }
 }
@@ -1,16 +1,30 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::access_controller::hook::HookAction;
+use crate::access_controller::rule::AccessRule;
 use crate::access_controller::AccessController;
-use crate::tx_signer::{SidecarTxSigner, TestTxSigner, TxSigner};
+use crate::keystore::{load_keypair, read_passphrase};
+use crate::multisig_signer::{MultisigMember, MultisigTxSigner};
+use crate::pkcs11_signer::Pkcs11TxSigner;
+use crate::sidecar_signer::SidecarTxSigner;
+use crate::tx_signer::{TestTxSigner, TxSigner};
+use crate::{KEYSTORE_PASSPHRASE_ENV_NAME, PKCS11_PIN_ENV_NAME};
+use anyhow::{bail, Context};
 use iota_config::Config;
 use iota_types::base_types::IotaAddress;
-use iota_types::crypto::{get_account_key_pair, IotaKeyPair};
+use iota_types::crypto::{get_account_key_pair, IotaKeyPair, PublicKey};
 use iota_types::gas_coin::NANOS_PER_IOTA;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use std::collections::BTreeMap;
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+use url::Url;
 
 pub const DEFAULT_RPC_PORT: u16 = 9527;
 pub const DEFAULT_METRICS_PORT: u16 = 9184;
@@ -18,7 +32,40 @@ pub const DEFAULT_METRICS_PORT: u16 = 9184;
 pub const DEFAULT_INIT_COIN_BALANCE: u64 = NANOS_PER_IOTA / 10;
 // 24 hours.
 const DEFAULT_COIN_POOL_REFRESH_INTERVAL_SEC: u64 = 60 * 60 * 24;
+const DEFAULT_BALANCE_POLL_INTERVAL_SEC: u64 = 60;
+const DEFAULT_PKCS11_NUM_SESSIONS: usize = 4;
+const DEFAULT_SIDECAR_REQUEST_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_SIDECAR_CONNECT_TIMEOUT_MS: u64 = 2_000;
+const DEFAULT_SIDECAR_STARTUP_MAX_ATTEMPTS: u32 = 10;
+const DEFAULT_SIDECAR_HEALTH_CHECK_INTERVAL_SEC: u64 = 10;
 pub const DEFAULT_DAILY_GAS_USAGE_CAP: u64 = 1500 * NANOS_PER_IOTA;
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SEC: u64 = 30;
+// 5 minutes.
+const DEFAULT_EXECUTION_LEASE_SEC: u64 = 60 * 5;
+// 2 minutes.
+const DEFAULT_EXECUTION_TIMEOUT_SEC: u64 = 60 * 2;
+// 1 hour.
+const DEFAULT_POOL_RECONCILIATION_INTERVAL_SEC: u64 = 60 * 60;
+// 1 hour.
+const DEFAULT_COIN_CONSOLIDATION_INTERVAL_SEC: u64 = 60 * 60;
+// 0.01 IOTA.
+const DEFAULT_DUST_COIN_BALANCE: u64 = NANOS_PER_IOTA / 100;
+const DEFAULT_MIN_DUST_COIN_COUNT: usize = 50;
+const DEFAULT_MIN_BUDGET_SAMPLE_COUNT: u64 = 1000;
+const DEFAULT_MAX_COIN_DENOMINATIONS: usize = 4;
+// 1 minute.
+const DEFAULT_LOW_BALANCE_CHECK_INTERVAL_SEC: u64 = 60;
+const DEFAULT_ROTATION_WAIT_TIMEOUT_SEC: u64 = 60 * 10;
+// 10 minutes.
+const DEFAULT_REFILL_COOLDOWN_SEC: u64 = 60 * 10;
+// 5 minutes.
+const DEFAULT_NOTIFICATION_MAX_RETRY_INTERVAL_SEC: u64 = 60 * 5;
+// About 8 hours with the default retry interval.
+const DEFAULT_NOTIFICATION_MAX_ATTEMPTS: u32 = 100;
+// 0.05 IOTA.
+const DEFAULT_BROWSER_MAX_GAS_BUDGET: u64 = 50_000_000;
+const DEFAULT_BROWSER_MAX_CONCURRENT_RESERVATIONS: usize = 20;
+const DEFAULT_BROWSER_MAX_RESERVATIONS_PER_MINUTE: u32 = 60;
 
 // Use 127.0.0.1 for tests to avoid OS complaining about permissions.
 #[cfg(test)]
@@ -43,11 +90,49 @@ pub struct GasStationConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub coin_init_config: Option<CoinInitConfig>,
     pub daily_gas_usage_cap: u64,
+    /// Limits of the execution of reserved transactions.
+    #[serde(default)]
+    pub execution: ExecutionConfig,
     #[serde(default)]
     pub access_controller: AccessController,
     /// Optional custom gas station address. If not specified, the address will be derived from the signer configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gas_station_address: Option<IotaAddress>,
+    /// Optional CORS configuration, required when the RPC server is called directly from a browser.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors: Option<CorsConfig>,
+    /// Optional authentication of browser clients with per-origin public keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser_auth: Option<BrowserAuthConfig>,
+    /// Optional TLS termination of the RPC server. Plain HTTP is served if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// How long to wait on shutdown for transactions that are being executed, in seconds.
+    #[serde(default = "default_shutdown_timeout_sec")]
+    pub shutdown_timeout_sec: u64,
+    /// Optional periodic reconciliation of the pool with the coins owned by the sponsor on chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_reconciliation: Option<PoolReconciliationConfig>,
+    /// Optional periodic merging of low-balance coins of the pool into larger coins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coin_consolidation: Option<CoinConsolidationConfig>,
+    /// Optional alerting, and refilling from a treasury, when the pool runs low.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_balance_alert: Option<LowBalanceAlertConfig>,
+    /// Optional rotation from a previous sponsor key to `signer-config`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_rotation: Option<SignerRotationConfig>,
+    /// Optional append-only log of every transaction signed by the gas station.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<AuditLogConfig>,
+    /// Optional webhook that is notified of every executed transaction, and the retries of the
+    /// notifications of all webhooks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_notifications: Option<ExecutionNotificationConfig>,
+}
+
+fn default_shutdown_timeout_sec() -> u64 {
+    DEFAULT_SHUTDOWN_TIMEOUT_SEC
 }
 
 impl Config for GasStationConfig {}
@@ -64,23 +149,125 @@ impl Default for GasStationConfig {
             fullnode_basic_auth: None,
             coin_init_config: Some(CoinInitConfig::default()),
             daily_gas_usage_cap: DEFAULT_DAILY_GAS_USAGE_CAP,
+            execution: ExecutionConfig::default(),
             access_controller: AccessController::default(),
             gas_station_address: None,
+            cors: None,
+            browser_auth: None,
+            tls: None,
+            shutdown_timeout_sec: DEFAULT_SHUTDOWN_TIMEOUT_SEC,
+            pool_reconciliation: None,
+            coin_consolidation: None,
+            low_balance_alert: None,
+            signer_rotation: None,
+            audit_log: None,
+            execution_notifications: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExecutionConfig {
+    /// How long the coins of a transaction stay leased to its execution, in seconds. Once the
+    /// lease expires, the coins are recovered from the fullnode, in case the gas station stopped
+    /// before releasing them. It must be at least `timeout-sec`, so that the coins of an
+    /// execution that is still running are not recovered.
+    #[serde(default = "default_execution_lease_sec")]
+    pub lease_sec: u64,
+    /// How long signing and executing a transaction may take, including retries, in seconds.
+    #[serde(default = "default_execution_timeout_sec")]
+    pub timeout_sec: u64,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            lease_sec: DEFAULT_EXECUTION_LEASE_SEC,
+            timeout_sec: DEFAULT_EXECUTION_TIMEOUT_SEC,
+        }
+    }
+}
+
+impl ExecutionConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.timeout_sec == 0 {
+            bail!("execution.timeout-sec must be positive");
         }
+        if self.lease_sec < self.timeout_sec {
+            bail!(
+                "execution.lease-sec ({}) must be at least execution.timeout-sec ({})",
+                self.lease_sec,
+                self.timeout_sec
+            );
+        }
+        Ok(())
+    }
+
+    pub fn lease(&self) -> Duration {
+        Duration::from_secs(self.lease_sec)
     }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_sec)
+    }
+}
+
+fn default_execution_lease_sec() -> u64 {
+    DEFAULT_EXECUTION_LEASE_SEC
+}
+
+fn default_execution_timeout_sec() -> u64 {
+    DEFAULT_EXECUTION_TIMEOUT_SEC
 }
 
 #[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum GasStationStorageConfig {
-    Redis { redis_url: String },
+    Redis {
+        redis_url: String,
+        /// How the gas coins of a reservation are picked from the pool.
+        #[serde(default)]
+        coin_selection_strategy: CoinSelectionStrategy,
+    },
 }
 
 impl Default for GasStationStorageConfig {
     fn default() -> Self {
         Self::Redis {
             redis_url: "redis://127.0.0.1:6379".to_string(),
+            coin_selection_strategy: CoinSelectionStrategy::default(),
+        }
+    }
+}
+
+/// The order in which available gas coins are taken to cover the budget of a reservation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoinSelectionStrategy {
+    /// Take the smallest coin that covers the remaining budget, or the largest coin if none does.
+    /// This keeps the number of coins per reservation low without wasting the large coins.
+    #[default]
+    BestFit,
+    /// Always take the largest coin.
+    LargestFirst,
+    /// Always take the smallest coin, which uses up low-balance coins first.
+    SmallestFirst,
+}
+
+impl CoinSelectionStrategy {
+    pub const ALL: [CoinSelectionStrategy; 3] = [
+        CoinSelectionStrategy::BestFit,
+        CoinSelectionStrategy::LargestFirst,
+        CoinSelectionStrategy::SmallestFirst,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CoinSelectionStrategy::BestFit => "best-fit",
+            CoinSelectionStrategy::LargestFirst => "largest-first",
+            CoinSelectionStrategy::SmallestFirst => "smallest-first",
         }
     }
 }
@@ -89,8 +276,203 @@ impl Default for GasStationStorageConfig {
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum TxSignerConfig {
-    Local { keypair: IotaKeyPair },
-    Sidecar { sidecar_url: String },
+    Local {
+        keypair: IotaKeyPair,
+    },
+    Sidecar(SidecarSignerConfig),
+    /// Loads the key pair from a key file, so that the config never contains key material.
+    /// Both passphrase-encrypted key files, as created by `tool create-keystore`, and keystores
+    /// of the IOTA CLI are accepted.
+    #[serde(rename_all = "kebab-case")]
+    Keystore {
+        path: PathBuf,
+        /// Address of the key to use, required when the keystore holds several keys.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        address: Option<IotaAddress>,
+        /// Environment variable holding the passphrase of an encrypted key file.
+        #[serde(default = "default_passphrase_env")]
+        passphrase_env: String,
+        /// File descriptor to read the passphrase from, instead of `passphrase_env`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase_fd: Option<i32>,
+    },
+    /// Signs with a secp256k1 or secp256r1 key held by a PKCS#11 token, e.g. a hardware or
+    /// cloud HSM.
+    #[serde(rename_all = "kebab-case")]
+    Pkcs11 {
+        /// Path to the PKCS#11 library of the HSM vendor.
+        module_path: PathBuf,
+        token_label: String,
+        /// Label of the key pair. The private and the public key must both carry it.
+        key_label: String,
+        /// Environment variable holding the user PIN of the token.
+        #[serde(default = "default_pkcs11_pin_env")]
+        pin_env: String,
+        /// Number of sessions opened on the token to sign concurrently.
+        #[serde(default = "default_pkcs11_num_sessions")]
+        num_sessions: usize,
+    },
+    /// Signs for a k-of-n multisig address with the member signers. The address of the gas
+    /// station is the multisig address.
+    Multisig {
+        members: Vec<MultisigMemberConfig>,
+        /// Sum of the member weights required for a valid signature.
+        threshold: u16,
+    },
+}
+
+/// One or more replicas of a signing sidecar, e.g. the [KMS sidecar](../sample_kms_sidecar/).
+/// Requests go to the first healthy replica in the order of the list, and fail over to the next
+/// one when it fails.
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SidecarSignerConfig {
+    /// URL of a single sidecar, kept for compatibility with older configs.
+    #[serde(
+        alias = "sidecar_url",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sidecar_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecar_urls: Vec<String>,
+    /// Timeout of a request to a sidecar, in milliseconds.
+    #[serde(default = "default_sidecar_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    /// Timeout to connect to a sidecar, in milliseconds.
+    #[serde(default = "default_sidecar_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// How many times the sidecars are asked for their address at startup, with an exponential
+    /// backoff in between, before giving up.
+    #[serde(default = "default_sidecar_startup_max_attempts")]
+    pub startup_max_attempts: u32,
+    /// How often the health of every replica is probed, in seconds.
+    #[serde(default = "default_sidecar_health_check_interval_sec")]
+    pub health_check_interval_sec: u64,
+    /// Static headers sent with every request, e.g. an API key.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Optional environment variable holding a token sent as `Authorization: Bearer <token>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer_token_env: Option<String>,
+    /// Optional TLS settings for `https` sidecars, with a client certificate for mutual TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<ClientTlsConfig>,
+}
+
+impl SidecarSignerConfig {
+    pub fn new(sidecar_url: String) -> Self {
+        Self {
+            sidecar_url: None,
+            sidecar_urls: vec![sidecar_url],
+            request_timeout_ms: DEFAULT_SIDECAR_REQUEST_TIMEOUT_MS,
+            connect_timeout_ms: DEFAULT_SIDECAR_CONNECT_TIMEOUT_MS,
+            startup_max_attempts: DEFAULT_SIDECAR_STARTUP_MAX_ATTEMPTS,
+            health_check_interval_sec: DEFAULT_SIDECAR_HEALTH_CHECK_INTERVAL_SEC,
+            headers: BTreeMap::new(),
+            bearer_token_env: None,
+            tls: None,
+        }
+    }
+
+    /// All configured sidecar URLs, in order of preference.
+    pub fn urls(&self) -> Vec<String> {
+        self.sidecar_url
+            .iter()
+            .chain(self.sidecar_urls.iter())
+            .map(|url| url.trim_end_matches('/').to_string())
+            .collect()
+    }
+}
+
+fn default_sidecar_request_timeout_ms() -> u64 {
+    DEFAULT_SIDECAR_REQUEST_TIMEOUT_MS
+}
+
+fn default_sidecar_connect_timeout_ms() -> u64 {
+    DEFAULT_SIDECAR_CONNECT_TIMEOUT_MS
+}
+
+fn default_sidecar_startup_max_attempts() -> u32 {
+    DEFAULT_SIDECAR_STARTUP_MAX_ATTEMPTS
+}
+
+fn default_sidecar_health_check_interval_sec() -> u64 {
+    DEFAULT_SIDECAR_HEALTH_CHECK_INTERVAL_SEC
+}
+
+/// TLS options of the gas station when it connects to a service, like a sidecar signer or a hook.
+#[serde_as]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ClientTlsConfig {
+    /// Optional path to PEM encoded CA certificates to trust besides the system ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<PathBuf>,
+    /// Optional path to the PEM encoded client certificate chain, for mutual TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<PathBuf>,
+    /// Path to the PEM encoded PKCS#8 private key of the client certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<PathBuf>,
+}
+
+impl ClientTlsConfig {
+    /// Configures `builder` to use rustls with the CA and client certificates of this config.
+    pub fn configure(
+        &self,
+        mut builder: reqwest::ClientBuilder,
+    ) -> anyhow::Result<reqwest::ClientBuilder> {
+        builder = builder.use_rustls_tls();
+        if let Some(ca_cert_path) = &self.ca_cert_path {
+            let pem = std::fs::read(ca_cert_path)
+                .with_context(|| format!("Failed to read {}", ca_cert_path.display()))?;
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+        }
+        match (&self.client_cert_path, &self.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let mut pem = std::fs::read(cert_path)
+                    .with_context(|| format!("Failed to read {}", cert_path.display()))?;
+                pem.extend(
+                    std::fs::read(key_path)
+                        .with_context(|| format!("Failed to read {}", key_path.display()))?,
+                );
+                builder = builder.identity(reqwest::Identity::from_pem(&pem)?);
+            }
+            (None, None) => {}
+            _ => bail!("Both client-cert-path and client-key-path are required for mutual TLS"),
+        }
+        Ok(builder)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MultisigMemberConfig {
+    pub signer: TxSignerConfig,
+    #[serde(default = "default_multisig_weight")]
+    pub weight: u8,
+    /// Public key of the member, base64 encoded with its scheme flag. Required for signers
+    /// that don't hold the key locally, like the sidecar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<PublicKey>,
+}
+
+fn default_multisig_weight() -> u8 {
+    1
+}
+
+fn default_pkcs11_pin_env() -> String {
+    PKCS11_PIN_ENV_NAME.to_string()
+}
+
+fn default_pkcs11_num_sessions() -> usize {
+    DEFAULT_PKCS11_NUM_SESSIONS
+}
+
+fn default_passphrase_env() -> String {
+    KEYSTORE_PASSPHRASE_ENV_NAME.to_string()
 }
 
 impl Default for TxSignerConfig {
@@ -106,7 +488,57 @@ impl TxSignerConfig {
     pub async fn new_signer(self) -> Arc<dyn TxSigner> {
         match self {
             TxSignerConfig::Local { keypair } => TestTxSigner::new(keypair),
-            TxSignerConfig::Sidecar { sidecar_url } => SidecarTxSigner::new(sidecar_url).await,
+            TxSignerConfig::Sidecar(config) => SidecarTxSigner::new(config)
+                .await
+                .unwrap_or_else(|err| panic!("Failed to initialize the sidecar signer: {:?}", err)),
+            TxSignerConfig::Keystore {
+                path,
+                address,
+                passphrase_env,
+                passphrase_fd,
+            } => {
+                let keypair = load_keypair(&path, address, || {
+                    read_passphrase(&passphrase_env, passphrase_fd)
+                })
+                .unwrap_or_else(|err| panic!("Failed to load the keystore: {:?}", err));
+                TestTxSigner::new(keypair)
+            }
+            TxSignerConfig::Pkcs11 {
+                module_path,
+                token_label,
+                key_label,
+                pin_env,
+                num_sessions,
+            } => {
+                let pin = std::env::var(&pin_env)
+                    .unwrap_or_else(|_| panic!("The PKCS#11 PIN is not set in {}", pin_env));
+                Pkcs11TxSigner::new(&module_path, &token_label, &key_label, pin, num_sessions)
+                    .unwrap_or_else(|err| {
+                        panic!("Failed to initialize the PKCS#11 signer: {:?}", err)
+                    })
+            }
+            TxSignerConfig::Multisig { members, threshold } => {
+                let mut multisig_members = Vec::with_capacity(members.len());
+                for member in members {
+                    let signer = Box::pin(member.signer.new_signer()).await;
+                    let public_key = member
+                        .public_key
+                        .or_else(|| signer.get_public_key())
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "The public key of the multisig member {} must be configured",
+                                signer.get_address()
+                            )
+                        });
+                    multisig_members.push(MultisigMember {
+                        signer,
+                        public_key,
+                        weight: member.weight,
+                    });
+                }
+                MultisigTxSigner::new(multisig_members, threshold)
+                    .unwrap_or_else(|err| panic!("Invalid multisig signer: {:?}", err))
+            }
         }
     }
 }
@@ -121,6 +553,20 @@ pub struct CoinInitConfig {
     /// requires initialization, i.e. splitting into smaller coins and add them to the Gas Station.
     /// This is in seconds.
     pub refresh_interval_sec: u64,
+    /// How often the balance of the sponsor account is polled, in seconds. A refresh starts
+    /// right away when the balance grew by enough to initialize a new coin, so that funds sent
+    /// to the sponsor are added to the pool without waiting for `refresh_interval_sec`.
+    /// Set it to 0 to disable the polling.
+    #[serde(default = "default_balance_poll_interval_sec")]
+    pub balance_poll_interval_sec: u64,
+    /// Optionally split new coins into several balances that match the budgets requested by
+    /// clients, instead of `target_init_balance` only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adaptive_sizing: Option<AdaptiveCoinSizingConfig>,
+}
+
+fn default_balance_poll_interval_sec() -> u64 {
+    DEFAULT_BALANCE_POLL_INTERVAL_SEC
 }
 
 impl Default for CoinInitConfig {
@@ -128,6 +574,331 @@ impl Default for CoinInitConfig {
         CoinInitConfig {
             target_init_balance: DEFAULT_INIT_COIN_BALANCE,
             refresh_interval_sec: DEFAULT_COIN_POOL_REFRESH_INTERVAL_SEC,
+            balance_poll_interval_sec: DEFAULT_BALANCE_POLL_INTERVAL_SEC,
+            adaptive_sizing: None,
         }
     }
 }
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AdaptiveCoinSizingConfig {
+    /// The denominations are only derived from the requested budgets once this many requests
+    /// were observed by all instances together. Until then, the last persisted denominations or `target_init_balance`
+    /// are used.
+    pub min_sample_count: u64,
+    /// Upper bound of the number of different coin balances.
+    pub max_denominations: usize,
+}
+
+impl Default for AdaptiveCoinSizingConfig {
+    fn default() -> Self {
+        AdaptiveCoinSizingConfig {
+            min_sample_count: DEFAULT_MIN_BUDGET_SAMPLE_COUNT,
+            max_denominations: DEFAULT_MAX_COIN_DENOMINATIONS,
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PoolReconciliationConfig {
+    /// How often the pool is compared with the coins owned by the sponsor on chain, in seconds.
+    pub interval_sec: u64,
+}
+
+impl Default for PoolReconciliationConfig {
+    fn default() -> Self {
+        PoolReconciliationConfig {
+            interval_sec: DEFAULT_POOL_RECONCILIATION_INTERVAL_SEC,
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CoinConsolidationConfig {
+    /// How often the pool is checked for low-balance coins, in seconds.
+    pub interval_sec: u64,
+    /// Coins with a balance below this are merged, in NANOS.
+    pub dust_balance: u64,
+    /// Coins are only merged once there are at least this many low-balance coins in the pool.
+    pub min_coin_count: usize,
+}
+
+impl Default for CoinConsolidationConfig {
+    fn default() -> Self {
+        CoinConsolidationConfig {
+            interval_sec: DEFAULT_COIN_CONSOLIDATION_INTERVAL_SEC,
+            dust_balance: DEFAULT_DUST_COIN_BALANCE,
+            min_coin_count: DEFAULT_MIN_DUST_COIN_COUNT,
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LowBalanceAlertConfig {
+    /// How often the pool is checked against the low-water marks, in seconds.
+    #[serde(default = "default_low_balance_check_interval_sec")]
+    pub check_interval_sec: u64,
+    /// Alert when fewer coins than this are available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_coin_count: Option<usize>,
+    /// Alert when the total balance of the available coins drops below this, in NANOS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_total_balance: Option<u64>,
+    /// Optional URL that is sent a POST request with a JSON body when a mark is crossed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<Url>,
+    /// Optional transfer of new funds from a treasury when a mark is crossed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refill: Option<RefillConfig>,
+}
+
+fn default_low_balance_check_interval_sec() -> u64 {
+    DEFAULT_LOW_BALANCE_CHECK_INTERVAL_SEC
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RefillConfig {
+    /// Signer of the treasury address that the funds are transferred from.
+    pub treasury_signer_config: TxSignerConfig,
+    /// Amount transferred to the sponsor per refill, in NANOS. It should be at least
+    /// `coin-init-config.target-init-balance` times 200, so that the coin init refresh picks the
+    /// new coin up and splits it.
+    pub amount: u64,
+    /// Minimum time between two refills, in seconds, so that the new funds are split into the pool
+    /// before the pool is refilled again.
+    #[serde(default = "default_refill_cooldown_sec")]
+    pub cooldown_sec: u64,
+}
+
+fn default_refill_cooldown_sec() -> u64 {
+    DEFAULT_REFILL_COOLDOWN_SEC
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SignerRotationConfig {
+    /// Signer of the sponsor address that is being retired. Its outstanding reservations can
+    /// still be executed, then its funds are moved to the sponsor of `signer-config`.
+    pub previous_signer_config: TxSignerConfig,
+    /// How long to wait for the outstanding reservations of the previous sponsor before moving
+    /// its funds, in seconds. The rotation is retried if some are still outstanding.
+    #[serde(default = "default_rotation_wait_timeout_sec")]
+    pub wait_timeout_sec: u64,
+}
+
+fn default_rotation_wait_timeout_sec() -> u64 {
+    DEFAULT_ROTATION_WAIT_TIMEOUT_SEC
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuditLogConfig {
+    /// Path of the audit log file. It is created if it doesn't exist, and verified at startup
+    /// otherwise.
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExecutionNotificationConfig {
+    /// Optional webhook that is sent every signed transaction, in addition to the webhooks in
+    /// the `notify` option of the access rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<HookAction>,
+    /// Longest delay between two attempts to deliver a notification, in seconds.
+    #[serde(default = "default_notification_max_retry_interval_sec")]
+    pub max_retry_interval_sec: u64,
+    /// How often the delivery of a notification is attempted, before it is moved to the dead
+    /// letters.
+    #[serde(default = "default_notification_max_attempts")]
+    pub max_attempts: u32,
+}
+
+impl Default for ExecutionNotificationConfig {
+    fn default() -> Self {
+        Self {
+            webhook: None,
+            max_retry_interval_sec: DEFAULT_NOTIFICATION_MAX_RETRY_INTERVAL_SEC,
+            max_attempts: DEFAULT_NOTIFICATION_MAX_ATTEMPTS,
+        }
+    }
+}
+
+fn default_notification_max_retry_interval_sec() -> u64 {
+    DEFAULT_NOTIFICATION_MAX_RETRY_INTERVAL_SEC
+}
+
+fn default_notification_max_attempts() -> u32 {
+    DEFAULT_NOTIFICATION_MAX_ATTEMPTS
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CorsConfig {
+    /// Origins that are allowed to call the RPC server, e.g. `https://app.example.com`.
+    /// `*` allows any origin.
+    pub allowed_origins: Vec<String>,
+    /// Request headers that are allowed in cross-origin requests. `*` allows any header.
+    #[serde(default = "default_cors_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+    /// HTTP methods that are allowed in cross-origin requests. `*` allows any method.
+    #[serde(default = "default_cors_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    /// How long browsers may cache the result of a preflight request, in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_sec: Option<u64>,
+}
+
+fn default_cors_allowed_headers() -> Vec<String> {
+    vec!["authorization".to_string(), "content-type".to_string()]
+}
+
+fn default_cors_allowed_methods() -> Vec<String> {
+    vec!["GET".to_string(), "POST".to_string()]
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![],
+            allowed_headers: default_cors_allowed_headers(),
+            allowed_methods: default_cors_allowed_methods(),
+            max_age_sec: None,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// Builds the CORS layer for the RPC server, failing on malformed origins, headers or methods.
+    pub fn to_layer(&self) -> anyhow::Result<CorsLayer> {
+        let allow_origin: AllowOrigin = if self.allowed_origins.iter().any(|o| o == "*") {
+            Any.into()
+        } else {
+            AllowOrigin::list(
+                self.allowed_origins
+                    .iter()
+                    .map(|origin| axum::http::HeaderValue::from_str(origin))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+        };
+        let allow_headers: AllowHeaders = if self.allowed_headers.iter().any(|h| h == "*") {
+            Any.into()
+        } else {
+            AllowHeaders::list(
+                self.allowed_headers
+                    .iter()
+                    .map(|header| axum::http::HeaderName::from_str(header))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+        };
+        let allow_methods: AllowMethods = if self.allowed_methods.iter().any(|m| m == "*") {
+            Any.into()
+        } else {
+            AllowMethods::list(
+                self.allowed_methods
+                    .iter()
+                    .map(|method| axum::http::Method::from_str(&method.to_uppercase()))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+        };
+        let mut layer = CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_headers(allow_headers)
+            .allow_methods(allow_methods);
+        if let Some(max_age_sec) = self.max_age_sec {
+            layer = layer.max_age(Duration::from_secs(max_age_sec));
+        }
+        Ok(layer)
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BrowserAuthConfig {
+    pub clients: Vec<BrowserClientConfig>,
+}
+
+/// A browser client that authenticates with a public key instead of the bearer secret.
+/// The key is not secret, as it ships with the frontend code, so every transaction of the client
+/// must additionally be allowed by one of its access rules.
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BrowserClientConfig {
+    /// The origin the requests must come from, e.g. `https://app.example.com`.
+    pub origin: String,
+    /// The key sent by the client as bearer token.
+    pub public_key: String,
+    /// Rules that are checked before the global access controller. Transactions that are not
+    /// explicitly allowed by any of the rules are denied.
+    pub access_rules: Vec<AccessRule>,
+    /// Limits of the gas reservations of the client, which are made before any access rule can
+    /// be checked.
+    #[serde(default)]
+    pub reservation_limits: BrowserReservationLimits,
+}
+
+/// Limits of the gas reservations of a browser client, so that a client can't lock up the pool.
+/// They are enforced by every gas station instance on its own.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct BrowserReservationLimits {
+    /// Highest gas budget of a single reservation.
+    #[serde(default = "default_browser_max_gas_budget")]
+    pub max_gas_budget: u64,
+    /// Most reservations that are neither executed nor expired at the same time.
+    #[serde(default = "default_browser_max_concurrent_reservations")]
+    pub max_concurrent_reservations: usize,
+    /// Most reservations within a minute.
+    #[serde(default = "default_browser_max_reservations_per_minute")]
+    pub max_reservations_per_minute: u32,
+}
+
+impl Default for BrowserReservationLimits {
+    fn default() -> Self {
+        Self {
+            max_gas_budget: DEFAULT_BROWSER_MAX_GAS_BUDGET,
+            max_concurrent_reservations: DEFAULT_BROWSER_MAX_CONCURRENT_RESERVATIONS,
+            max_reservations_per_minute: DEFAULT_BROWSER_MAX_RESERVATIONS_PER_MINUTE,
+        }
+    }
+}
+
+fn default_browser_max_gas_budget() -> u64 {
+    DEFAULT_BROWSER_MAX_GAS_BUDGET
+}
+
+fn default_browser_max_concurrent_reservations() -> usize {
+    DEFAULT_BROWSER_MAX_CONCURRENT_RESERVATIONS
+}
+
+fn default_browser_max_reservations_per_minute() -> u32 {
+    DEFAULT_BROWSER_MAX_RESERVATIONS_PER_MINUTE
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TlsConfig {
+    /// Path to the PEM encoded certificate chain of the server.
+    pub cert_path: PathBuf,
+    /// Path to the PEM encoded private key of the server.
+    pub key_path: PathBuf,
+    /// Optional path to PEM encoded CA certificates. If specified, clients must present a
+    /// certificate signed by one of them, and its subject is used as the caller identity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ca_path: Option<PathBuf>,
+}
@@ -1,6 +1,12 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use axum::http::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use utoipa::ToSchema;
+
 #[macro_export]
 macro_rules! retry_with_max_attempts {
     ($func:expr, $max_attempts:expr) => {{
@@ -33,3 +39,204 @@ pub fn generate_event_id() -> String {
     use uuid::Uuid;
     Uuid::new_v4().to_string()
 }
+
+/// Stable, machine-readable error codes returned by the RPC server in the `code` field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, JsonSchema, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    Unauthorized,
+    InvalidRequest,
+    InsufficientPoolBalance,
+    DailyCapExceeded,
+    AccessDenied,
+    AccessControllerFailure,
+    HookError,
+    InvalidTxBytes,
+    ReservationExpired,
+    PaymentMismatch,
+    SignerFailure,
+    InvalidUserSignature,
+    InvalidSponsorSignature,
+    FullnodeFailure,
+    NotFound,
+    Draining,
+    RateLimited,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::InvalidRequest
+            | ErrorCode::InvalidTxBytes
+            | ErrorCode::PaymentMismatch
+            | ErrorCode::InvalidUserSignature => StatusCode::BAD_REQUEST,
+            ErrorCode::InsufficientPoolBalance | ErrorCode::Draining => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ErrorCode::DailyCapExceeded | ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::AccessDenied => StatusCode::FORBIDDEN,
+            ErrorCode::HookError | ErrorCode::FullnodeFailure => StatusCode::BAD_GATEWAY,
+            ErrorCode::ReservationExpired => StatusCode::GONE,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::AccessControllerFailure
+            | ErrorCode::SignerFailure
+            | ErrorCode::InvalidSponsorSignature
+            | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Typed error of the Gas Station. Each variant maps to exactly one [`ErrorCode`] and carries
+/// a human-readable message, which is what gets displayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GasStationError {
+    Unauthorized(String),
+    InvalidRequest(String),
+    InsufficientPoolBalance(String),
+    DailyCapExceeded(String),
+    AccessDenied(String),
+    AccessControllerFailure(String),
+    HookError(String),
+    InvalidTxBytes(String),
+    ReservationExpired(String),
+    PaymentMismatch(String),
+    SignerFailure(String),
+    InvalidUserSignature(String),
+    InvalidSponsorSignature(String),
+    FullnodeFailure(String),
+    NotFound(String),
+    Draining(String),
+    RateLimited(String),
+    Internal(String),
+}
+
+impl GasStationError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match code {
+            ErrorCode::Unauthorized => Self::Unauthorized(message),
+            ErrorCode::InvalidRequest => Self::InvalidRequest(message),
+            ErrorCode::InsufficientPoolBalance => Self::InsufficientPoolBalance(message),
+            ErrorCode::DailyCapExceeded => Self::DailyCapExceeded(message),
+            ErrorCode::AccessDenied => Self::AccessDenied(message),
+            ErrorCode::AccessControllerFailure => Self::AccessControllerFailure(message),
+            ErrorCode::HookError => Self::HookError(message),
+            ErrorCode::InvalidTxBytes => Self::InvalidTxBytes(message),
+            ErrorCode::ReservationExpired => Self::ReservationExpired(message),
+            ErrorCode::PaymentMismatch => Self::PaymentMismatch(message),
+            ErrorCode::SignerFailure => Self::SignerFailure(message),
+            ErrorCode::InvalidUserSignature => Self::InvalidUserSignature(message),
+            ErrorCode::InvalidSponsorSignature => Self::InvalidSponsorSignature(message),
+            ErrorCode::FullnodeFailure => Self::FullnodeFailure(message),
+            ErrorCode::NotFound => Self::NotFound(message),
+            ErrorCode::Draining => Self::Draining(message),
+            ErrorCode::RateLimited => Self::RateLimited(message),
+            ErrorCode::Internal => Self::Internal(message),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Unauthorized(_) => ErrorCode::Unauthorized,
+            Self::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            Self::InsufficientPoolBalance(_) => ErrorCode::InsufficientPoolBalance,
+            Self::DailyCapExceeded(_) => ErrorCode::DailyCapExceeded,
+            Self::AccessDenied(_) => ErrorCode::AccessDenied,
+            Self::AccessControllerFailure(_) => ErrorCode::AccessControllerFailure,
+            Self::HookError(_) => ErrorCode::HookError,
+            Self::InvalidTxBytes(_) => ErrorCode::InvalidTxBytes,
+            Self::ReservationExpired(_) => ErrorCode::ReservationExpired,
+            Self::PaymentMismatch(_) => ErrorCode::PaymentMismatch,
+            Self::SignerFailure(_) => ErrorCode::SignerFailure,
+            Self::InvalidUserSignature(_) => ErrorCode::InvalidUserSignature,
+            Self::InvalidSponsorSignature(_) => ErrorCode::InvalidSponsorSignature,
+            Self::FullnodeFailure(_) => ErrorCode::FullnodeFailure,
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::Draining(_) => ErrorCode::Draining,
+            Self::RateLimited(_) => ErrorCode::RateLimited,
+            Self::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Unauthorized(message)
+            | Self::InvalidRequest(message)
+            | Self::InsufficientPoolBalance(message)
+            | Self::DailyCapExceeded(message)
+            | Self::AccessDenied(message)
+            | Self::AccessControllerFailure(message)
+            | Self::HookError(message)
+            | Self::InvalidTxBytes(message)
+            | Self::ReservationExpired(message)
+            | Self::PaymentMismatch(message)
+            | Self::SignerFailure(message)
+            | Self::InvalidUserSignature(message)
+            | Self::InvalidSponsorSignature(message)
+            | Self::FullnodeFailure(message)
+            | Self::NotFound(message)
+            | Self::Draining(message)
+            | Self::RateLimited(message)
+            | Self::Internal(message) => message,
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.code().status_code()
+    }
+}
+
+impl Display for GasStationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for GasStationError {}
+
+impl From<anyhow::Error> for GasStationError {
+    /// Uses the first typed error found in the chain to determine the code, and falls back to
+    /// `Internal` for untyped errors. The message is always the one of the outermost error.
+    fn from(err: anyhow::Error) -> Self {
+        let code = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<GasStationError>())
+            .map(|typed| typed.code())
+            .unwrap_or(ErrorCode::Internal);
+        Self::new(code, err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_code_serde() {
+        let serialized = serde_json::to_string(&ErrorCode::DailyCapExceeded).unwrap();
+        assert_eq!(serialized, "\"DAILY_CAP_EXCEEDED\"");
+        let deserialized: ErrorCode = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, ErrorCode::DailyCapExceeded);
+    }
+
+    #[test]
+    fn test_error_from_anyhow() {
+        let typed: anyhow::Error = GasStationError::ReservationExpired("expired".into()).into();
+        let err = GasStationError::from(typed);
+        assert_eq!(err.code(), ErrorCode::ReservationExpired);
+        assert_eq!(err.to_string(), "expired");
+
+        let wrapped = Err::<(), _>(GasStationError::HookError("hook down".into()))
+            .context("while checking access")
+            .unwrap_err();
+        let err = GasStationError::from(wrapped);
+        assert_eq!(err.code(), ErrorCode::HookError);
+        assert_eq!(err.to_string(), "while checking access");
+
+        let err = GasStationError::from(anyhow::anyhow!("untyped"));
+        assert_eq!(err, GasStationError::Internal("untyped".into()));
+    }
+}
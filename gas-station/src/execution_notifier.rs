@@ -0,0 +1,780 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::access_controller::hook::HookAction;
+use crate::access_controller::AccessController;
+use crate::metrics::ExecutionNotificationMetrics;
+use crate::retry_with_max_attempts;
+use crate::storage::{OutboxNotification, Storage};
+use crate::types::ReservationID;
+use arc_swap::ArcSwap;
+use fastcrypto::encoding::{Encoding, Hex};
+use futures_util::future::join_all;
+use iota_json_rpc_types::{
+    IotaExecutionStatus, IotaTransactionBlockEffects, IotaTransactionBlockEffectsAPI,
+};
+use iota_types::base_types::IotaAddress;
+use iota_types::digests::TransactionDigest;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// How often the outbox is checked for notifications that are due, if no new notification
+/// arrives in between.
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum number of notifications delivered at once.
+const DELIVERY_BATCH_SIZE: usize = 100;
+/// How much longer claimed notifications are not claimed again than the longest timeout of the
+/// webhooks, which bounds how long a delivery takes.
+const DELIVERY_LEASE_MARGIN_MS: u64 = 30 * 1000;
+/// Delay before the first retry of a notification. It doubles with every further retry.
+const INITIAL_RETRY_INTERVAL_MS: u64 = 1000;
+/// How much later than the end of the execution lease the notification of a transaction is
+/// delivered as pending, if its outcome was not recorded, e.g. because the gas station stopped.
+/// The execution has finished or was given up by then.
+const PENDING_NOTIFICATION_MARGIN: Duration = Duration::from_secs(5 * 60);
+/// How often the storage is tried when the outcome of a transaction is recorded.
+const RESOLVE_MAX_ATTEMPTS: usize = 3;
+
+/// The JSON body posted to the webhooks after a transaction was submitted.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionNotification {
+    pub transaction_digest: TransactionDigest,
+    pub reservation_id: ReservationID,
+    pub sender: IotaAddress,
+    pub sponsor: IotaAddress,
+    pub state: ExecutionState,
+    /// Whether the transaction succeeded on chain, if it was executed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<IotaExecutionStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<u64>,
+    /// The rule or policy of the access controller that allowed the transaction.
+    pub allowed_by: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effects: Option<IotaTransactionBlockEffects>,
+    /// Why the outcome of the transaction is unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the state of the transaction was recorded, in milliseconds since the Unix epoch.
+    pub timestamp_ms: i64,
+}
+
+/// What the gas station knows about a signed transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExecutionState {
+    /// The transaction was signed, but its outcome was not recorded, e.g. because the gas station
+    /// stopped during the execution. It may have been executed.
+    Pending,
+    /// The transaction was executed. Its status tells whether it succeeded on chain.
+    Executed,
+    /// Submitting the signed transaction failed, e.g. because the fullnode timed out. It may still
+    /// be executed.
+    Unknown,
+}
+
+impl ExecutionNotification {
+    /// The notification of a transaction that is about to be signed and submitted.
+    pub fn pending(
+        transaction_digest: TransactionDigest,
+        reservation_id: ReservationID,
+        sender: IotaAddress,
+        sponsor: IotaAddress,
+        allowed_by: String,
+    ) -> Self {
+        Self {
+            transaction_digest,
+            reservation_id,
+            sender,
+            sponsor,
+            state: ExecutionState::Pending,
+            status: None,
+            gas_used: None,
+            allowed_by,
+            effects: None,
+            error: None,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// The notification of the executed transaction with `effects`.
+    pub fn executed(self, effects: IotaTransactionBlockEffects) -> Self {
+        Self {
+            state: ExecutionState::Executed,
+            status: Some(effects.status().clone()),
+            gas_used: Some(effects.gas_cost_summary().gas_used()),
+            effects: Some(effects),
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            ..self
+        }
+    }
+
+    /// The notification of a signed transaction that could not be submitted.
+    pub fn unknown(self, error: String) -> Self {
+        Self {
+            state: ExecutionState::Unknown,
+            error: Some(error),
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            ..self
+        }
+    }
+}
+
+/// The notifications queued for a transaction before it is submitted, which are updated with its
+/// outcome by `ExecutionNotifier::resolve`.
+pub struct PendingNotifications {
+    notification: ExecutionNotification,
+    /// The ids of the notifications in the outbox, and the ids of their webhooks.
+    entries: Vec<(u64, String)>,
+}
+
+/// A notification in the outbox, together with the id of the webhook it is delivered to. The
+/// webhook is looked up in the configuration on delivery, so that its credentials are not stored
+/// and changes of its options apply to queued notifications.
+#[derive(Serialize, Deserialize)]
+struct OutboxEntry {
+    webhook: String,
+    notification: serde_json::Value,
+}
+
+/// Identifies a webhook by the hash of its URL, which may contain credentials in its query.
+fn webhook_id(webhook: &HookAction) -> String {
+    Hex::encode(Sha256::digest(webhook.url.as_str().as_bytes()))
+}
+
+/// Queues notifications about executed transactions in the outbox of the storage. They are
+/// delivered to their webhooks by the `NotificationDelivery` task.
+pub struct ExecutionNotifier {
+    storage: Arc<dyn Storage>,
+    /// Notified of every executed transaction, in addition to the webhooks of the rules.
+    webhook: Option<HookAction>,
+    /// The access controller with the webhooks of the rules, which may be reloaded.
+    access_controller: Arc<ArcSwap<AccessController>>,
+    /// The webhooks of the rules of browser clients, which are not reloaded.
+    client_webhooks: Vec<HookAction>,
+    /// Wakes up the delivery task when notifications are queued.
+    delivery_notifier: Arc<Notify>,
+    /// Delay after which pending notifications are delivered, if their outcome was not recorded.
+    pending_delay: Duration,
+}
+
+impl ExecutionNotifier {
+    pub fn new(
+        storage: Arc<dyn Storage>,
+        webhook: Option<HookAction>,
+        access_controller: Arc<ArcSwap<AccessController>>,
+        client_webhooks: Vec<HookAction>,
+        execution_lease: Duration,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            storage,
+            webhook,
+            access_controller,
+            client_webhooks,
+            delivery_notifier: Arc::new(Notify::new()),
+            pending_delay: execution_lease + PENDING_NOTIFICATION_MARGIN,
+        })
+    }
+
+    /// Returns the configured webhook with the id `id`, if it is still configured. Clones of a
+    /// webhook share its connections.
+    fn resolve_webhook(&self, id: &str) -> Option<HookAction> {
+        self.webhooks(|webhooks| webhooks.find(|webhook| webhook_id(webhook) == id).cloned())
+    }
+
+    /// How long notifications are claimed for delivery. It is longer than a delivery to any of the
+    /// configured webhooks can take, so that a notification is not delivered twice at once.
+    fn delivery_lease_ms(&self) -> u64 {
+        let max_timeout_ms = self.webhooks(|webhooks| {
+            webhooks
+                .map(|webhook| webhook.options.timeout_ms)
+                .max()
+                .unwrap_or_default()
+        });
+        max_timeout_ms.saturating_add(DELIVERY_LEASE_MARGIN_MS)
+    }
+
+    /// Calls `f` with all configured webhooks, including those of the current access rules.
+    fn webhooks<T>(&self, f: impl FnOnce(&mut dyn Iterator<Item = &HookAction>) -> T) -> T {
+        let access_controller = self.access_controller.load();
+        let mut webhooks = self.webhook.iter().chain(&self.client_webhooks).chain(
+            access_controller
+                .rules
+                .iter()
+                .filter_map(|rule| rule.notify.as_ref()),
+        );
+        f(&mut webhooks)
+    }
+
+    /// Queues the pending notification of a transaction for the global webhook and the webhooks of
+    /// the rules that allowed the transaction, before the transaction is signed. If the outcome of
+    /// the transaction is not recorded with `resolve`, the notification is delivered as pending
+    /// after a delay. The transaction must not be submitted if this fails, as its notification
+    /// would be lost.
+    pub async fn queue_pending(
+        &self,
+        notification: ExecutionNotification,
+        rule_webhooks: impl IntoIterator<Item = HookAction>,
+    ) -> anyhow::Result<PendingNotifications> {
+        let entries = self
+            .queue(
+                &serde_json::to_value(&notification)?,
+                rule_webhooks,
+                self.pending_delay.as_millis() as u64,
+            )
+            .await?;
+        Ok(PendingNotifications {
+            notification,
+            entries,
+        })
+    }
+
+    /// Records the outcome of the transaction of `pending` and makes its notifications due. If
+    /// the transaction was never `signed`, its notifications are removed. If the outcome can't be
+    /// recorded, the notifications are delivered as pending.
+    pub async fn resolve(
+        &self,
+        pending: PendingNotifications,
+        signed: bool,
+        result: &anyhow::Result<IotaTransactionBlockEffects>,
+    ) {
+        let transaction_digest = pending.notification.transaction_digest;
+        let notification = match result {
+            Ok(effects) => pending.notification.executed(effects.clone()),
+            Err(err) if signed => pending.notification.unknown(err.to_string()),
+            Err(_) => {
+                for (id, _) in pending.entries {
+                    if let Err(err) = retry_with_max_attempts!(
+                        self.storage.complete_notification(id),
+                        RESOLVE_MAX_ATTEMPTS
+                    ) {
+                        error!(
+                            "Failed to remove the notification of the unsigned transaction {}: {:?}",
+                            transaction_digest, err
+                        );
+                    }
+                }
+                return;
+            }
+        };
+        let notification = match serde_json::to_value(&notification) {
+            Ok(notification) => notification,
+            Err(err) => {
+                error!(
+                    "Failed to serialize the notification of {}: {:?}",
+                    transaction_digest, err
+                );
+                return;
+            }
+        };
+        for (id, webhook) in pending.entries {
+            let result = retry_with_max_attempts!(
+                self.update(id, &webhook, &notification),
+                RESOLVE_MAX_ATTEMPTS
+            );
+            if let Err(err) = result {
+                error!(
+                    "Failed to record the outcome of {} in the outbox, it is delivered as pending: {:?}",
+                    transaction_digest, err
+                );
+            }
+        }
+        self.delivery_notifier.notify_one();
+    }
+
+    /// Adds the notification to the outbox for each distinct webhook, due after `delay_ms`.
+    /// Returns the ids of the notifications and their webhooks.
+    async fn queue(
+        &self,
+        notification: &serde_json::Value,
+        rule_webhooks: impl IntoIterator<Item = HookAction>,
+        delay_ms: u64,
+    ) -> anyhow::Result<Vec<(u64, String)>> {
+        let mut webhooks: Vec<String> = vec![];
+        for webhook in self.webhook.iter().cloned().chain(rule_webhooks) {
+            // A webhook that is configured more than once is notified once.
+            let webhook = webhook_id(&webhook);
+            if !webhooks.contains(&webhook) {
+                webhooks.push(webhook);
+            }
+        }
+        let mut entries = Vec::with_capacity(webhooks.len());
+        for webhook in webhooks {
+            let id = self
+                .storage
+                .add_notification(&Self::outbox_entry(&webhook, notification)?, delay_ms)
+                .await?;
+            entries.push((id, webhook));
+        }
+        if delay_ms == 0 && !entries.is_empty() {
+            self.delivery_notifier.notify_one();
+        }
+        Ok(entries)
+    }
+
+    /// Replaces the notification `id`, or adds it again if it was delivered already.
+    async fn update(
+        &self,
+        id: u64,
+        webhook: &str,
+        notification: &serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let content = Self::outbox_entry(webhook, notification)?;
+        if !self.storage.update_notification(id, &content).await? {
+            self.storage.add_notification(&content, 0).await?;
+        }
+        Ok(())
+    }
+
+    fn outbox_entry(webhook: &str, notification: &serde_json::Value) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(&OutboxEntry {
+            webhook: webhook.to_string(),
+            notification: notification.clone(),
+        })?)
+    }
+}
+
+/// Delivers the notifications of the outbox to their webhooks. Notifications that can't be
+/// delivered are retried with an exponential backoff, until they are delivered or moved to the dead
+/// letters after too many attempts. As the outbox is kept in the storage, notifications are not lost when the gas station restarts, and several
+/// gas station instances can share the delivery.
+pub struct NotificationDelivery {
+    task_handle: JoinHandle<()>,
+    // This is always Some. It is None only after the drop or shutdown method is called.
+    cancel_sender: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl Drop for NotificationDelivery {
+    fn drop(&mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            cancel_sender.send(()).unwrap();
+        }
+    }
+}
+
+struct DeliveryEnv {
+    notifier: Arc<ExecutionNotifier>,
+    max_retry_interval_ms: u64,
+    max_attempts: u32,
+    metrics: Arc<ExecutionNotificationMetrics>,
+}
+
+impl NotificationDelivery {
+    pub fn start(
+        notifier: Arc<ExecutionNotifier>,
+        max_retry_interval: Duration,
+        max_attempts: u32,
+        metrics: Arc<ExecutionNotificationMetrics>,
+    ) -> Self {
+        let delivery_notifier = notifier.delivery_notifier.clone();
+        let env = DeliveryEnv::new(notifier, max_retry_interval, max_attempts, metrics);
+        let (cancel_sender, mut cancel_receiver) = tokio::sync::oneshot::channel();
+        let task_handle = tokio::spawn(async move {
+            loop {
+                env.deliver_due().await;
+                tokio::select! {
+                    _ = tokio::time::sleep(OUTBOX_POLL_INTERVAL) => {}
+                    _ = delivery_notifier.notified() => {}
+                    _ = &mut cancel_receiver => {
+                        info!("Notification delivery task is cancelled");
+                        break;
+                    }
+                }
+            }
+        });
+        Self {
+            task_handle,
+            cancel_sender: Some(cancel_sender),
+        }
+    }
+
+    /// Cancels the delivery task and waits for it to stop.
+    pub async fn shutdown(mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            let _ = cancel_sender.send(());
+        }
+        if let Err(err) = (&mut self.task_handle).await {
+            error!("Notification delivery task failed: {:?}", err);
+        }
+    }
+}
+
+impl DeliveryEnv {
+    fn new(
+        notifier: Arc<ExecutionNotifier>,
+        max_retry_interval: Duration,
+        max_attempts: u32,
+        metrics: Arc<ExecutionNotificationMetrics>,
+    ) -> Self {
+        Self {
+            notifier,
+            max_retry_interval_ms: max_retry_interval.as_millis() as u64,
+            max_attempts,
+            metrics,
+        }
+    }
+
+    fn storage(&self) -> &dyn Storage {
+        self.notifier.storage.as_ref()
+    }
+
+    /// Delivers all notifications that are due.
+    async fn deliver_due(&self) {
+        loop {
+            let notifications = match self
+                .storage()
+                .claim_notifications(DELIVERY_BATCH_SIZE, self.notifier.delivery_lease_ms())
+                .await
+            {
+                Ok(notifications) => notifications,
+                Err(err) => {
+                    error!("Failed to claim notifications from the outbox: {:?}", err);
+                    return;
+                }
+            };
+            let count = notifications.len();
+            join_all(
+                notifications
+                    .into_iter()
+                    .map(|notification| self.deliver(notification)),
+            )
+            .await;
+            if count < DELIVERY_BATCH_SIZE {
+                break;
+            }
+        }
+        match self.storage().get_notification_count().await {
+            Ok(count) => self.metrics.notification_outbox_size.set(count as i64),
+            Err(err) => error!("Failed to get the size of the outbox: {:?}", err),
+        }
+        match self.storage().get_dead_notification_count().await {
+            Ok(count) => self.metrics.notification_dead_letter_size.set(count as i64),
+            Err(err) => error!("Failed to get the number of dead letters: {:?}", err),
+        }
+    }
+
+    async fn deliver(&self, notification: OutboxNotification) {
+        let entry = match serde_json::from_str::<OutboxEntry>(&notification.content) {
+            Ok(entry) => entry,
+            Err(err) => {
+                // It would never be delivered.
+                error!(
+                    "Notification {} in the outbox is malformed: {:?}",
+                    notification.id, err
+                );
+                self.dead_letter(notification.id).await;
+                return;
+            }
+        };
+        let Some(webhook) = self.notifier.resolve_webhook(&entry.webhook) else {
+            error!(
+                "The webhook of notification {} is no longer configured",
+                notification.id
+            );
+            self.dead_letter(notification.id).await;
+            return;
+        };
+        let body = entry.notification;
+        let result = match serde_json::to_vec(&body) {
+            Ok(body) => webhook.send_notification(body).await,
+            Err(err) => Err(err.into()),
+        };
+        match result {
+            Ok(()) => {
+                self.metrics.num_delivered_notifications.inc();
+                self.complete(notification.id).await;
+            }
+            Err(err) if notification.attempts >= self.max_attempts => {
+                self.metrics.num_failed_notification_deliveries.inc();
+                error!(
+                    "Failed to deliver notification {} to {} after {} attempts: {:#}",
+                    notification.id, webhook.url, notification.attempts, err
+                );
+                self.dead_letter(notification.id).await;
+            }
+            Err(err) => {
+                self.metrics.num_failed_notification_deliveries.inc();
+                let delay_ms = retry_interval_ms(notification.attempts, self.max_retry_interval_ms);
+                warn!(
+                    "Failed to deliver notification {} to {} (attempt {}), retrying in {}ms: {:#}",
+                    notification.id, webhook.url, notification.attempts, delay_ms, err
+                );
+                if let Err(err) = self
+                    .storage()
+                    .reschedule_notification(notification.id, delay_ms)
+                    .await
+                {
+                    error!(
+                        "Failed to reschedule notification {}: {:?}",
+                        notification.id, err
+                    );
+                }
+            }
+        }
+    }
+
+    async fn dead_letter(&self, id: u64) {
+        self.metrics.num_dead_lettered_notifications.inc();
+        if let Err(err) = self.storage().dead_letter_notification(id).await {
+            error!(
+                "Failed to move notification {} to the dead letters: {:?}",
+                id, err
+            );
+        }
+    }
+
+    async fn complete(&self, id: u64) {
+        if let Err(err) = self.storage().complete_notification(id).await {
+            error!(
+                "Failed to remove notification {} from the outbox: {:?}",
+                id, err
+            );
+        }
+    }
+}
+
+/// The delay before the next attempt to deliver a notification, after `attempts` failed attempts.
+fn retry_interval_ms(attempts: u32, max_retry_interval_ms: u64) -> u64 {
+    let exponent = attempts.saturating_sub(1).min(32);
+    INITIAL_RETRY_INTERVAL_MS
+        .saturating_mul(1 << exponent)
+        .min(max_retry_interval_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access_controller::hook::HookOptions;
+    use crate::access_controller::policy::AccessPolicy;
+    use crate::access_controller::rule::AccessRuleBuilder;
+    use crate::config::ExecutionConfig;
+    use crate::storage::connect_storage_for_testing;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use parking_lot::Mutex;
+    use url::Url;
+
+    #[tokio::test]
+    async fn test_notification_delivery() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let webhook_url =
+            Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let received = Arc::new(Mutex::new(vec![]));
+        let app = Router::new().route(
+            "/",
+            post({
+                let received = received.clone();
+                move |Json(body): Json<serde_json::Value>| async move {
+                    let mut received = received.lock();
+                    let fail = body["fail"] == true;
+                    received.push(body);
+                    // The first delivery fails, and so do all deliveries of failing notifications.
+                    if received.len() == 1 || fail {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let webhook = HookAction::new(webhook_url);
+        let notifier = ExecutionNotifier::new(
+            storage.clone(),
+            Some(webhook.clone()),
+            Arc::new(ArcSwap::new(Arc::new(AccessController::default()))),
+            vec![],
+            ExecutionConfig::default().lease(),
+        );
+        let notification = serde_json::json!({"transactionDigest": "digest"});
+        // The webhook of the rule is the global webhook, so it is notified once.
+        notifier.queue(&notification, [webhook], 0).await.unwrap();
+        assert_eq!(storage.get_notification_count().await.unwrap(), 1);
+
+        let metrics = ExecutionNotificationMetrics::new_for_testing();
+        let env = DeliveryEnv::new(
+            notifier.clone(),
+            Duration::from_millis(0),
+            3,
+            metrics.clone(),
+        );
+        env.deliver_due().await;
+        assert_eq!(metrics.num_failed_notification_deliveries.get(), 1);
+        assert_eq!(metrics.notification_outbox_size.get(), 1);
+
+        // The failed notification is retried once it is due again.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        env.deliver_due().await;
+        assert_eq!(metrics.num_delivered_notifications.get(), 1);
+        assert_eq!(metrics.notification_outbox_size.get(), 0);
+        assert_eq!(*received.lock(), vec![notification.clone(), notification]);
+
+        // A notification that can't be delivered is moved to the dead letters after the last
+        // attempt.
+        notifier
+            .queue(&serde_json::json!({"fail": true}), [], 0)
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            env.deliver_due().await;
+        }
+        assert_eq!(metrics.num_failed_notification_deliveries.get(), 4);
+        assert_eq!(metrics.num_dead_lettered_notifications.get(), 1);
+        assert_eq!(metrics.notification_outbox_size.get(), 0);
+        assert_eq!(metrics.notification_dead_letter_size.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_outbox_webhook_resolution() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let webhook_url = Url::parse("https://example.org/notify?token=secret").unwrap();
+        let rule = AccessRuleBuilder::new().notify(webhook_url.clone()).build();
+        let access_controller = Arc::new(ArcSwap::new(Arc::new(AccessController::new(
+            AccessPolicy::AllowAll,
+            [rule],
+        ))));
+        let notifier = ExecutionNotifier::new(
+            storage.clone(),
+            None,
+            access_controller.clone(),
+            vec![],
+            ExecutionConfig::default().lease(),
+        );
+        notifier
+            .queue(
+                &serde_json::json!({"transactionDigest": "digest"}),
+                [HookAction::new(webhook_url.clone())],
+                0,
+            )
+            .await
+            .unwrap();
+
+        // The outbox contains the id of the webhook, but not its URL with the credentials.
+        let notifications = storage.claim_notifications(1, 0).await.unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert!(!notifications[0].content.contains("secret"));
+        let entry: OutboxEntry = serde_json::from_str(&notifications[0].content).unwrap();
+        let webhook = notifier.resolve_webhook(&entry.webhook).unwrap();
+        assert_eq!(webhook.url, webhook_url);
+
+        // The webhook is no longer resolved once its rule is removed.
+        access_controller.store(Arc::new(AccessController::default()));
+        assert!(notifier.resolve_webhook(&entry.webhook).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delivery_lease() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let webhook = HookAction::new(Url::parse("https://example.org/notify").unwrap())
+            .with_options(HookOptions {
+                timeout_ms: 1000,
+                ..Default::default()
+            });
+        let access_controller = Arc::new(ArcSwap::new(Arc::new(AccessController::default())));
+        let notifier = ExecutionNotifier::new(
+            storage,
+            Some(webhook),
+            access_controller.clone(),
+            vec![],
+            ExecutionConfig::default().lease(),
+        );
+        assert_eq!(
+            notifier.delivery_lease_ms(),
+            1000 + DELIVERY_LEASE_MARGIN_MS
+        );
+
+        // The lease follows the timeouts of the webhooks of the current rules.
+        let mut rule = AccessRuleBuilder::new().build();
+        rule.notify = Some(
+            HookAction::new(Url::parse("https://example.org/slow").unwrap()).with_options(
+                HookOptions {
+                    timeout_ms: 600_000,
+                    ..Default::default()
+                },
+            ),
+        );
+        access_controller.store(Arc::new(AccessController::new(
+            AccessPolicy::AllowAll,
+            [rule],
+        )));
+        assert_eq!(
+            notifier.delivery_lease_ms(),
+            600_000 + DELIVERY_LEASE_MARGIN_MS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pending_notifications() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let webhook = HookAction::new(Url::parse("https://example.org/notify").unwrap());
+        let notifier = ExecutionNotifier::new(
+            storage.clone(),
+            Some(webhook),
+            Arc::new(ArcSwap::new(Arc::new(AccessController::default()))),
+            vec![],
+            ExecutionConfig::default().lease(),
+        );
+        let notification = ExecutionNotification::pending(
+            TransactionDigest::random(),
+            1,
+            IotaAddress::random_for_testing_only(),
+            sponsor,
+            "policy".to_string(),
+        );
+
+        // The notification of a transaction that was never signed is removed.
+        let pending = notifier
+            .queue_pending(notification.clone(), [])
+            .await
+            .unwrap();
+        assert_eq!(storage.get_notification_count().await.unwrap(), 1);
+        notifier
+            .resolve(pending, false, &Err(anyhow::anyhow!("not signed")))
+            .await;
+        assert_eq!(storage.get_notification_count().await.unwrap(), 0);
+
+        // Pending notifications are not due until their outcome is recorded.
+        let pending = notifier.queue_pending(notification, []).await.unwrap();
+        assert!(storage
+            .claim_notifications(10, 100)
+            .await
+            .unwrap()
+            .is_empty());
+        notifier
+            .resolve(pending, true, &Err(anyhow::anyhow!("timed out")))
+            .await;
+        let claimed = storage.claim_notifications(10, 100).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        let entry: OutboxEntry = serde_json::from_str(&claimed[0].content).unwrap();
+        assert_eq!(entry.notification["state"], "unknown");
+        assert_eq!(entry.notification["error"], "timed out");
+        assert!(entry.notification.get("effects").is_none());
+    }
+
+    #[test]
+    fn test_retry_interval() {
+        assert_eq!(retry_interval_ms(1, 60000), 1000);
+        assert_eq!(retry_interval_ms(2, 60000), 2000);
+        assert_eq!(retry_interval_ms(6, 60000), 32000);
+        assert_eq!(retry_interval_ms(7, 60000), 60000);
+        assert_eq!(retry_interval_ms(100, 60000), 60000);
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::config::ExecutionConfig;
+use crate::errors::GasStationError;
 use crate::iota_client::IotaClient;
 use crate::metrics::GasStationCoreMetrics;
 use crate::rpc::rpc_types::ExecuteTransactionRequestType;
@@ -8,7 +10,6 @@ use crate::storage::Storage;
 use crate::tx_signer::TxSigner;
 use crate::types::{GasCoin, ReservationID};
 use crate::{retry_forever, retry_with_max_attempts};
-use anyhow::bail;
 use iota_json_rpc_types::{IotaTransactionBlockEffects, IotaTransactionBlockEffectsAPI};
 use iota_types::base_types::{IotaAddress, ObjectID, ObjectRef};
 use iota_types::gas_coin::NANOS_PER_IOTA;
@@ -17,6 +18,8 @@ use iota_types::signature::GenericSignature;
 use iota_types::transaction::{
     Argument, Command, Transaction, TransactionData, TransactionDataAPI, TransactionKind,
 };
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tap::TapFallible;
@@ -24,12 +27,13 @@ use tokio::task::JoinHandle;
 use tracing::{debug, error, info};
 
 use super::gas_usage_cap::GasUsageCap;
+use super::signature_verifier::verify_tx_signature;
 
 const EXPIRATION_JOB_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct GasStationContainer {
     inner: Arc<GasStation>,
-    _coin_unlocker_task: JoinHandle<()>,
+    coin_unlocker_task: JoinHandle<()>,
     // This is always Some. It is None only after the drop method is called.
     cancel_sender: Option<tokio::sync::oneshot::Sender<()>>,
 }
@@ -40,6 +44,25 @@ pub struct GasStation {
     iota_client: IotaClient,
     metrics: Arc<GasStationCoreMetrics>,
     gas_usage_cap: Arc<GasUsageCap>,
+    /// The coins of a transaction are leased to its execution for `execution.lease_sec`, which
+    /// covers signing and execution, as they are bounded by `execution.timeout_sec`.
+    execution: ExecutionConfig,
+    /// Pool of the sponsor that is being rotated out, if any. No gas is reserved from it, but the
+    /// transactions of its outstanding reservations can still be executed.
+    previous_sponsor_store: Option<(IotaAddress, Arc<dyn Storage>)>,
+    /// Payment coins of transactions between `ready_for_execution` and the release of their coins,
+    /// by sponsor and reservation. Whoever removes an entry is responsible for completing the
+    /// execution in storage.
+    in_flight_payments: Mutex<HashMap<(IotaAddress, ReservationID), InFlightPayment>>,
+}
+
+/// Payment coins of a transaction that is being executed.
+struct InFlightPayment {
+    coins: Vec<ObjectID>,
+    /// Whether the sponsor signed the transaction. From then on, the transaction may be executed
+    /// at any time, so its coins are only recovered once the execution lease expires unless the
+    /// execution finishes.
+    signed: bool,
 }
 
 impl GasStation {
@@ -49,6 +72,8 @@ impl GasStation {
         iota_client: IotaClient,
         metrics: Arc<GasStationCoreMetrics>,
         gas_usage_cap: Arc<GasUsageCap>,
+        execution: ExecutionConfig,
+        previous_sponsor_store: Option<(IotaAddress, Arc<dyn Storage>)>,
     ) -> Arc<Self> {
         let pool = Self {
             signer,
@@ -56,6 +81,9 @@ impl GasStation {
             iota_client,
             metrics,
             gas_usage_cap,
+            execution,
+            previous_sponsor_store,
+            in_flight_payments: Mutex::new(HashMap::new()),
         };
 
         Arc::new(pool)
@@ -91,12 +119,51 @@ impl GasStation {
         tx_data: TransactionData,
         user_sig: GenericSignature,
         request_type: Option<ExecuteTransactionRequestType>,
+    ) -> anyhow::Result<IotaTransactionBlockEffects> {
+        self.execute_transaction_with_sign_notifier(
+            reservation_id,
+            tx_data,
+            user_sig,
+            request_type,
+            None,
+        )
+        .await
+    }
+
+    /// Same as `execute_transaction`, but notifies `signed_notifier` as soon as the transaction
+    /// has been signed by the sponsor and is about to be submitted to the fullnode.
+    /// If the execution fails before signing, the notifier is dropped without being notified.
+    pub async fn execute_transaction_with_sign_notifier(
+        &self,
+        reservation_id: ReservationID,
+        tx_data: TransactionData,
+        user_sig: GenericSignature,
+        request_type: Option<ExecuteTransactionRequestType>,
+        signed_notifier: Option<tokio::sync::oneshot::Sender<()>>,
     ) -> anyhow::Result<IotaTransactionBlockEffects> {
         let sponsor = tx_data.gas_data().owner;
         if !self.signer.is_valid_address(&sponsor) {
-            bail!("Sponsor {:?} is not registered", sponsor);
+            return Err(GasStationError::InvalidRequest(format!(
+                "Sponsor {:?} is not registered",
+                sponsor
+            ))
+            .into());
         };
         Self::check_transaction_validity(&tx_data)?;
+        // Checked before the reservation is used, so that the client can retry with a valid
+        // signature.
+        if let Err(err) = verify_tx_signature(&tx_data, &user_sig, tx_data.sender()) {
+            self.metrics
+                .num_invalid_signatures
+                .with_label_values(&[&sponsor.to_string(), "user"])
+                .inc();
+            return Err(GasStationError::InvalidUserSignature(format!(
+                "Invalid signature of the sender {}: {}",
+                tx_data.sender(),
+                err
+            ))
+            .into());
+        }
         let payment: Vec<_> = tx_data
             .gas_data()
             .payment
@@ -108,10 +175,22 @@ impl GasStation {
             ?reservation_id,
             "Payment coins in transaction: {:?}", payment
         );
-        self.gas_station_store
-            .ready_for_execution(reservation_id)
+        let store = self.store_for(sponsor);
+        store
+            .ready_for_execution(
+                reservation_id,
+                &payment,
+                self.execution.lease().as_millis() as u64,
+            )
             .await?;
         debug!(?reservation_id, "Reservation is ready for execution");
+        self.in_flight_payments.lock().insert(
+            (sponsor, reservation_id),
+            InFlightPayment {
+                coins: payment.clone(),
+                signed: false,
+            },
+        );
 
         // To avoid read-after-write inconsistency, we apply a trick here to calculate the
         // new balance of the gas coin after the transaction.
@@ -122,9 +201,50 @@ impl GasStation {
             ?reservation_id,
             "Total gas coin balance prior to execution: {}", total_gas_coin_balance,
         );
-        let response = self
-            .execute_transaction_impl(reservation_id, tx_data, user_sig, request_type)
-            .await;
+        // Bounded, so that the caller gets a response before the execution lease expires.
+        let response = match tokio::time::timeout(
+            self.execution.timeout(),
+            self.execute_transaction_impl(
+                reservation_id,
+                tx_data,
+                user_sig,
+                request_type,
+                signed_notifier,
+            ),
+        )
+        .await
+        {
+            Ok(response) => response,
+            Err(_) => {
+                error!(?reservation_id, "Transaction execution timed out");
+                let signed = {
+                    let mut in_flight_payments = self.in_flight_payments.lock();
+                    let key = (sponsor, reservation_id);
+                    let signed = in_flight_payments
+                        .get(&key)
+                        .is_some_and(|payment| payment.signed);
+                    if signed {
+                        in_flight_payments.remove(&key);
+                    }
+                    signed
+                };
+                // A signed transaction may still be executed, so releasing its coins now could
+                // hand them out at an outdated version.
+                if signed {
+                    return Err(GasStationError::FullnodeFailure(format!(
+                        "Transaction execution timed out after {:?}, its gas coins are \
+                        recovered once the execution lease expires",
+                        self.execution.timeout()
+                    ))
+                    .into());
+                }
+                Err(GasStationError::FullnodeFailure(format!(
+                    "Transaction execution timed out after {:?}",
+                    self.execution.timeout()
+                ))
+                .into())
+            }
+        };
         let updated_coins = match &response {
             Ok(effects) => {
                 let new_gas_coin = effects.gas_object().reference.to_object_ref();
@@ -162,9 +282,18 @@ impl GasStation {
         };
         let smashed_coin_count = payment_count - updated_coins.len();
         // Regardless of whether the transaction succeeded, we need to release the coins.
-        // Otherwise, we lose track of them. This is because `ready_for_execution` already takes
-        // the coins out of the pool and will not be covered by the auto-release mechanism.
-        self.release_gas_coins(updated_coins).await;
+        // Otherwise, they are only recovered once the execution lease expires. This is because
+        // `ready_for_execution` already takes the coins out of the reservation expiration queue.
+        // The coins may have been released already if the station is shutting down.
+        if self
+            .in_flight_payments
+            .lock()
+            .remove(&(sponsor, reservation_id))
+            .is_some()
+        {
+            self.complete_execution(store, reservation_id, updated_coins)
+                .await;
+        }
         if smashed_coin_count > 0 {
             info!(
                 ?reservation_id,
@@ -186,6 +315,7 @@ impl GasStation {
         tx_data: TransactionData,
         user_sig: GenericSignature,
         request_type: Option<ExecuteTransactionRequestType>,
+        signed_notifier: Option<tokio::sync::oneshot::Sender<()>>,
     ) -> anyhow::Result<IotaTransactionBlockEffects> {
         let sponsor = tx_data.gas_data().owner;
         let cur_time = std::time::Instant::now();
@@ -197,19 +327,49 @@ impl GasStation {
                     .tap_err(|err| error!("Failed to sign transaction: {:?}", err))
             },
             3
-        )?;
+        )
+        .map_err(|err| GasStationError::SignerFailure(err.to_string()))?;
         let elapsed = cur_time.elapsed().as_millis();
         self.metrics
             .transaction_signing_latency_ms
             .observe(elapsed as u64);
+        // A misconfigured signer, e.g. one using another key, is caught before the transaction is
+        // submitted.
+        if let Err(err) = verify_tx_signature(&tx_data, &sponsor_sig, sponsor) {
+            error!(
+                ?reservation_id,
+                "The signer returned an invalid sponsor signature: {:?}", err
+            );
+            self.metrics
+                .num_invalid_signatures
+                .with_label_values(&[&sponsor.to_string(), "sponsor"])
+                .inc();
+            return Err(GasStationError::InvalidSponsorSignature(format!(
+                "Invalid signature of the sponsor {}: {}",
+                sponsor, err
+            ))
+            .into());
+        }
         debug!(?reservation_id, "Transaction signed by sponsor");
+        if let Some(payment) = self
+            .in_flight_payments
+            .lock()
+            .get_mut(&(sponsor, reservation_id))
+        {
+            payment.signed = true;
+        }
+        if let Some(notifier) = signed_notifier {
+            // The receiver may have given up waiting already, which is fine.
+            let _ = notifier.send(());
+        }
 
         let tx = Transaction::from_generic_sig_data(tx_data, vec![sponsor_sig, user_sig]);
         let cur_time = std::time::Instant::now();
         let effects = self
             .iota_client
             .execute_transaction(tx, 3, request_type)
-            .await?;
+            .await
+            .map_err(|err| GasStationError::FullnodeFailure(err.to_string()))?;
         debug!(?reservation_id, "Transaction executed");
         let elapsed = cur_time.elapsed().as_millis();
         self.metrics
@@ -261,16 +421,36 @@ impl GasStation {
             .into_iter()
             .any(|arg| matches!(*arg, Argument::GasCoin));
         if uses_gas {
-            bail!("Gas coin can only be used to pay gas")
+            return Err(GasStationError::InvalidRequest(
+                "Gas coin can only be used to pay gas".to_string(),
+            )
+            .into());
         };
         Ok(())
     }
 
+    /// Returns the pool that the coins of the sponsor are reserved from.
+    fn store_for(&self, sponsor: IotaAddress) -> &Arc<dyn Storage> {
+        match &self.previous_sponsor_store {
+            Some((previous_sponsor, store)) if *previous_sponsor == sponsor => store,
+            _ => &self.gas_station_store,
+        }
+    }
+
+    /// Returns every pool whose coins may be reserved or in execution, with its sponsor.
+    fn stores(&self) -> impl Iterator<Item = (IotaAddress, &Arc<dyn Storage>)> {
+        std::iter::once((self.signer.get_address(), &self.gas_station_store)).chain(
+            self.previous_sponsor_store
+                .iter()
+                .map(|(sponsor, store)| (*sponsor, store)),
+        )
+    }
+
     /// Release gas coins back to the Gas Station, by adding them to the storage.
-    async fn release_gas_coins(&self, gas_coins: Vec<GasCoin>) {
+    async fn release_gas_coins(&self, store: &Arc<dyn Storage>, gas_coins: Vec<GasCoin>) {
         debug!("Trying to release gas coins: {:?}", gas_coins);
         retry_forever!(async {
-            self.gas_station_store
+            store
                 .add_new_coins(gas_coins.clone())
                 .await
                 .tap_err(|err| error!("Failed to call update_gas_coins on storage: {:?}", err))
@@ -278,8 +458,114 @@ impl GasStation {
         .unwrap();
     }
 
+    /// Releases the coins of an executed transaction and its execution lease.
+    async fn complete_execution(
+        &self,
+        store: &Arc<dyn Storage>,
+        reservation_id: ReservationID,
+        gas_coins: Vec<GasCoin>,
+    ) {
+        debug!(
+            ?reservation_id,
+            "Trying to release gas coins: {:?}", gas_coins
+        );
+        let released = retry_forever!(async {
+            store
+                .complete_execution(reservation_id, gas_coins.clone())
+                .await
+                .tap_err(|err| error!("Failed to call complete_execution on storage: {:?}", err))
+        })
+        .unwrap();
+        if !released {
+            info!(
+                ?reservation_id,
+                "Execution lease expired before the transaction finished, coins were recovered already"
+            );
+        }
+    }
+
+    /// Releases the coins of transactions that are still being executed but weren't signed yet,
+    /// using their latest state on chain. This is only meant to be called on shutdown, after the
+    /// executions had a chance to finish, so that their coins don't have to wait for the execution
+    /// lease to expire. Signed transactions may still be executed, so their coins are left to the
+    /// execution lease.
+    pub async fn release_in_flight_coins(&self) {
+        let payments: Vec<_> = self.in_flight_payments.lock().drain().collect();
+        let mut count = 0;
+        let mut signed_count = 0;
+        for ((sponsor, reservation_id), payment) in payments {
+            if payment.signed {
+                signed_count += 1;
+                continue;
+            }
+            let latest_coins: Vec<_> = self
+                .iota_client
+                .get_latest_gas_objects(payment.coins)
+                .await
+                .into_values()
+                .flatten()
+                .collect();
+            count += latest_coins.len();
+            self.complete_execution(self.store_for(sponsor), reservation_id, latest_coins)
+                .await;
+        }
+        if count > 0 {
+            info!(
+                "Released {:?} coins of transactions still in execution",
+                count
+            );
+        }
+        if signed_count > 0 {
+            info!(
+                "Left the coins of {:?} signed transactions to their execution lease",
+                signed_count
+            );
+        }
+    }
+
+    /// Puts the coins of executions whose lease expired back into the pool, using their latest
+    /// state on chain.
+    async fn recover_expired_execution_leases(
+        &self,
+        sponsor: IotaAddress,
+        store: &Arc<dyn Storage>,
+    ) {
+        let expired_coins = match store.expire_execution_leases().await {
+            Ok(expired_coins) => expired_coins,
+            Err(err) => {
+                error!(
+                    "Failed to call expire_execution_leases to the storage: {:?}",
+                    err
+                );
+                return;
+            }
+        };
+        if expired_coins.is_empty() {
+            return;
+        }
+        debug!("Coins whose execution lease expired: {:?}", expired_coins);
+        let latest_coins: Vec<_> = self
+            .iota_client
+            .get_latest_gas_objects(expired_coins)
+            .await
+            .into_values()
+            .flatten()
+            .collect();
+        let count = latest_coins.len();
+        self.release_gas_coins(store, latest_coins).await;
+        self.metrics
+            .num_recovered_gas_coins
+            .with_label_values(&[&sponsor.to_string()])
+            .inc_by(count as u64);
+        info!(
+            "Recovered {:?} coins after execution lease expiration",
+            count
+        );
+    }
+
     /// Performs an end-to-end flow of reserving gas, signing a transaction, and releasing the gas coins.
     pub async fn debug_check_health(&self) -> anyhow::Result<()> {
+        self.signer.check_health().await?;
         let gas_budget = NANOS_PER_IOTA / 10;
         let (_address, _reservation_id, gas_coins) =
             self.reserve_gas(gas_budget, Duration::from_secs(3)).await?;
@@ -298,29 +584,37 @@ impl GasStation {
         Ok(())
     }
 
+    /// Puts the coins of reservations that expired before their execution back into the pool.
+    async fn unlock_expired_coins(&self, store: &Arc<dyn Storage>) {
+        let expire_results = store.expire_coins().await;
+        let unlocked_coins = expire_results.unwrap_or_else(|err| {
+            error!("Failed to call expire_coins to the storage: {:?}", err);
+            vec![]
+        });
+        if !unlocked_coins.is_empty() {
+            debug!("Coins that are expired: {:?}", unlocked_coins);
+            let latest_coins: Vec<_> = self
+                .iota_client
+                .get_latest_gas_objects(unlocked_coins.clone())
+                .await
+                .into_values()
+                .flatten()
+                .collect();
+            let count = latest_coins.len();
+            self.release_gas_coins(store, latest_coins).await;
+            info!("Released {:?} coins after expiration", count);
+        }
+    }
+
     async fn start_coin_unlock_task(
         self: Arc<Self>,
         mut cancel_receiver: tokio::sync::oneshot::Receiver<()>,
     ) -> JoinHandle<()> {
         tokio::task::spawn(async move {
             loop {
-                let expire_results = self.gas_station_store.expire_coins().await;
-                let unlocked_coins = expire_results.unwrap_or_else(|err| {
-                    error!("Failed to call expire_coins to the storage: {:?}", err);
-                    vec![]
-                });
-                if !unlocked_coins.is_empty() {
-                    debug!("Coins that are expired: {:?}", unlocked_coins);
-                    let latest_coins: Vec<_> = self
-                        .iota_client
-                        .get_latest_gas_objects(unlocked_coins.clone())
-                        .await
-                        .into_values()
-                        .flatten()
-                        .collect();
-                    let count = latest_coins.len();
-                    self.release_gas_coins(latest_coins).await;
-                    info!("Released {:?} coins after expiration", count);
+                for (sponsor, store) in self.stores() {
+                    self.unlock_expired_coins(store).await;
+                    self.recover_expired_execution_leases(sponsor, store).await;
                 }
                 tokio::select! {
                     _ = tokio::time::sleep(EXPIRATION_JOB_INTERVAL) => {}
@@ -347,7 +641,9 @@ impl GasStationContainer {
         gas_station_store: Arc<dyn Storage>,
         iota_client: IotaClient,
         gas_usage_daily_cap: u64,
+        execution: ExecutionConfig,
         metrics: Arc<GasStationCoreMetrics>,
+        previous_sponsor_store: Option<(IotaAddress, Arc<dyn Storage>)>,
     ) -> Self {
         let inner = GasStation::new(
             signer,
@@ -355,18 +651,30 @@ impl GasStationContainer {
             iota_client,
             metrics,
             Arc::new(GasUsageCap::new(gas_usage_daily_cap)),
+            execution,
+            previous_sponsor_store,
         )
         .await;
         let (cancel_sender, cancel_receiver) = tokio::sync::oneshot::channel();
-        let _coin_unlocker_task = inner.clone().start_coin_unlock_task(cancel_receiver).await;
+        let coin_unlocker_task = inner.clone().start_coin_unlock_task(cancel_receiver).await;
 
         Self {
             inner,
-            _coin_unlocker_task,
+            coin_unlocker_task,
             cancel_sender: Some(cancel_sender),
         }
     }
 
+    /// Cancels the background tasks and waits for them to stop.
+    pub async fn shutdown(mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            let _ = cancel_sender.send(());
+        }
+        if let Err(err) = (&mut self.coin_unlocker_task).await {
+            error!("Coin unlocker task failed: {:?}", err);
+        }
+    }
+
     pub fn get_gas_station_arc(&self) -> Arc<GasStation> {
         self.inner.clone()
     }
@@ -379,6 +687,9 @@ impl GasStationContainer {
 
 impl Drop for GasStationContainer {
     fn drop(&mut self) {
-        self.cancel_sender.take().unwrap().send(()).unwrap();
+        // The sender was already used if the container was shut down.
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            cancel_sender.send(()).unwrap();
+        }
     }
 }
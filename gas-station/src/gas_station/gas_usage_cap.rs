@@ -1,7 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::bail;
+use crate::errors::GasStationError;
 use chrono::{Local, NaiveDate};
 use tokio::sync::RwLock;
 
@@ -30,7 +30,10 @@ impl GasUsageCap {
         self.reset_date_maybe().await;
         let cur_daily_usage = self.inner.read().await.cur_daily_usage;
         if cur_daily_usage >= self.daily_cap as i64 {
-            bail!("Gas usage exceeds daily cap");
+            return Err(GasStationError::DailyCapExceeded(
+                "Gas usage exceeds daily cap".to_string(),
+            )
+            .into());
         }
         Ok(())
     }
@@ -3,18 +3,26 @@
 
 pub mod gas_station_core;
 mod gas_usage_cap;
+mod signature_verifier;
 
 #[cfg(test)]
 mod tests {
-    use crate::test_env::{create_test_transaction, start_gas_station};
+    use crate::errors::{ErrorCode, GasStationError};
+    use crate::test_env::{
+        create_test_transaction, start_gas_station, start_gas_station_with_signer,
+    };
+    use crate::tx_signer::{TestTxSigner, TxSigner};
     use iota_json_rpc_types::IotaTransactionBlockEffectsAPI;
     use iota_types::{
+        base_types::IotaAddress,
         crypto::{get_account_key_pair, Signature},
         gas_coin::NANOS_PER_IOTA,
         programmable_transaction_builder::ProgrammableTransactionBuilder,
+        signature::GenericSignature,
         transaction::{TransactionData, TransactionKind},
     };
     use shared_crypto::intent::{Intent, IntentMessage};
+    use std::sync::Arc;
     use std::time::Duration;
 
     #[tokio::test]
@@ -98,6 +106,88 @@ mod tests {
         assert_eq!(station.query_pool_available_coin_count().await, 1);
     }
 
+    #[tokio::test]
+    async fn test_invalid_user_signature() {
+        let (test_cluster, container) =
+            start_gas_station(vec![NANOS_PER_IOTA], NANOS_PER_IOTA).await;
+        let station = container.get_gas_station_arc();
+        let (sponsor, reservation_id, gas_coins) = station
+            .reserve_gas(NANOS_PER_IOTA, Duration::from_secs(10))
+            .await
+            .unwrap();
+        let (tx_data, user_sig) = create_test_transaction(&test_cluster, sponsor, gas_coins).await;
+        // Signed by another key than the one of the sender.
+        let (_, keypair) = get_account_key_pair();
+        let wrong_sig: Signature = Signature::new_secure(
+            &IntentMessage::new(Intent::iota_transaction(), &tx_data),
+            &keypair,
+        );
+        let err = station
+            .execute_transaction(reservation_id, tx_data.clone(), wrong_sig.into(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            GasStationError::from(err).code(),
+            ErrorCode::InvalidUserSignature
+        );
+
+        // The reservation can still be used with a valid signature.
+        let effects = station
+            .execute_transaction(reservation_id, tx_data, user_sig, None)
+            .await
+            .unwrap();
+        assert!(effects.status().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_sponsor_signature() {
+        // The gas station signs for the sponsor address with another key.
+        let (test_cluster, container) =
+            start_gas_station_with_signer(vec![NANOS_PER_IOTA], NANOS_PER_IOTA, |signer| {
+                Arc::new(WrongKeyTxSigner {
+                    address: signer.get_address(),
+                })
+            })
+            .await;
+        let station = container.get_gas_station_arc();
+        let (sponsor, reservation_id, gas_coins) = station
+            .reserve_gas(NANOS_PER_IOTA, Duration::from_secs(10))
+            .await
+            .unwrap();
+        let (tx_data, user_sig) = create_test_transaction(&test_cluster, sponsor, gas_coins).await;
+        let err = station
+            .execute_transaction(reservation_id, tx_data, user_sig, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            GasStationError::from(err).code(),
+            ErrorCode::InvalidSponsorSignature
+        );
+        // The coins were not used, and are back in the pool.
+        assert_eq!(station.query_pool_available_coin_count().await, 1);
+    }
+
+    struct WrongKeyTxSigner {
+        address: IotaAddress,
+    }
+
+    #[async_trait::async_trait]
+    impl TxSigner for WrongKeyTxSigner {
+        async fn sign_transaction(
+            &self,
+            tx_data: &TransactionData,
+        ) -> anyhow::Result<GenericSignature> {
+            let (_, keypair) = get_account_key_pair();
+            TestTxSigner::new(keypair.into())
+                .sign_transaction(tx_data)
+                .await
+        }
+
+        fn get_address(&self) -> IotaAddress {
+            self.address
+        }
+    }
+
     #[tokio::test]
     async fn test_coin_expiration() {
         telemetry_subscribers::init_for_testing();
@@ -128,7 +218,6 @@ mod tests {
             .unwrap();
     }
 
-    #[ignore]
     #[tokio::test]
     async fn test_incomplete_gas_usage() {
         let (test_cluster, container) =
@@ -159,7 +248,6 @@ mod tests {
         assert!(effects.status().is_ok());
     }
 
-    #[ignore]
     #[tokio::test]
     async fn test_mixed_up_gas_coins() {
         let (test_cluster, container) =
@@ -0,0 +1,29 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::anyhow;
+use iota_types::base_types::IotaAddress;
+use iota_types::crypto::IotaSignature;
+use iota_types::signature::{AuthenticatorTrait, GenericSignature, VerifyParams};
+use iota_types::transaction::TransactionData;
+use shared_crypto::intent::{Intent, IntentMessage};
+
+/// Verifies that `signature` signs `tx_data` on behalf of `author`, as the fullnode does before
+/// executing it. Signature schemes that depend on chain state, like passkeys, are left to the
+/// fullnode.
+pub(crate) fn verify_tx_signature(
+    tx_data: &TransactionData,
+    signature: &GenericSignature,
+    author: IotaAddress,
+) -> anyhow::Result<()> {
+    let intent_msg = IntentMessage::new(Intent::iota_transaction(), tx_data);
+    match signature {
+        GenericSignature::Signature(signature) => signature
+            .verify_secure(&intent_msg, author, signature.scheme())
+            .map_err(|err| anyhow!(err.to_string())),
+        GenericSignature::MultiSig(multisig) => multisig
+            .verify_claims(&intent_msg, author, &VerifyParams::default())
+            .map_err(|err| anyhow!(err.to_string())),
+        _ => Ok(()),
+    }
+}
@@ -1,14 +1,15 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::coin_sizing::AdaptiveCoinSizing;
 use crate::config::CoinInitConfig;
 use crate::iota_client::IotaClient;
 use crate::retry_forever;
 use crate::storage::Storage;
 use crate::tx_signer::TxSigner;
-use crate::types::GasCoin;
+use crate::types::{CoinDenomination, GasCoin};
 use iota_json_rpc_types::IotaTransactionBlockEffectsAPI;
-use iota_types::base_types::IotaAddress;
+use iota_types::base_types::{IotaAddress, ObjectID};
 use iota_types::coin::{PAY_MODULE_NAME, PAY_SPLIT_N_FUNC_NAME};
 use iota_types::gas_coin::GAS;
 use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
@@ -16,22 +17,32 @@ use iota_types::transaction::{Argument, Transaction, TransactionData};
 use iota_types::IOTA_FRAMEWORK_PACKAGE_ID;
 use parking_lot::Mutex;
 use std::cmp::min;
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::time::Duration;
 use tap::TapFallible;
+use tokio::sync::Notify;
 use tokio::task::JoinHandle;
-use tokio::time::Instant;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use tokio_retry::strategy::{jitter, ExponentialBackoff};
 use tracing::{debug, error, info};
 
 /// Any coin owned by the sponsor address with balance above target_init_coin_balance * NEW_COIN_BALANCE_FACTOR_THRESHOLD
 /// is considered a new coin, and we will try to split it into smaller coins with balance close to target_init_coin_balance.
-const NEW_COIN_BALANCE_FACTOR_THRESHOLD: u64 = 200;
+pub const NEW_COIN_BALANCE_FACTOR_THRESHOLD: u64 = 200;
 
 /// Assume that initializing the Gas Station (i.e. splitting coins) will take at most 12 hours.
 const MAX_INIT_DURATION_SEC: u64 = 60 * 60 * 12;
 
+/// How often the budgets observed by this instance are added to the statistics in storage that
+/// adaptive coin sizing is based on.
+const BUDGET_SAMPLE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Bounds of the delay between attempts to split a coin into denominations.
+const INITIAL_SPLIT_RETRY_DELAY_MS: u64 = 500;
+const MAX_SPLIT_RETRY_DELAY: Duration = Duration::from_secs(60);
+
 #[derive(Clone)]
 struct CoinSplitEnv {
     target_init_coin_balance: u64,
@@ -59,6 +70,13 @@ impl CoinSplitEnv {
         None
     }
 
+    fn with_target_balance(&self, target_init_coin_balance: u64) -> Self {
+        Self {
+            target_init_coin_balance,
+            ..self.clone()
+        }
+    }
+
     fn increment_total_coin_count_by(&self, delta: usize) {
         info!(
             "Number of coins got so far: {}",
@@ -156,6 +174,122 @@ impl CoinSplitEnv {
         self.increment_total_coin_count_by(result.len() - 1);
         result
     }
+
+    /// Splits the coin into one part per denomination, in proportion to the balance the
+    /// denomination needs to provide its share of coins. The gas coin keeps the part of the first
+    /// denomination. Returns each part with the balance it should be split into.
+    async fn split_into_denominations(
+        &self,
+        mut coin: GasCoin,
+        denominations: &[CoinDenomination],
+    ) -> Vec<(GasCoin, u64)> {
+        let total_weight: f64 = denominations
+            .iter()
+            .map(|denomination| denomination.share * denomination.balance as f64)
+            .sum();
+        let budget = self.gas_cost_per_object * denominations.len() as u64;
+        let mut retry_delays = ExponentialBackoff::from_millis(2)
+            .factor(INITIAL_SPLIT_RETRY_DELAY_MS / 2)
+            .max_delay(MAX_SPLIT_RETRY_DELAY)
+            .map(jitter);
+        loop {
+            // Parts that are too small to be split further stay with the gas coin.
+            let available_balance = coin.balance.saturating_sub(budget) as f64;
+            let parts: Vec<_> = denominations[1..]
+                .iter()
+                .map(|denomination| {
+                    let weight = denomination.share * denomination.balance as f64 / total_weight;
+                    (denomination.balance, (available_balance * weight) as u64)
+                })
+                .filter(|(target, amount)| *amount > (self.gas_cost_per_object + target) * 2)
+                .collect();
+            if parts.is_empty() {
+                return vec![(coin, denominations[0].balance)];
+            }
+            let mut pt_builder = ProgrammableTransactionBuilder::new();
+            pt_builder
+                .pay_iota(
+                    vec![self.sponsor_address; parts.len()],
+                    parts.iter().map(|(_, amount)| *amount).collect(),
+                )
+                .unwrap();
+            let tx_data = TransactionData::new_programmable(
+                self.sponsor_address,
+                vec![coin.object_ref],
+                pt_builder.finish(),
+                budget,
+                self.rgp,
+            );
+            let sig = retry_forever!(async {
+                self.signer
+                    .sign_transaction(&tx_data)
+                    .await
+                    .tap_err(|err| error!("Failed to sign transaction: {:?}", err))
+            })
+            .unwrap();
+            let tx = Transaction::from_generic_sig_data(tx_data, vec![sig]);
+            let effects = match self.iota_client.execute_transaction(tx, 10, None).await {
+                Ok(effects) if effects.status().is_ok() => effects,
+                result => {
+                    let delay = retry_delays.next().unwrap();
+                    error!(
+                        "Failed to split coin into denominations, retrying in {:?}: {:?}",
+                        delay, result
+                    );
+                    tokio::time::sleep(delay).await;
+                    match self
+                        .iota_client
+                        .get_latest_gas_objects([coin.object_ref.0])
+                        .await
+                        .remove(&coin.object_ref.0)
+                        .flatten()
+                    {
+                        Some(latest_coin) => coin = latest_coin,
+                        None => {
+                            error!("Coin {:?} no longer exists, skipping it", coin.object_ref);
+                            return vec![];
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            let gas_object_id = effects.gas_object().reference.object_id;
+            let mut result = vec![];
+            if let Some(Some(gas_coin)) = self
+                .iota_client
+                .get_latest_gas_objects([gas_object_id])
+                .await
+                .remove(&gas_object_id)
+            {
+                result.push((gas_coin, denominations[0].balance));
+            }
+            // `pay_iota` splits the parts off the gas coin in order, and the IDs of new objects
+            // are derived from the transaction digest and the order of creation.
+            let created = effects.created();
+            for (creation_num, (target, amount)) in parts.into_iter().enumerate() {
+                let object_id =
+                    ObjectID::derive_id(*effects.transaction_digest(), creation_num as u64);
+                match created
+                    .iter()
+                    .find(|created| created.reference.object_id == object_id)
+                {
+                    Some(created) => result.push((
+                        GasCoin {
+                            object_ref: created.reference.to_object_ref(),
+                            balance: amount,
+                        },
+                        target,
+                    )),
+                    None => error!(
+                        "Coin {} split off for balance {} was not created",
+                        object_id, target
+                    ),
+                }
+            }
+            return result;
+        }
+    }
 }
 
 enum RunMode {
@@ -163,15 +297,55 @@ enum RunMode {
     Refresh,
 }
 
+/// Detects funds sent to the sponsor address from the polled total balance. Gas payments only
+/// decrease the balance, so an increase of at least `min_increase` means that a coin large enough
+/// to be initialized may have arrived.
+struct BalanceWatcher {
+    min_increase: u128,
+    last_balance: Option<u128>,
+}
+
+impl BalanceWatcher {
+    fn new(min_increase: u128) -> Self {
+        Self {
+            min_increase,
+            last_balance: None,
+        }
+    }
+
+    /// Records the latest balance and returns true if it grew by at least `min_increase` since
+    /// the previous one.
+    fn observe(&mut self, balance: u128) -> bool {
+        let increased = self
+            .last_balance
+            .is_some_and(|last_balance| balance >= last_balance + self.min_increase);
+        self.last_balance = Some(balance);
+        increased
+    }
+}
+
+/// Waits for the next tick of the interval, or forever if there is none.
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
 pub struct GasStationInitializer {
-    _task_handle: JoinHandle<()>,
-    // This is always Some. It is None only after the drop method is called.
+    task_handle: JoinHandle<()>,
+    refresh_notifier: Arc<Notify>,
+    // This is always Some. It is None only after the drop or shutdown method is called.
     cancel_sender: Option<tokio::sync::oneshot::Sender<()>>,
 }
 
 impl Drop for GasStationInitializer {
     fn drop(&mut self) {
-        self.cancel_sender.take().unwrap().send(()).unwrap();
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            cancel_sender.send(()).unwrap();
+        }
     }
 }
 
@@ -181,6 +355,7 @@ impl GasStationInitializer {
         storage: Arc<dyn Storage>,
         coin_init_config: CoinInitConfig,
         signer: Arc<dyn TxSigner>,
+        coin_sizing: Option<AdaptiveCoinSizing>,
     ) -> Self {
         if !storage.is_initialized().await.unwrap() {
             // If the pool has never been initialized, always run once at the beginning to make sure we have enough coins.
@@ -190,36 +365,104 @@ impl GasStationInitializer {
                 RunMode::Init,
                 coin_init_config.target_init_balance,
                 &signer,
+                coin_sizing.as_ref(),
             )
             .await;
         }
         let (cancel_sender, cancel_receiver) = tokio::sync::oneshot::channel();
-        let _task_handle = tokio::spawn(Self::run(
+        let refresh_notifier = Arc::new(Notify::new());
+        let task_handle = tokio::spawn(Self::run(
             iota_client,
             storage,
             coin_init_config,
             signer,
+            coin_sizing,
+            refresh_notifier.clone(),
             cancel_receiver,
         ));
         Self {
-            _task_handle,
+            task_handle,
+            refresh_notifier,
             cancel_sender: Some(cancel_sender),
         }
     }
 
+    /// Returns a handle that starts the next refresh right away when notified, instead of waiting
+    /// for `refresh_interval_sec` or for the sponsor balance to grow.
+    pub fn refresh_notifier(&self) -> Arc<Notify> {
+        self.refresh_notifier.clone()
+    }
+
+    /// Cancels the refresh task and waits for it to stop. A coin initialization that is already
+    /// running is completed first, so that the new coins are added to the pool and the init lock
+    /// is released.
+    pub async fn shutdown(mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            let _ = cancel_sender.send(());
+        }
+        if let Err(err) = (&mut self.task_handle).await {
+            error!("Coin init task failed: {:?}", err);
+        }
+    }
+
     async fn run(
         iota_client: IotaClient,
         storage: Arc<dyn Storage>,
         coin_init_config: CoinInitConfig,
         signer: Arc<dyn TxSigner>,
+        coin_sizing: Option<AdaptiveCoinSizing>,
+        refresh_notifier: Arc<Notify>,
         mut cancel_receiver: tokio::sync::oneshot::Receiver<()>,
     ) {
+        let refresh_interval = Duration::from_secs(coin_init_config.refresh_interval_sec);
+        let mut balance_poll = (coin_init_config.balance_poll_interval_sec > 0).then(|| {
+            let mut interval = tokio::time::interval(Duration::from_secs(
+                coin_init_config.balance_poll_interval_sec,
+            ));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let mut budget_sample_flush = coin_sizing.as_ref().map(|_| {
+            let mut interval = tokio::time::interval(BUDGET_SAMPLE_FLUSH_INTERVAL);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let mut balance_watcher = BalanceWatcher::new(
+            coin_init_config.target_init_balance as u128
+                * NEW_COIN_BALANCE_FACTOR_THRESHOLD as u128,
+        );
         loop {
-            tokio::select! {
-                _ = tokio::time::sleep(Duration::from_secs(coin_init_config.refresh_interval_sec)) => {}
-                _ = &mut cancel_receiver => {
-                    info!("Coin init task is cancelled");
-                    break;
+            let next_refresh = Instant::now() + refresh_interval;
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(next_refresh) => break,
+                    _ = refresh_notifier.notified() => {
+                        info!("Coin init refresh was requested");
+                        break;
+                    }
+                    _ = tick(&mut balance_poll) => {
+                        match iota_client.get_total_balance(signer.get_address()).await {
+                            Ok(balance) => {
+                                if balance_watcher.observe(balance) {
+                                    info!("Sponsor balance increased to {}. Starting a refresh", balance);
+                                    break;
+                                }
+                            }
+                            Err(err) => debug!("Failed to get the sponsor balance: {:?}", err),
+                        }
+                    }
+                    _ = tick(&mut budget_sample_flush) => {
+                        if let Some(coin_sizing) = &coin_sizing {
+                            coin_sizing.flush_budget_samples(&storage).await;
+                        }
+                    }
+                    _ = &mut cancel_receiver => {
+                        info!("Coin init task is cancelled");
+                        if let Some(coin_sizing) = &coin_sizing {
+                            coin_sizing.flush_budget_samples(&storage).await;
+                        }
+                        return;
+                    }
                 }
             }
             info!("Coin init task waking up and looking for new coins to initialize");
@@ -229,6 +472,7 @@ impl GasStationInitializer {
                 RunMode::Refresh,
                 coin_init_config.target_init_balance,
                 &signer,
+                coin_sizing.as_ref(),
             )
             .await;
         }
@@ -240,6 +484,7 @@ impl GasStationInitializer {
         mode: RunMode,
         target_init_coin_balance: u64,
         signer: &Arc<dyn TxSigner>,
+        coin_sizing: Option<&AdaptiveCoinSizing>,
     ) {
         let sponsor_address = signer.get_address();
         if storage
@@ -270,29 +515,22 @@ impl GasStationInitializer {
             storage.release_init_lock().await.unwrap();
             return;
         }
-        let total_coin_count = Arc::new(AtomicUsize::new(coins.len()));
-        let rgp = iota_client.get_reference_gas_price().await;
-        let gas_cost_per_object = iota_client
-            .calibrate_gas_cost_per_object(sponsor_address, &coins[0])
-            .await;
-        info!("Calibrated gas cost per object: {:?}", gas_cost_per_object);
-        let result = Self::split_gas_coins(
-            coins,
-            CoinSplitEnv {
-                target_init_coin_balance,
-                gas_cost_per_object,
-                signer: signer.clone(),
-                sponsor_address,
-                iota_client,
-                task_queue: Default::default(),
-                total_coin_count,
-                rgp,
-            },
-        )
-        .await;
+        let denominations = match coin_sizing {
+            Some(coin_sizing) => coin_sizing.denominations(storage, sponsor_address).await,
+            None => vec![],
+        };
+        let result = if denominations.is_empty() {
+            Self::split_coins(iota_client, signer, target_init_coin_balance, coins).await
+        } else {
+            Self::split_coins_into_denominations(iota_client, signer, &denominations, coins).await
+        };
         for chunk in result.chunks(5000) {
             storage.add_new_coins(chunk.to_vec()).await.unwrap();
         }
+        if matches!(mode, RunMode::Init) {
+            // A pool that was drained before takes reservations again once it has new coins.
+            storage.set_draining(false).await.unwrap();
+        }
         storage.release_init_lock().await.unwrap();
         info!(
             "New coin initialization took {:?}s",
@@ -300,18 +538,83 @@ impl GasStationInitializer {
         );
     }
 
-    async fn split_gas_coins(coins: Vec<GasCoin>, env: CoinSplitEnv) -> Vec<GasCoin> {
-        let total_balance: u64 = coins.iter().map(|c| c.balance).sum();
+    /// Splits the coins into smaller coins with balance close to `target_init_coin_balance`.
+    /// Coins that are too small to be split are returned as they are.
+    pub async fn split_coins(
+        iota_client: IotaClient,
+        signer: &Arc<dyn TxSigner>,
+        target_init_coin_balance: u64,
+        coins: Vec<GasCoin>,
+    ) -> Vec<GasCoin> {
+        let env =
+            Self::new_split_env(iota_client, signer, target_init_coin_balance, &coins[0]).await;
+        let coins = coins
+            .into_iter()
+            .map(|coin| (coin, target_init_coin_balance))
+            .collect();
+        Self::split_gas_coins(coins, env).await
+    }
+
+    /// Splits the coins into smaller coins with the balances of the denominations, so that each
+    /// denomination gets close to its share of the new coins.
+    async fn split_coins_into_denominations(
+        iota_client: IotaClient,
+        signer: &Arc<dyn TxSigner>,
+        denominations: &[CoinDenomination],
+        coins: Vec<GasCoin>,
+    ) -> Vec<GasCoin> {
+        let env =
+            Self::new_split_env(iota_client, signer, denominations[0].balance, &coins[0]).await;
+        let mut parts = vec![];
+        for coin in coins {
+            parts.extend(env.split_into_denominations(coin, denominations).await);
+        }
+        Self::split_gas_coins(parts, env).await
+    }
+
+    async fn new_split_env(
+        iota_client: IotaClient,
+        signer: &Arc<dyn TxSigner>,
+        target_init_coin_balance: u64,
+        sample_coin: &GasCoin,
+    ) -> CoinSplitEnv {
+        let sponsor_address = signer.get_address();
+        let rgp = iota_client.get_reference_gas_price().await;
+        let gas_cost_per_object = iota_client
+            .calibrate_gas_cost_per_object(sponsor_address, sample_coin)
+            .await;
+        info!("Calibrated gas cost per object: {:?}", gas_cost_per_object);
+        CoinSplitEnv {
+            target_init_coin_balance,
+            gas_cost_per_object,
+            signer: signer.clone(),
+            sponsor_address,
+            iota_client,
+            task_queue: Default::default(),
+            total_coin_count: Default::default(),
+            rgp,
+        }
+    }
+
+    /// Splits each coin evenly into coins of the balance it is paired with.
+    async fn split_gas_coins(coins: Vec<(GasCoin, u64)>, env: CoinSplitEnv) -> Vec<GasCoin> {
+        let total_balance: u64 = coins.iter().map(|(c, _)| c.balance).sum();
+        let expected_coin_count: u64 = coins
+            .iter()
+            .map(|(c, target_balance)| c.balance / target_balance)
+            .sum();
         info!(
-            "Splitting {} coins with total balance of {} into smaller coins with target balance of {}. This will result in close to {} coins",
+            "Splitting {} coins with total balance of {} into smaller coins with target balance of {:?}. This will result in close to {} coins",
             coins.len(),
             total_balance,
-            env.target_init_coin_balance,
-            total_balance / env.target_init_coin_balance,
+            coins.iter().map(|(_, target_balance)| *target_balance).collect::<BTreeSet<_>>(),
+            expected_coin_count,
         );
+        env.total_coin_count
+            .store(coins.len(), std::sync::atomic::Ordering::Relaxed);
         let mut result = vec![];
-        for coin in coins {
-            result.extend(env.enqueue_task(coin));
+        for (coin, target_balance) in coins {
+            result.extend(env.with_target_balance(target_balance).enqueue_task(coin));
         }
         loop {
             let Some(task) = env.task_queue.lock().pop_front() else {
@@ -334,7 +637,7 @@ impl GasStationInitializer {
 mod tests {
     use crate::config::CoinInitConfig;
     use crate::gas_station_initializer::{
-        GasStationInitializer, NEW_COIN_BALANCE_FACTOR_THRESHOLD,
+        BalanceWatcher, GasStationInitializer, NEW_COIN_BALANCE_FACTOR_THRESHOLD,
     };
     use crate::iota_client::IotaClient;
     use crate::storage::connect_storage_for_testing;
@@ -343,6 +646,18 @@ mod tests {
 
     // TODO: Add more accurate tests.
 
+    #[test]
+    fn test_balance_watcher() {
+        let mut watcher = BalanceWatcher::new(100);
+        // The first balance is only recorded.
+        assert!(!watcher.observe(1000));
+        // Gas payments and small transfers don't trigger a refresh.
+        assert!(!watcher.observe(900));
+        assert!(!watcher.observe(950));
+        assert!(watcher.observe(1050));
+        assert!(!watcher.observe(1050));
+    }
+
     #[tokio::test]
     async fn test_basic_init_flow() {
         telemetry_subscribers::init_for_testing();
@@ -356,8 +671,10 @@ mod tests {
             CoinInitConfig {
                 target_init_balance: NANOS_PER_IOTA,
                 refresh_interval_sec: 200,
+                ..Default::default()
             },
             signer,
+            None,
         )
         .await;
         assert!(storage.get_available_coin_count().await.unwrap() > 900);
@@ -377,8 +694,10 @@ mod tests {
             CoinInitConfig {
                 target_init_balance,
                 refresh_interval_sec: 200,
+                ..Default::default()
             },
             signer,
+            None,
         )
         .await;
         assert!(storage.get_available_coin_count().await.unwrap() > 800);
@@ -397,9 +716,13 @@ mod tests {
             storage.clone(),
             CoinInitConfig {
                 target_init_balance: NANOS_PER_IOTA,
-                refresh_interval_sec: 1,
+                // The new funds must be picked up by the balance polling.
+                refresh_interval_sec: 200,
+                balance_poll_interval_sec: 1,
+                ..Default::default()
             },
             signer,
+            None,
         )
         .await;
         assert!(storage.is_initialized().await.unwrap());
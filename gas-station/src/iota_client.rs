@@ -78,6 +78,16 @@ impl IotaClient {
         coins
     }
 
+    /// Returns the total IOTA balance owned by the address, summed over all its coins.
+    pub async fn get_total_balance(&self, address: IotaAddress) -> anyhow::Result<u128> {
+        let balance = self
+            .iota_client
+            .coin_read_api()
+            .get_balance(address, None)
+            .await?;
+        Ok(balance.total_balance)
+    }
+
     pub async fn get_reference_gas_price(&self) -> u64 {
         retry_forever!(async {
             self.iota_client
@@ -198,6 +208,35 @@ impl IotaClient {
         gas_used / SPLIT_COUNT * 2
     }
 
+    /// Estimates the gas budget of a transaction sent by `sender` by inspecting it.
+    pub async fn estimate_gas_budget(
+        &self,
+        sender: IotaAddress,
+        pt: ProgrammableTransaction,
+    ) -> anyhow::Result<u64> {
+        let response = retry_with_max_attempts!(
+            async {
+                self.iota_client
+                    .read_api()
+                    .dev_inspect_transaction_block(
+                        sender,
+                        TransactionKind::ProgrammableTransaction(pt.clone()),
+                        None,
+                        None,
+                        None,
+                    )
+                    .await
+                    .tap_err(|err| debug!("Failed to inspect transaction: {:?}", err))
+            },
+            3
+        )?;
+        if let Some(error) = response.error {
+            anyhow::bail!("Transaction would fail: {}", error);
+        }
+        // Multiply by 2 to be conservative, as in calibrate_gas_cost_per_object.
+        Ok(response.effects.gas_cost_summary().gas_used() * 2)
+    }
+
     pub async fn execute_transaction(
         &self,
         tx: Transaction,
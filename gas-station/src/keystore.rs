@@ -0,0 +1,338 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail, Context};
+use fastcrypto::encoding::{Base64, Encoding};
+use iota_types::base_types::IotaAddress;
+use iota_types::crypto::{EncodeDecodeBase64, IotaKeyPair};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::mem::ManuallyDrop;
+use std::os::fd::FromRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Version of the passphrase-encrypted key file format.
+const ENCRYPTED_KEY_FILE_VERSION: u32 = 1;
+
+/// Cost parameters of the scrypt key derivation. The derivation runs once at startup, so it can
+/// afford to be slow.
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// Passphrases read from file descriptors. A pipe can only be read once, so the passphrase is
+/// kept for the other key files that use the same descriptor.
+static FD_PASSPHRASES: Lazy<Mutex<HashMap<i32, String>>> = Lazy::new(Default::default);
+
+/// A single key pair, encrypted with a key derived from a passphrase with scrypt, using
+/// AES-256-GCM. The address is stored in clear so that the file can be identified without the
+/// passphrase, and is authenticated as part of the encryption.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EncryptedKeyFile {
+    pub version: u32,
+    pub address: IotaAddress,
+    pub kdf: ScryptParams,
+    /// Base64 encoded.
+    pub nonce: String,
+    /// Base64 encoded.
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    /// Base64 encoded.
+    pub salt: String,
+}
+
+/// The key file formats accepted by the keystore signer.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyFile {
+    Encrypted(EncryptedKeyFile),
+    /// The keystore of the IOTA CLI, a list of base64 encoded key pairs.
+    IotaCli(Vec<String>),
+}
+
+impl EncryptedKeyFile {
+    pub fn encrypt(keypair: &IotaKeyPair, passphrase: &str) -> anyhow::Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let kdf = ScryptParams {
+            log_n: SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: Base64::encode(salt),
+        };
+        let address: IotaAddress = (&keypair.public()).into();
+        let cipher = kdf.derive_cipher(passphrase)?;
+        let plaintext = keypair.encode_base64();
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: address.as_ref(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt the key pair"))?;
+        Ok(Self {
+            version: ENCRYPTED_KEY_FILE_VERSION,
+            address,
+            kdf,
+            nonce: Base64::encode(nonce),
+            ciphertext: Base64::encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> anyhow::Result<IotaKeyPair> {
+        if self.version != ENCRYPTED_KEY_FILE_VERSION {
+            bail!("Unsupported key file version {}", self.version);
+        }
+        let cipher = self.kdf.derive_cipher(passphrase)?;
+        let nonce = decode_base64(&self.nonce, "nonce")?;
+        if nonce.len() != NONCE_LEN {
+            bail!("Invalid nonce length {}", nonce.len());
+        }
+        let ciphertext = decode_base64(&self.ciphertext, "ciphertext")?;
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: self.address.as_ref(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to decrypt the key file. Is the passphrase correct?"))?;
+        let keypair = IotaKeyPair::decode_base64(std::str::from_utf8(&plaintext)?)
+            .map_err(|err| anyhow!("Invalid key pair in the key file: {}", err))?;
+        let address: IotaAddress = (&keypair.public()).into();
+        if address != self.address {
+            bail!(
+                "The key file is for {}, but the key pair is for {}",
+                self.address,
+                address
+            );
+        }
+        Ok(keypair)
+    }
+
+    /// Writes the key file, readable by the owner only.
+    pub fn save(&self, path: &Path, overwrite: bool) -> anyhow::Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).mode(0o600);
+        if overwrite {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to create the key file {}", path.display()))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl ScryptParams {
+    fn derive_cipher(&self, passphrase: &str) -> anyhow::Result<Aes256Gcm> {
+        let salt = decode_base64(&self.salt, "salt")?;
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, KEY_LEN)
+            .map_err(|err| anyhow!("Invalid scrypt parameters: {}", err))?;
+        let mut key = [0u8; KEY_LEN];
+        scrypt::scrypt(passphrase.as_bytes(), &salt, &params, &mut key)
+            .map_err(|err| anyhow!("Failed to derive the key: {}", err))?;
+        Aes256Gcm::new_from_slice(&key).map_err(|err| anyhow!("Invalid key: {}", err))
+    }
+}
+
+fn decode_base64(value: &str, name: &str) -> anyhow::Result<Vec<u8>> {
+    Base64::decode(value).map_err(|err| anyhow!("Invalid {} in the key file: {}", name, err))
+}
+
+/// Loads the key pair of `address` from a key file. Both passphrase-encrypted key files and
+/// keystores of the IOTA CLI are accepted. The passphrase is only read for encrypted files. The
+/// address is required when the keystore holds several keys.
+pub fn load_keypair(
+    path: &Path,
+    address: Option<IotaAddress>,
+    passphrase: impl FnOnce() -> anyhow::Result<String>,
+) -> anyhow::Result<IotaKeyPair> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the key file {}", path.display()))?;
+    let key_file: KeyFile = serde_json::from_str(&content)
+        .with_context(|| format!("Unknown key file format in {}", path.display()))?;
+    let keypair = match key_file {
+        KeyFile::Encrypted(key_file) => {
+            if let Some(address) = address {
+                if address != key_file.address {
+                    bail!("The key file is for {}, not {}", key_file.address, address);
+                }
+            }
+            key_file.decrypt(&passphrase()?)?
+        }
+        KeyFile::IotaCli(keys) => {
+            let mut keypairs = keys
+                .iter()
+                .map(|key| {
+                    IotaKeyPair::decode_base64(key)
+                        .map_err(|err| anyhow!("Invalid key pair in the keystore: {}", err))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            match address {
+                Some(address) => keypairs
+                    .into_iter()
+                    .find(|keypair| IotaAddress::from(&keypair.public()) == address)
+                    .ok_or_else(|| anyhow!("No key for {} in the keystore", address))?,
+                None if keypairs.len() == 1 => keypairs.remove(0),
+                None => bail!(
+                    "The keystore holds {} keys. Specify the address of the key to use",
+                    keypairs.len()
+                ),
+            }
+        }
+    };
+    Ok(keypair)
+}
+
+/// Reads a passphrase from the file descriptor `fd` if given, e.g. a pipe set up by the process
+/// manager, or else from the environment variable `env_name`. A trailing newline is removed.
+/// The descriptor is read once and left open, as it isn't owned by the gas station.
+pub fn read_passphrase(env_name: &str, fd: Option<i32>) -> anyhow::Result<String> {
+    let mut passphrase = match fd {
+        Some(fd) => {
+            let mut passphrases = FD_PASSPHRASES.lock();
+            match passphrases.get(&fd) {
+                Some(passphrase) => passphrase.clone(),
+                None => {
+                    // SAFETY: the file is never dropped, so the descriptor is not closed.
+                    let mut file = ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(fd) });
+                    let mut passphrase = String::new();
+                    file.read_to_string(&mut passphrase)
+                        .with_context(|| format!("Failed to read the passphrase from fd {}", fd))?;
+                    passphrases.insert(fd, passphrase.clone());
+                    passphrase
+                }
+            }
+        }
+        None => std::env::var(env_name)
+            .with_context(|| format!("The passphrase is not set in {}", env_name))?,
+    };
+    if passphrase.ends_with('\n') {
+        passphrase.pop();
+        if passphrase.ends_with('\r') {
+            passphrase.pop();
+        }
+    }
+    if passphrase.is_empty() {
+        bail!("The passphrase is empty");
+    }
+    Ok(passphrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iota_types::crypto::get_account_key_pair;
+    use std::os::fd::AsRawFd;
+    use std::os::unix::net::UnixStream;
+
+    fn new_keypair() -> (IotaAddress, IotaKeyPair) {
+        let (address, keypair) = get_account_key_pair();
+        (address, keypair.into())
+    }
+
+    #[test]
+    fn test_encrypted_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sponsor.key");
+        let (address, keypair) = new_keypair();
+        let key_file = EncryptedKeyFile::encrypt(&keypair, "secret").unwrap();
+        key_file.save(&path, false).unwrap();
+        assert!(key_file.save(&path, false).is_err());
+
+        // The key pair is not stored in clear.
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains(&keypair.encode_base64()));
+
+        let loaded = load_keypair(&path, None, || Ok("secret".to_string())).unwrap();
+        assert_eq!(loaded, keypair);
+        let loaded = load_keypair(&path, Some(address), || Ok("secret".to_string())).unwrap();
+        assert_eq!(loaded, keypair);
+
+        assert!(load_keypair(&path, None, || Ok("wrong".to_string())).is_err());
+        assert!(load_keypair(&path, Some(IotaAddress::ZERO), || {
+            Ok("secret".to_string())
+        })
+        .is_err());
+
+        // A key file whose address was changed fails to decrypt.
+        let mut tampered = key_file.clone();
+        tampered.address = new_keypair().0;
+        assert!(tampered.decrypt("secret").is_err());
+    }
+
+    #[test]
+    fn test_iota_cli_keystore() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iota.keystore");
+        let (address1, keypair1) = new_keypair();
+        let (address2, keypair2) = new_keypair();
+        let keys = vec![keypair1.encode_base64(), keypair2.encode_base64()];
+        std::fs::write(&path, serde_json::to_string(&keys).unwrap()).unwrap();
+
+        let no_passphrase = || -> anyhow::Result<String> { panic!("Passphrase was read") };
+        assert_eq!(
+            load_keypair(&path, Some(address1), no_passphrase).unwrap(),
+            keypair1
+        );
+        assert_eq!(
+            load_keypair(&path, Some(address2), no_passphrase).unwrap(),
+            keypair2
+        );
+        assert!(load_keypair(&path, None, no_passphrase).is_err());
+        assert!(load_keypair(&path, Some(IotaAddress::ZERO), no_passphrase).is_err());
+
+        std::fs::write(&path, serde_json::to_string(&keys[..1]).unwrap()).unwrap();
+        assert_eq!(load_keypair(&path, None, no_passphrase).unwrap(), keypair1);
+    }
+
+    #[test]
+    fn test_read_passphrase() {
+        let env_name = "GAS_STATION_TEST_KEYSTORE_PASSPHRASE";
+        std::env::set_var(env_name, "secret\n");
+        assert_eq!(read_passphrase(env_name, None).unwrap(), "secret");
+        std::env::remove_var(env_name);
+        assert!(read_passphrase(env_name, None).is_err());
+    }
+
+    #[test]
+    fn test_read_passphrase_from_fd() {
+        let (mut writer, reader) = UnixStream::pair().unwrap();
+        writer.write_all(b"secret\n").unwrap();
+        drop(writer);
+        let fd = reader.as_raw_fd();
+        assert_eq!(read_passphrase("", Some(fd)).unwrap(), "secret");
+        // The pipe is drained, so the passphrase is reused.
+        assert_eq!(read_passphrase("", Some(fd)).unwrap(), "secret");
+        // The descriptor is still open.
+        assert!(reader.peer_addr().is_ok());
+    }
+}
@@ -3,16 +3,28 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod access_controller;
+pub mod audit_log;
 pub mod benchmarks;
+pub mod coin_consolidator;
+pub mod coin_sizing;
 pub mod command;
 pub mod config;
 pub mod errors;
+pub mod execution_notifier;
 pub mod gas_station;
 pub mod gas_station_initializer;
 pub mod iota_client;
+pub mod keystore;
 pub mod logging;
+pub mod low_balance_monitor;
 pub mod metrics;
+pub mod multisig_signer;
+pub mod pkcs11_signer;
+pub mod pool_drainer;
+pub mod pool_reconciler;
 pub mod rpc;
+pub mod sidecar_signer;
+pub mod signer_rotation;
 pub mod storage;
 pub mod tracker;
 
@@ -22,6 +34,8 @@ pub mod tx_signer;
 pub mod types;
 
 pub const AUTH_ENV_NAME: &str = "GAS_STATION_AUTH";
+pub const KEYSTORE_PASSPHRASE_ENV_NAME: &str = "GAS_STATION_KEYSTORE_PASSPHRASE";
+pub const PKCS11_PIN_ENV_NAME: &str = "GAS_STATION_PKCS11_PIN";
 pub const TRANSACTION_LOGGING_ENV_NAME: &str = "TRANSACTIONS_LOGGING";
 pub const TRANSACTION_LOGGING_TARGET_NAME: &str = "transactions";
 pub const GIT_REVISION: &str = {
@@ -0,0 +1,497 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::LowBalanceAlertConfig;
+use crate::iota_client::IotaClient;
+use crate::metrics::LowBalanceMonitorMetrics;
+use crate::storage::Storage;
+use crate::tx_signer::TxSigner;
+use anyhow::{bail, Context};
+use iota_json_rpc_types::IotaTransactionBlockEffectsAPI;
+use iota_types::base_types::IotaAddress;
+use iota_types::digests::TransactionDigest;
+use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use iota_types::transaction::{Transaction, TransactionData};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use url::Url;
+
+const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Delay before a failed refill is retried. It doubles with every further failure, up to the
+/// cooldown of the refill.
+const INITIAL_REFILL_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The low-water marks of the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LowWaterMark {
+    CoinCount,
+    TotalBalance,
+}
+
+impl LowWaterMark {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LowWaterMark::CoinCount => "coin-count",
+            LowWaterMark::TotalBalance => "total-balance",
+        }
+    }
+}
+
+/// The JSON body posted to the webhook when the pool drops below a low-water mark.
+#[derive(Clone, Debug, Serialize)]
+pub struct LowBalanceAlert {
+    pub sponsor: IotaAddress,
+    pub mark: LowWaterMark,
+    pub threshold: u64,
+    pub value: u64,
+}
+
+/// Treasury that new funds are requested from when the pool runs low.
+pub struct Refill {
+    pub iota_client: IotaClient,
+    pub signer: Arc<dyn TxSigner>,
+    pub amount: u64,
+    /// Minimum time between two refills.
+    pub cooldown: Duration,
+}
+
+/// Periodically compares the available coins of the pool with the low-water marks. When a mark is
+/// crossed, it logs a warning and calls the webhook. A mark fires once when it is crossed, and
+/// again only after the pool recovered in between.
+/// Independently of the alerts, the pool is refilled from a treasury on every check while any
+/// mark is crossed, at most once per cooldown. Failed refills are retried with a backoff.
+pub struct LowBalanceMonitor {
+    task_handle: JoinHandle<()>,
+    // This is always Some. It is None only after the drop or shutdown method is called.
+    cancel_sender: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl Drop for LowBalanceMonitor {
+    fn drop(&mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            cancel_sender.send(()).unwrap();
+        }
+    }
+}
+
+struct MonitorEnv {
+    storage: Arc<dyn Storage>,
+    sponsor_address: IotaAddress,
+    check_interval: Duration,
+    min_coin_count: Option<usize>,
+    min_total_balance: Option<u64>,
+    webhook_url: Option<Url>,
+    http_client: reqwest::Client,
+    refill: Option<Refill>,
+    /// Starts the coin init refresh after a refill, so that the new funds are split right away.
+    refresh_notifier: Option<Arc<Notify>>,
+    metrics: Arc<LowBalanceMonitorMetrics>,
+    /// The marks the pool is currently below.
+    crossed_marks: BTreeSet<LowWaterMark>,
+    /// The next refill is not attempted before this time.
+    next_refill_at: Option<Instant>,
+    /// The number of refills that failed in a row.
+    failed_refills: u32,
+}
+
+impl LowBalanceMonitor {
+    pub fn start(
+        storage: Arc<dyn Storage>,
+        sponsor_address: IotaAddress,
+        config: LowBalanceAlertConfig,
+        refill: Option<Refill>,
+        refresh_notifier: Option<Arc<Notify>>,
+        metrics: Arc<LowBalanceMonitorMetrics>,
+    ) -> Self {
+        let mut env = MonitorEnv::new(
+            storage,
+            sponsor_address,
+            config,
+            refill,
+            refresh_notifier,
+            metrics,
+        );
+        let (cancel_sender, mut cancel_receiver) = tokio::sync::oneshot::channel();
+        let task_handle = tokio::spawn(async move {
+            loop {
+                env.check_once().await;
+                tokio::select! {
+                    _ = tokio::time::sleep(env.check_interval) => {}
+                    _ = &mut cancel_receiver => {
+                        info!("Low balance monitor task is cancelled");
+                        break;
+                    }
+                }
+            }
+        });
+        Self {
+            task_handle,
+            cancel_sender: Some(cancel_sender),
+        }
+    }
+
+    /// Cancels the monitor task and waits for it to stop.
+    pub async fn shutdown(mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            let _ = cancel_sender.send(());
+        }
+        if let Err(err) = (&mut self.task_handle).await {
+            error!("Low balance monitor task failed: {:?}", err);
+        }
+    }
+}
+
+impl MonitorEnv {
+    fn new(
+        storage: Arc<dyn Storage>,
+        sponsor_address: IotaAddress,
+        config: LowBalanceAlertConfig,
+        refill: Option<Refill>,
+        refresh_notifier: Option<Arc<Notify>>,
+        metrics: Arc<LowBalanceMonitorMetrics>,
+    ) -> Self {
+        Self {
+            storage,
+            sponsor_address,
+            check_interval: Duration::from_secs(config.check_interval_sec),
+            min_coin_count: config.min_coin_count,
+            min_total_balance: config.min_total_balance,
+            webhook_url: config.webhook_url,
+            http_client: reqwest::Client::builder()
+                .timeout(WEBHOOK_REQUEST_TIMEOUT)
+                .build()
+                .unwrap(),
+            refill,
+            refresh_notifier,
+            metrics,
+            crossed_marks: BTreeSet::new(),
+            next_refill_at: None,
+            failed_refills: 0,
+        }
+    }
+
+    async fn check_once(&mut self) {
+        let coin_count = match self.storage.get_available_coin_count().await {
+            Ok(coin_count) => coin_count as u64,
+            Err(err) => {
+                error!("Failed to get the available coin count: {:?}", err);
+                return;
+            }
+        };
+        let total_balance = self.storage.get_available_coin_total_balance().await;
+
+        let sponsor = self.sponsor_address.to_string();
+        let mut alerts = vec![];
+        for (mark, threshold, value) in [
+            (
+                LowWaterMark::CoinCount,
+                self.min_coin_count.map(|count| count as u64),
+                coin_count,
+            ),
+            (
+                LowWaterMark::TotalBalance,
+                self.min_total_balance,
+                total_balance,
+            ),
+        ] {
+            let Some(threshold) = threshold else {
+                continue;
+            };
+            let is_low = value < threshold;
+            self.metrics
+                .low_balance_alert
+                .with_label_values(&[&sponsor, mark.as_str()])
+                .set(is_low as i64);
+            if !is_low {
+                self.crossed_marks.remove(&mark);
+            } else if self.crossed_marks.insert(mark) {
+                warn!(
+                    "The pool of sponsor {} dropped below the {} low-water mark: {} < {}",
+                    sponsor,
+                    mark.as_str(),
+                    value,
+                    threshold
+                );
+                self.metrics
+                    .num_low_balance_alerts
+                    .with_label_values(&[&sponsor, mark.as_str()])
+                    .inc();
+                alerts.push(LowBalanceAlert {
+                    sponsor: self.sponsor_address,
+                    mark,
+                    threshold,
+                    value,
+                });
+            }
+        }
+        for alert in &alerts {
+            if let Err(err) = self.call_webhook(alert).await {
+                error!("Failed to call the low balance webhook: {:?}", err);
+                self.metrics.num_failed_low_balance_webhooks.inc();
+            }
+        }
+        if !self.crossed_marks.is_empty() {
+            self.refill_if_due().await;
+        }
+    }
+
+    /// Refills the pool from the treasury, unless the last refill is within its cooldown or the
+    /// last failed refill within its backoff.
+    async fn refill_if_due(&mut self) {
+        let Some(refill) = &self.refill else {
+            return;
+        };
+        let now = Instant::now();
+        if self.next_refill_at.is_some_and(|next| now < next) {
+            return;
+        }
+        match refill_from_treasury(refill, self.sponsor_address).await {
+            Ok(digest) => {
+                info!(
+                    ?digest,
+                    "Transferred {} from the treasury {} to the sponsor",
+                    refill.amount,
+                    refill.signer.get_address()
+                );
+                self.metrics.num_pool_refills.inc();
+                self.failed_refills = 0;
+                self.next_refill_at = Some(now + refill.cooldown);
+                if let Some(refresh_notifier) = &self.refresh_notifier {
+                    refresh_notifier.notify_one();
+                }
+            }
+            Err(err) => {
+                self.failed_refills += 1;
+                let retry_interval = INITIAL_REFILL_RETRY_INTERVAL
+                    .saturating_mul(1 << (self.failed_refills - 1).min(16))
+                    .min(refill.cooldown);
+                error!(
+                    "Failed to refill the pool from the treasury, retrying in {:?}: {:?}",
+                    retry_interval, err
+                );
+                self.metrics.num_failed_pool_refills.inc();
+                self.next_refill_at = Some(now + retry_interval);
+            }
+        }
+    }
+
+    async fn call_webhook(&self, alert: &LowBalanceAlert) -> anyhow::Result<()> {
+        let Some(webhook_url) = &self.webhook_url else {
+            return Ok(());
+        };
+        let response = self
+            .http_client
+            .post(webhook_url.clone())
+            .json(alert)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!("Webhook responded with status {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Transfers `refill.amount` from the treasury to the sponsor, paying for gas with a single
+/// treasury coin that covers both.
+pub async fn refill_from_treasury(
+    refill: &Refill,
+    sponsor_address: IotaAddress,
+) -> anyhow::Result<TransactionDigest> {
+    let iota_client = &refill.iota_client;
+    let treasury_address = refill.signer.get_address();
+    let build_pt = || {
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        pt_builder.pay_iota(vec![sponsor_address], vec![refill.amount])?;
+        anyhow::Ok(pt_builder.finish())
+    };
+    let budget = iota_client
+        .estimate_gas_budget(treasury_address, build_pt()?)
+        .await?;
+    let gas_coin = iota_client
+        .get_all_owned_iota_coins_above_balance_threshold(treasury_address, refill.amount + budget)
+        .await
+        .into_iter()
+        .max_by_key(|coin| coin.balance)
+        .with_context(|| {
+            format!(
+                "Treasury {} has no coin with a balance of at least {}",
+                treasury_address,
+                refill.amount + budget
+            )
+        })?;
+    let rgp = iota_client.get_reference_gas_price().await;
+    let tx_data = TransactionData::new_programmable(
+        treasury_address,
+        vec![gas_coin.object_ref],
+        build_pt()?,
+        budget,
+        rgp,
+    );
+    let sig = refill.signer.sign_transaction(&tx_data).await?;
+    let tx = Transaction::from_generic_sig_data(tx_data, vec![sig]);
+    let effects = iota_client.execute_transaction(tx, 3, None).await?;
+    if !effects.status().is_ok() {
+        bail!("Refill transaction failed: {:?}", effects.status());
+    }
+    Ok(*effects.transaction_digest())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::connect_storage_for_testing;
+    use crate::test_env::start_iota_cluster;
+    use crate::types::GasCoin;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use iota_types::base_types::random_object_ref;
+    use iota_types::gas_coin::NANOS_PER_IOTA;
+    use parking_lot::Mutex;
+
+    #[tokio::test]
+    async fn test_low_balance_alert() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let webhook_url =
+            Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let received = Arc::new(Mutex::new(vec![]));
+        let app = Router::new().route(
+            "/",
+            post({
+                let received = received.clone();
+                move |Json(body): Json<serde_json::Value>| async move {
+                    received.lock().push(body);
+                }
+            }),
+        );
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let add_coins = |count: usize| {
+            let storage = storage.clone();
+            async move {
+                let coins = (0..count)
+                    .map(|_| GasCoin {
+                        object_ref: random_object_ref(),
+                        balance: 100,
+                    })
+                    .collect();
+                storage.add_new_coins(coins).await.unwrap();
+            }
+        };
+        add_coins(3).await;
+
+        let metrics = LowBalanceMonitorMetrics::new_for_testing();
+        let mut env = MonitorEnv::new(
+            storage.clone(),
+            sponsor,
+            LowBalanceAlertConfig {
+                check_interval_sec: 60,
+                min_coin_count: Some(5),
+                min_total_balance: Some(200),
+                webhook_url: Some(webhook_url),
+                refill: None,
+            },
+            None,
+            None,
+            metrics.clone(),
+        );
+        let alert_gauge = |mark: LowWaterMark| {
+            metrics
+                .low_balance_alert
+                .with_label_values(&[&sponsor.to_string(), mark.as_str()])
+                .get()
+        };
+
+        // Only the coin count is low, and it fires only once.
+        env.check_once().await;
+        env.check_once().await;
+        assert_eq!(alert_gauge(LowWaterMark::CoinCount), 1);
+        assert_eq!(alert_gauge(LowWaterMark::TotalBalance), 0);
+        assert_eq!(
+            *received.lock(),
+            vec![serde_json::json!({
+                "sponsor": sponsor,
+                "mark": "coin-count",
+                "threshold": 5,
+                "value": 3,
+            })]
+        );
+
+        // The alert is cleared once the pool recovers, and fires again on the next drop.
+        add_coins(2).await;
+        env.check_once().await;
+        assert_eq!(alert_gauge(LowWaterMark::CoinCount), 0);
+        storage.reserve_gas_coins(100, 1000).await.unwrap();
+        env.check_once().await;
+        assert_eq!(alert_gauge(LowWaterMark::CoinCount), 1);
+        assert_eq!(received.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refill_retry() {
+        telemetry_subscribers::init_for_testing();
+        let (cluster, treasury_signer) = start_iota_cluster(vec![NANOS_PER_IOTA; 10]).await;
+        let iota_client = IotaClient::new(&cluster.fullnode_handle.rpc_url, None).await;
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+
+        let metrics = LowBalanceMonitorMetrics::new_for_testing();
+        let mut env = MonitorEnv::new(
+            storage,
+            sponsor,
+            LowBalanceAlertConfig {
+                check_interval_sec: 60,
+                min_coin_count: Some(1),
+                min_total_balance: None,
+                webhook_url: None,
+                refill: None,
+            },
+            // The treasury has no coin that covers the amount, so the first refill fails.
+            Some(Refill {
+                iota_client: iota_client.clone(),
+                signer: treasury_signer,
+                amount: 100 * NANOS_PER_IOTA,
+                cooldown: Duration::from_secs(600),
+            }),
+            None,
+            metrics.clone(),
+        );
+        env.check_once().await;
+        assert_eq!(metrics.num_failed_pool_refills.get(), 1);
+
+        // The refill is not retried before its backoff, although no new alert fires.
+        env.refill.as_mut().unwrap().amount = NANOS_PER_IOTA / 10;
+        env.check_once().await;
+        assert_eq!(metrics.num_failed_pool_refills.get(), 1);
+        assert_eq!(metrics.num_pool_refills.get(), 0);
+
+        // Once the backoff passed, the refill is retried while the pool is still low.
+        env.next_refill_at = Some(Instant::now());
+        env.check_once().await;
+        assert_eq!(metrics.num_pool_refills.get(), 1);
+        assert_eq!(
+            iota_client
+                .get_all_owned_iota_coins_above_balance_threshold(sponsor, 0)
+                .await
+                .len(),
+            1
+        );
+
+        // The pool is not refilled again within the cooldown.
+        env.check_once().await;
+        assert_eq!(metrics.num_pool_refills.get(), 1);
+        assert_eq!(metrics.num_failed_pool_refills.get(), 1);
+    }
+}
@@ -3,8 +3,11 @@
 
 use iota_metrics::histogram::Histogram;
 use prometheus::{
-    register_int_counter_vec_with_registry, register_int_counter_with_registry,
-    register_int_gauge_vec_with_registry, IntCounter, IntCounterVec, IntGaugeVec, Registry,
+    exponential_buckets, register_gauge_vec_with_registry, register_histogram_vec_with_registry,
+    register_histogram_with_registry, register_int_counter_vec_with_registry,
+    register_int_counter_with_registry, register_int_gauge_vec_with_registry,
+    register_int_gauge_with_registry, GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Registry,
 };
 use std::sync::Arc;
 use tracing::error;
@@ -20,6 +23,9 @@ pub struct GasStationRpcMetrics {
     // Statistics about the gas reservation request
     pub target_gas_budget_per_request: Histogram,
     pub reserve_duration_per_request: Histogram,
+    /// Same as `target_gas_budget_per_request`, with fixed buckets whose counts can be read back
+    /// to size new gas coins.
+    pub target_gas_budget_distribution: prometheus::Histogram,
 
     // RPC metrics for the execute_tx endpoint
     pub num_execute_tx_requests: IntCounter,
@@ -30,6 +36,8 @@ pub struct GasStationRpcMetrics {
     /// Access controller metrics
     pub num_allowed_execute_tx_requests: IntCounter,
     pub num_blocked_execute_tx_requests: IntCounter,
+    pub hook_request_latency_ms: HistogramVec,
+    pub num_hook_errors: IntCounterVec,
 }
 
 impl GasStationRpcMetrics {
@@ -69,6 +77,14 @@ impl GasStationRpcMetrics {
                 "Reserve duration value in the reserve_gas RPC request",
                 registry,
             ),
+            target_gas_budget_distribution: register_histogram_with_registry!(
+                "target_gas_budget_distribution",
+                "Distribution of the target gas budget in the reserve_gas RPC request",
+                // From 0.001 IOTA to about 500 IOTA.
+                exponential_buckets(1_000_000.0, 2.0, 20).unwrap(),
+                registry,
+            )
+            .unwrap(),
             num_execute_tx_requests: register_int_counter_with_registry!(
                 "num_execute_tx_requests",
                 "Total number of execute_tx RPC requests received",
@@ -105,6 +121,22 @@ impl GasStationRpcMetrics {
                 registry,
             )
             .unwrap(),
+            hook_request_latency_ms: register_histogram_vec_with_registry!(
+                "hook_request_latency_ms",
+                "Latency of requests to access controller hooks",
+                &["hook"],
+                // From 5 ms to about 40 seconds.
+                exponential_buckets(5.0, 2.0, 14).unwrap(),
+                registry,
+            )
+            .unwrap(),
+            num_hook_errors: register_int_counter_vec_with_registry!(
+                "num_hook_errors",
+                "Total number of failed calls of access controller hooks, by kind of failure",
+                &["hook", "kind"],
+                registry,
+            )
+            .unwrap(),
         })
     }
 
@@ -116,12 +148,14 @@ impl GasStationRpcMetrics {
 pub struct GasStationCoreMetrics {
     pub num_expired_gas_coins: IntCounterVec,
     pub num_smashed_gas_coins: IntCounterVec,
+    pub num_recovered_gas_coins: IntCounterVec,
     pub reserved_gas_coin_count_per_request: Histogram,
     pub reserve_gas_latency_ms: Histogram,
     pub transaction_signing_latency_ms: Histogram,
     pub transaction_execution_latency_ms: Histogram,
     pub num_gas_station_invariant_violations: IntCounter,
     pub daily_gas_usage: IntGaugeVec,
+    pub num_invalid_signatures: IntCounterVec,
 }
 
 impl GasStationCoreMetrics {
@@ -146,6 +180,13 @@ impl GasStationCoreMetrics {
                 registry,
             )
                 .unwrap(),
+            num_recovered_gas_coins: register_int_counter_vec_with_registry!(
+                "num_recovered_gas_coins",
+                "Total number of gas coins that are put back due to execution lease expiration",
+                &["sponsor"],
+                registry,
+            )
+                .unwrap(),
             reserve_gas_latency_ms: Histogram::new_in_registry(
                 "reserve_gas_latency",
                 "Latency of gas reservation, in milliseconds",
@@ -174,6 +215,13 @@ impl GasStationCoreMetrics {
                 registry,
             )
                 .unwrap(),
+            num_invalid_signatures: register_int_counter_vec_with_registry!(
+                "num_invalid_signatures",
+                "Total number of transactions rejected before submission because the user or the sponsor signature is invalid",
+                &["sponsor", "signature"],
+                registry,
+            )
+                .unwrap(),
         })
     }
 
@@ -197,12 +245,20 @@ pub struct StorageMetrics {
 
     pub num_reserve_gas_coins_requests: IntCounter,
     pub num_successful_reserve_gas_coins_requests: IntCounter,
+    pub num_reserve_dust_coins_requests: IntCounter,
+    pub num_successful_reserve_dust_coins_requests: IntCounter,
     pub num_ready_for_execution_requests: IntCounter,
     pub num_successful_ready_for_execution_requests: IntCounter,
     pub num_add_new_coins_requests: IntCounter,
     pub num_successful_add_new_coins_requests: IntCounter,
     pub num_expire_coins_requests: IntCounter,
     pub num_successful_expire_coins_requests: IntCounter,
+    pub num_complete_execution_requests: IntCounter,
+    pub num_successful_complete_execution_requests: IntCounter,
+    pub num_expire_execution_leases_requests: IntCounter,
+    pub num_successful_expire_execution_leases_requests: IntCounter,
+    pub num_reconcile_coins_requests: IntCounter,
+    pub num_successful_reconcile_coins_requests: IntCounter,
 }
 
 impl StorageMetrics {
@@ -234,6 +290,18 @@ impl StorageMetrics {
                 registry,
             )
             .unwrap(),
+            num_reserve_dust_coins_requests: register_int_counter_with_registry!(
+                "num_reserve_dust_coins_requests",
+                "Total number of reserve_dust_coins requests received",
+                registry,
+            )
+            .unwrap(),
+            num_successful_reserve_dust_coins_requests: register_int_counter_with_registry!(
+                "num_successful_reserve_dust_coins_requests",
+                "Total number of reserve_dust_coins requests that were successful",
+                registry,
+            )
+            .unwrap(),
             num_ready_for_execution_requests: register_int_counter_with_registry!(
                 "num_ready_for_execution_requests",
                 "Total number of ready_for_execution requests received",
@@ -270,6 +338,236 @@ impl StorageMetrics {
                 registry,
             )
             .unwrap(),
+            num_complete_execution_requests: register_int_counter_with_registry!(
+                "num_complete_execution_requests",
+                "Total number of complete_execution requests received",
+                registry,
+            )
+            .unwrap(),
+            num_successful_complete_execution_requests: register_int_counter_with_registry!(
+                "num_successful_complete_execution_requests",
+                "Total number of complete_execution requests that were successful",
+                registry,
+            )
+            .unwrap(),
+            num_expire_execution_leases_requests: register_int_counter_with_registry!(
+                "num_expire_execution_leases_requests",
+                "Total number of expire_execution_leases requests received",
+                registry,
+            )
+            .unwrap(),
+            num_successful_expire_execution_leases_requests: register_int_counter_with_registry!(
+                "num_successful_expire_execution_leases_requests",
+                "Total number of expire_execution_leases requests that were successful",
+                registry,
+            )
+            .unwrap(),
+            num_reconcile_coins_requests: register_int_counter_with_registry!(
+                "num_reconcile_coins_requests",
+                "Total number of reconcile_coins requests received",
+                registry,
+            )
+            .unwrap(),
+            num_successful_reconcile_coins_requests: register_int_counter_with_registry!(
+                "num_successful_reconcile_coins_requests",
+                "Total number of reconcile_coins requests that were successful",
+                registry,
+            )
+            .unwrap(),
+        })
+    }
+
+    pub fn new_for_testing() -> Arc<Self> {
+        Self::new(&Registry::new())
+    }
+}
+
+pub struct PoolReconcilerMetrics {
+    pub num_pool_reconciliations: IntCounter,
+    pub num_failed_pool_reconciliations: IntCounter,
+    pub num_reconciled_gas_coins: IntCounterVec,
+}
+
+impl PoolReconcilerMetrics {
+    pub fn new(registry: &Registry) -> Arc<Self> {
+        Arc::new(Self {
+            num_pool_reconciliations: register_int_counter_with_registry!(
+                "num_pool_reconciliations",
+                "Total number of reconciliations of the pool with the coins owned on chain",
+                registry,
+            )
+            .unwrap(),
+            num_failed_pool_reconciliations: register_int_counter_with_registry!(
+                "num_failed_pool_reconciliations",
+                "Total number of reconciliations of the pool that failed",
+                registry,
+            )
+            .unwrap(),
+            num_reconciled_gas_coins: register_int_counter_vec_with_registry!(
+                "num_reconciled_gas_coins",
+                "Total number of gas coins fixed by pool reconciliation, by kind of difference",
+                &["sponsor", "kind"],
+                registry,
+            )
+            .unwrap(),
+        })
+    }
+
+    pub fn new_for_testing() -> Arc<Self> {
+        Self::new(&Registry::new())
+    }
+}
+
+pub struct CoinConsolidatorMetrics {
+    pub num_coin_consolidations: IntCounter,
+    pub num_failed_coin_consolidations: IntCounter,
+    pub num_consolidated_gas_coins: IntCounterVec,
+}
+
+impl CoinConsolidatorMetrics {
+    pub fn new(registry: &Registry) -> Arc<Self> {
+        Arc::new(Self {
+            num_coin_consolidations: register_int_counter_with_registry!(
+                "num_coin_consolidations",
+                "Total number of transactions merging low-balance gas coins",
+                registry,
+            )
+            .unwrap(),
+            num_failed_coin_consolidations: register_int_counter_with_registry!(
+                "num_failed_coin_consolidations",
+                "Total number of transactions merging low-balance gas coins that failed",
+                registry,
+            )
+            .unwrap(),
+            num_consolidated_gas_coins: register_int_counter_vec_with_registry!(
+                "num_consolidated_gas_coins",
+                "Total number of low-balance gas coins merged into larger coins",
+                &["sponsor"],
+                registry,
+            )
+            .unwrap(),
+        })
+    }
+
+    pub fn new_for_testing() -> Arc<Self> {
+        Self::new(&Registry::new())
+    }
+}
+
+pub struct CoinSizingMetrics {
+    pub coin_denomination_share: GaugeVec,
+}
+
+impl CoinSizingMetrics {
+    pub fn new(registry: &Registry) -> Arc<Self> {
+        Arc::new(Self {
+            coin_denomination_share: register_gauge_vec_with_registry!(
+                "coin_denomination_share",
+                "Fraction of the new gas coins that are split with each balance",
+                &["sponsor", "balance"],
+                registry,
+            )
+            .unwrap(),
+        })
+    }
+
+    pub fn new_for_testing() -> Arc<Self> {
+        Self::new(&Registry::new())
+    }
+}
+
+pub struct LowBalanceMonitorMetrics {
+    pub low_balance_alert: IntGaugeVec,
+    pub num_low_balance_alerts: IntCounterVec,
+    pub num_failed_low_balance_webhooks: IntCounter,
+    pub num_pool_refills: IntCounter,
+    pub num_failed_pool_refills: IntCounter,
+}
+
+impl LowBalanceMonitorMetrics {
+    pub fn new(registry: &Registry) -> Arc<Self> {
+        Arc::new(Self {
+            low_balance_alert: register_int_gauge_vec_with_registry!(
+                "low_balance_alert",
+                "Whether the pool is below the low-water mark, 1 if it is and 0 otherwise",
+                &["sponsor", "mark"],
+                registry,
+            )
+            .unwrap(),
+            num_low_balance_alerts: register_int_counter_vec_with_registry!(
+                "num_low_balance_alerts",
+                "Total number of times the pool dropped below a low-water mark",
+                &["sponsor", "mark"],
+                registry,
+            )
+            .unwrap(),
+            num_failed_low_balance_webhooks: register_int_counter_with_registry!(
+                "num_failed_low_balance_webhooks",
+                "Total number of low balance webhook calls that failed",
+                registry,
+            )
+            .unwrap(),
+            num_pool_refills: register_int_counter_with_registry!(
+                "num_pool_refills",
+                "Total number of transfers of new funds from the treasury",
+                registry,
+            )
+            .unwrap(),
+            num_failed_pool_refills: register_int_counter_with_registry!(
+                "num_failed_pool_refills",
+                "Total number of transfers of new funds from the treasury that failed",
+                registry,
+            )
+            .unwrap(),
+        })
+    }
+
+    pub fn new_for_testing() -> Arc<Self> {
+        Self::new(&Registry::new())
+    }
+}
+
+pub struct ExecutionNotificationMetrics {
+    pub num_delivered_notifications: IntCounter,
+    pub num_failed_notification_deliveries: IntCounter,
+    pub notification_outbox_size: IntGauge,
+    pub num_dead_lettered_notifications: IntCounter,
+    pub notification_dead_letter_size: IntGauge,
+}
+
+impl ExecutionNotificationMetrics {
+    pub fn new(registry: &Registry) -> Arc<Self> {
+        Arc::new(Self {
+            num_delivered_notifications: register_int_counter_with_registry!(
+                "num_delivered_notifications",
+                "Total number of execution notifications delivered to their webhook",
+                registry,
+            )
+            .unwrap(),
+            num_failed_notification_deliveries: register_int_counter_with_registry!(
+                "num_failed_notification_deliveries",
+                "Total number of failed attempts to deliver an execution notification",
+                registry,
+            )
+            .unwrap(),
+            notification_outbox_size: register_int_gauge_with_registry!(
+                "notification_outbox_size",
+                "Number of execution notifications that are not delivered yet",
+                registry,
+            )
+            .unwrap(),
+            num_dead_lettered_notifications: register_int_counter_with_registry!(
+                "num_dead_lettered_notifications",
+                "Total number of execution notifications moved to the dead letters, because they could not be delivered",
+                registry,
+            )
+            .unwrap(),
+            notification_dead_letter_size: register_int_gauge_with_registry!(
+                "notification_dead_letter_size",
+                "Number of execution notifications in the dead letters",
+                registry,
+            )
+            .unwrap(),
         })
     }
 
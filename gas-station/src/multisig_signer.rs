@@ -0,0 +1,274 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::tx_signer::TxSigner;
+use anyhow::{anyhow, bail};
+use futures_util::future::join_all;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use iota_types::base_types::IotaAddress;
+use iota_types::crypto::PublicKey;
+use iota_types::multisig::{MultiSig, MultiSigPublicKey};
+use iota_types::signature::GenericSignature;
+use iota_types::transaction::TransactionData;
+use std::sync::Arc;
+use tracing::warn;
+
+/// A member of a multisig sponsor account.
+pub struct MultisigMember {
+    pub signer: Arc<dyn TxSigner>,
+    pub public_key: PublicKey,
+    pub weight: u8,
+}
+
+/// Signs for a k-of-n multisig address by asking its member signers for partial signatures
+/// concurrently. The signature is assembled as soon as the members that responded reach the
+/// threshold, so a failing or slow member doesn't block the others.
+pub struct MultisigTxSigner {
+    members: Vec<MultisigMember>,
+    multisig_pk: MultiSigPublicKey,
+    address: IotaAddress,
+}
+
+impl MultisigTxSigner {
+    pub fn new(members: Vec<MultisigMember>, threshold: u16) -> anyhow::Result<Arc<Self>> {
+        for member in &members {
+            let address = IotaAddress::from(&member.public_key);
+            if !member.signer.is_valid_address(&address) {
+                bail!(
+                    "The public key of the member {} doesn't match its signer address {}",
+                    address,
+                    member.signer.get_address()
+                );
+            }
+        }
+        let multisig_pk = MultiSigPublicKey::new(
+            members
+                .iter()
+                .map(|member| member.public_key.clone())
+                .collect(),
+            members.iter().map(|member| member.weight).collect(),
+            threshold,
+        )
+        .map_err(|err| anyhow!("Invalid multisig configuration: {}", err))?;
+        let address = IotaAddress::from(&multisig_pk);
+        Ok(Arc::new(Self {
+            members,
+            multisig_pk,
+            address,
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl TxSigner for MultisigTxSigner {
+    async fn sign_transaction(
+        &self,
+        tx_data: &TransactionData,
+    ) -> anyhow::Result<GenericSignature> {
+        let mut pending: FuturesUnordered<_> =
+            self.members
+                .iter()
+                .enumerate()
+                .map(|(index, member)| async move {
+                    (index, member.signer.sign_transaction(tx_data).await)
+                })
+                .collect();
+        let threshold = *self.multisig_pk.threshold();
+        let mut weight = 0u16;
+        let mut partial_sigs = vec![];
+        let mut errors = vec![];
+        while let Some((index, result)) = pending.next().await {
+            let member = &self.members[index];
+            match result {
+                Ok(GenericSignature::Signature(signature)) => {
+                    partial_sigs.push((index, signature));
+                    weight += member.weight as u16;
+                    if weight >= threshold {
+                        break;
+                    }
+                }
+                Ok(other) => {
+                    errors.push(format!(
+                        "{}: unsupported signature {:?}",
+                        member.signer.get_address(),
+                        other
+                    ));
+                }
+                Err(err) => {
+                    warn!(
+                        "Multisig member {} failed to sign: {:?}",
+                        member.signer.get_address(),
+                        err
+                    );
+                    errors.push(format!("{}: {}", member.signer.get_address(), err));
+                }
+            }
+        }
+        if weight < threshold {
+            bail!(
+                "Only reached weight {} of the multisig threshold {}. Errors: {}",
+                weight,
+                threshold,
+                errors.join("; ")
+            );
+        }
+        // The partial signatures are expected in the order of the members.
+        partial_sigs.sort_by_key(|(index, _)| *index);
+        let multisig = MultiSig::combine(
+            partial_sigs
+                .into_iter()
+                .map(|(_, signature)| signature)
+                .collect(),
+            self.multisig_pk.clone(),
+        )
+        .map_err(|err| anyhow!("Failed to combine the multisig signature: {}", err))?;
+        Ok(GenericSignature::MultiSig(multisig))
+    }
+
+    fn get_address(&self) -> IotaAddress {
+        self.address
+    }
+
+    async fn check_health(&self) -> anyhow::Result<()> {
+        let results = join_all(
+            self.members
+                .iter()
+                .map(|member| member.signer.check_health()),
+        )
+        .await;
+        let weight: u16 = self
+            .members
+            .iter()
+            .zip(results)
+            .filter(|(_, result)| result.is_ok())
+            .map(|(member, _)| member.weight as u16)
+            .sum();
+        if weight < *self.multisig_pk.threshold() {
+            bail!(
+                "The healthy multisig members only reach weight {} of the threshold {}",
+                weight,
+                self.multisig_pk.threshold()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iota_client::IotaClient;
+    use crate::pool_drainer::drain_pool;
+    use crate::storage::connect_storage_for_testing;
+    use crate::test_env::start_iota_cluster;
+    use crate::tx_signer::TestTxSigner;
+    use iota_types::crypto::{get_key_pair, AccountKeyPair, IotaKeyPair};
+    use iota_types::gas_coin::NANOS_PER_IOTA;
+    use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use iota_types::transaction::TransactionKind;
+    use std::time::Duration;
+
+    struct FailingTxSigner {
+        address: IotaAddress,
+    }
+
+    #[async_trait::async_trait]
+    impl TxSigner for FailingTxSigner {
+        async fn sign_transaction(
+            &self,
+            _tx_data: &TransactionData,
+        ) -> anyhow::Result<GenericSignature> {
+            bail!("The signer is unavailable")
+        }
+
+        fn get_address(&self) -> IotaAddress {
+            self.address
+        }
+    }
+
+    fn new_member(failing: bool) -> MultisigMember {
+        let (_, keypair): (_, AccountKeyPair) = get_key_pair();
+        let keypair = IotaKeyPair::Ed25519(keypair);
+        let public_key = keypair.public();
+        let signer: Arc<dyn TxSigner> = if failing {
+            Arc::new(FailingTxSigner {
+                address: IotaAddress::from(&public_key),
+            })
+        } else {
+            TestTxSigner::new(keypair)
+        };
+        MultisigMember {
+            signer,
+            public_key,
+            weight: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multisig_threshold_not_met() {
+        let signer = MultisigTxSigner::new(
+            vec![new_member(false), new_member(true), new_member(true)],
+            2,
+        )
+        .unwrap();
+        let tx_data = TransactionData::new_with_gas_coins_allow_sponsor(
+            TransactionKind::programmable(ProgrammableTransactionBuilder::new().finish()),
+            IotaAddress::random_for_testing_only(),
+            vec![],
+            1,
+            1,
+            signer.get_address(),
+        );
+        let err = signer.sign_transaction(&tx_data).await.unwrap_err();
+        assert!(err.to_string().contains("threshold"));
+
+        // The public key must match the signer.
+        let mut member = new_member(false);
+        member.public_key = new_member(false).public_key;
+        assert!(MultisigTxSigner::new(vec![member], 1).is_err());
+        // The threshold must be reachable.
+        assert!(MultisigTxSigner::new(vec![new_member(false)], 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multisig_sponsor() {
+        telemetry_subscribers::init_for_testing();
+        let (cluster, signer) = start_iota_cluster(vec![NANOS_PER_IOTA; 1]).await;
+        let fullnode_url = cluster.fullnode_handle.rpc_url.clone();
+        let iota_client = IotaClient::new(&fullnode_url, None).await;
+        // A 2-of-3 multisig with one unavailable member.
+        let multisig: Arc<dyn TxSigner> = MultisigTxSigner::new(
+            vec![new_member(false), new_member(true), new_member(false)],
+            2,
+        )
+        .unwrap();
+        let multisig_address = multisig.get_address();
+
+        // Fund the multisig address, then send the funds back with transactions signed by it.
+        let storage = connect_storage_for_testing(signer.get_address()).await;
+        drain_pool(
+            &iota_client,
+            &storage,
+            &signer,
+            multisig_address,
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        let multisig_storage = connect_storage_for_testing(multisig_address).await;
+        let summary = drain_pool(
+            &iota_client,
+            &multisig_storage,
+            &multisig,
+            signer.get_address(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.merged_coins, 1);
+        assert!(iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(multisig_address, 0)
+            .await
+            .is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::tx_signer::TxSigner;
+use anyhow::{anyhow, bail, Context};
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::error::{Error as Pkcs11Error, RvError};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use fastcrypto::hash::{Blake2b256, HashFunction, Sha256};
+use fastcrypto::traits::ToFromBytes;
+use iota_types::base_types::IotaAddress;
+use iota_types::crypto::{PublicKey, Signature, SignatureScheme};
+use iota_types::signature::GenericSignature;
+use iota_types::transaction::TransactionData;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use shared_crypto::intent::{Intent, IntentMessage};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// DER encoded object identifiers of the supported curves, as found in `CKA_EC_PARAMS`.
+const SECP256K1_OID: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+const SECP256R1_OID: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+/// Orders of the curves, big endian.
+const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+const SECP256R1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+
+/// A PKCS#11 library can only be initialized once per process, so all signers using the same
+/// module share its context. Contexts are kept for the lifetime of the process.
+static CONTEXTS: Lazy<Mutex<HashMap<PathBuf, Arc<Pkcs11>>>> = Lazy::new(Default::default);
+
+/// Returns the initialized context of the PKCS#11 library at `module_path`, loading it on first
+/// use.
+fn pkcs11_context(module_path: &Path) -> anyhow::Result<Arc<Pkcs11>> {
+    let mut contexts = CONTEXTS.lock();
+    if let Some(pkcs11) = contexts.get(module_path) {
+        return Ok(pkcs11.clone());
+    }
+    let pkcs11 = Pkcs11::new(module_path)
+        .with_context(|| format!("Failed to load {}", module_path.display()))?;
+    pkcs11.initialize(CInitializeArgs::OsThreads)?;
+    let pkcs11 = Arc::new(pkcs11);
+    contexts.insert(module_path.to_path_buf(), pkcs11.clone());
+    Ok(pkcs11)
+}
+
+/// Signs with an ECDSA key held by a PKCS#11 token, e.g. a hardware or cloud HSM. secp256k1 and
+/// secp256r1 keys are supported. The key never leaves the token: the gas station computes the
+/// digest of the transaction and only the raw ECDSA signature is computed by the token.
+pub struct Pkcs11TxSigner {
+    // PKCS#11 sessions can't be used concurrently, so signing requests are spread over a few
+    // sessions.
+    sessions: Vec<Arc<Mutex<Session>>>,
+    next_session: AtomicUsize,
+    private_key: ObjectHandle,
+    scheme: SignatureScheme,
+    public_key: PublicKey,
+    address: IotaAddress,
+}
+
+impl Pkcs11TxSigner {
+    /// Opens `num_sessions` sessions on the token labeled `token_label`, logs in with `pin` and
+    /// looks up the key pair labeled `key_label`.
+    pub fn new(
+        module_path: &Path,
+        token_label: &str,
+        key_label: &str,
+        pin: String,
+        num_sessions: usize,
+    ) -> anyhow::Result<Arc<Self>> {
+        let pkcs11 = pkcs11_context(module_path)?;
+        let slot = pkcs11
+            .get_slots_with_token()?
+            .into_iter()
+            .find(|slot| {
+                pkcs11
+                    .get_token_info(*slot)
+                    .map(|info| info.label() == token_label)
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| anyhow!("No token labeled {:?}", token_label))?;
+
+        let mut sessions = Vec::with_capacity(num_sessions.max(1));
+        for _ in 0..num_sessions.max(1) {
+            sessions.push(pkcs11.open_ro_session(slot)?);
+        }
+        // The login applies to all sessions of the application on the token, so it's already done
+        // if another signer uses a key of the same token.
+        match sessions[0].login(UserType::User, Some(&AuthPin::new(pin))) {
+            Err(Pkcs11Error::Pkcs11(RvError::UserAlreadyLoggedIn, ..)) => {}
+            result => result.context("Failed to log in to the token")?,
+        }
+
+        let session = &sessions[0];
+        let label = Attribute::Label(key_label.as_bytes().to_vec());
+        let private_key = find_single_object(session, ObjectClass::PRIVATE_KEY, &label)?;
+        let public_key_handle = find_single_object(session, ObjectClass::PUBLIC_KEY, &label)?;
+        let attributes = session.get_attributes(
+            public_key_handle,
+            &[AttributeType::EcParams, AttributeType::EcPoint],
+        )?;
+        let (mut ec_params, mut ec_point) = (None, None);
+        for attribute in attributes {
+            match attribute {
+                Attribute::EcParams(value) => ec_params = Some(value),
+                Attribute::EcPoint(value) => ec_point = Some(value),
+                _ => {}
+            }
+        }
+        let ec_params =
+            ec_params.ok_or_else(|| anyhow!("The key {:?} is not an EC key", key_label))?;
+        let ec_point =
+            ec_point.ok_or_else(|| anyhow!("The key {:?} has no EC point", key_label))?;
+        let scheme = match ec_params.as_slice() {
+            SECP256K1_OID => SignatureScheme::Secp256k1,
+            SECP256R1_OID => SignatureScheme::Secp256r1,
+            _ => bail!(
+                "The key {:?} is not on the secp256k1 or secp256r1 curve",
+                key_label
+            ),
+        };
+        let public_key = PublicKey::try_from_bytes(scheme, &compress_ec_point(&ec_point)?)
+            .map_err(|err| anyhow!("Invalid public key: {}", err))?;
+        let address = IotaAddress::from(&public_key);
+        Ok(Arc::new(Self {
+            sessions: sessions
+                .into_iter()
+                .map(|session| Arc::new(Mutex::new(session)))
+                .collect(),
+            next_session: AtomicUsize::new(0),
+            private_key,
+            scheme,
+            public_key,
+            address,
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl TxSigner for Pkcs11TxSigner {
+    async fn sign_transaction(
+        &self,
+        tx_data: &TransactionData,
+    ) -> anyhow::Result<GenericSignature> {
+        // Same digest as `Signature::new_secure`: ECDSA signs the SHA-256 hash of the Blake2b
+        // hash of the intent message.
+        let intent_msg = IntentMessage::new(Intent::iota_transaction(), tx_data);
+        let message = Blake2b256::digest(bcs::to_bytes(&intent_msg)?);
+        let hash = Sha256::digest(message.digest);
+
+        let index = self.next_session.fetch_add(1, Ordering::Relaxed) % self.sessions.len();
+        let session = self.sessions[index].clone();
+        let private_key = self.private_key;
+        let raw_signature = tokio::task::spawn_blocking(move || {
+            session
+                .lock()
+                .sign(&Mechanism::Ecdsa, private_key, &hash.digest)
+        })
+        .await??;
+        if raw_signature.len() != 64 {
+            bail!(
+                "Unexpected signature length {} from the token",
+                raw_signature.len()
+            );
+        }
+        let order = match self.scheme {
+            SignatureScheme::Secp256k1 => &SECP256K1_ORDER,
+            _ => &SECP256R1_ORDER,
+        };
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&raw_signature);
+        normalize_s(&mut signature[32..], order);
+
+        let mut bytes = Vec::with_capacity(1 + 64 + 33);
+        bytes.push(self.scheme.flag());
+        bytes.extend_from_slice(&signature);
+        bytes.extend_from_slice(self.public_key.as_ref());
+        let signature = Signature::from_bytes(&bytes)
+            .map_err(|err| anyhow!("Invalid signature from the token: {}", err))?;
+        Ok(signature.into())
+    }
+
+    fn get_address(&self) -> IotaAddress {
+        self.address
+    }
+
+    fn get_public_key(&self) -> Option<PublicKey> {
+        Some(self.public_key.clone())
+    }
+}
+
+fn find_single_object(
+    session: &Session,
+    class: ObjectClass,
+    label: &Attribute,
+) -> anyhow::Result<ObjectHandle> {
+    let objects = session.find_objects(&[Attribute::Class(class), label.clone()])?;
+    match objects.as_slice() {
+        [object] => Ok(*object),
+        [] => bail!("No {} found with the key label", class),
+        _ => bail!(
+            "{} objects of {} found with the key label",
+            objects.len(),
+            class
+        ),
+    }
+}
+
+/// `CKA_EC_POINT` holds the uncompressed point `04 || x || y`, usually wrapped in a DER octet
+/// string. Returns the compressed encoding used by IOTA public keys.
+fn compress_ec_point(ec_point: &[u8]) -> anyhow::Result<[u8; 33]> {
+    let point = match ec_point {
+        [0x04, 0x41, point @ ..] if point.len() == 65 => point,
+        point if point.len() == 65 => point,
+        _ => bail!("Unsupported EC point encoding"),
+    };
+    if point[0] != 0x04 {
+        bail!("The EC point is not uncompressed");
+    }
+    let mut compressed = [0u8; 33];
+    compressed[0] = if point[64] & 1 == 0 { 0x02 } else { 0x03 };
+    compressed[1..].copy_from_slice(&point[1..33]);
+    Ok(compressed)
+}
+
+/// IOTA only accepts ECDSA signatures with `s` in the lower half of the curve order. Replaces
+/// `s` by `order - s` otherwise, which is an equally valid signature.
+fn normalize_s(s: &mut [u8], order: &[u8; 32]) {
+    let mut half_order = [0u8; 32];
+    let mut carry = 0;
+    for (half, byte) in half_order.iter_mut().zip(order.iter()) {
+        *half = (byte >> 1) | carry;
+        carry = (byte & 1) << 7;
+    }
+    if &*s <= &half_order[..] {
+        return;
+    }
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let diff = order[i] as i16 - s[i] as i16 - borrow;
+        borrow = (diff < 0) as i16;
+        s[i] = diff.rem_euclid(256) as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cryptoki::object::KeyType;
+    use iota_types::crypto::IotaSignature;
+    use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use iota_types::transaction::TransactionKind;
+    use std::path::PathBuf;
+
+    const SOFTHSM_MODULE_PATHS: &[&str] = &[
+        "/usr/lib/softhsm/libsofthsm2.so",
+        "/usr/lib/x86_64-linux-gnu/softhsm/libsofthsm2.so",
+        "/usr/local/lib/softhsm/libsofthsm2.so",
+        "/opt/homebrew/lib/softhsm/libsofthsm2.so",
+    ];
+    const TOKEN_LABEL: &str = "gas-station";
+    const PIN: &str = "1234";
+
+    fn softhsm_module() -> PathBuf {
+        std::env::var("SOFTHSM2_MODULE")
+            .ok()
+            .into_iter()
+            .chain(SOFTHSM_MODULE_PATHS.iter().map(|path| path.to_string()))
+            .map(PathBuf::from)
+            .find(|path| path.exists())
+            .expect("SoftHSM is not installed, set SOFTHSM2_MODULE to its library")
+    }
+
+    /// Initializes a token in the first free slot of SoftHSM with a secp256k1 and a secp256r1
+    /// key.
+    fn init_softhsm_token(module: &Path) {
+        let pkcs11 = pkcs11_context(module).unwrap();
+        let slot = pkcs11
+            .get_slots_with_token()
+            .unwrap()
+            .into_iter()
+            .find(|slot| !pkcs11.get_token_info(*slot).unwrap().token_initialized())
+            .unwrap();
+        let so_pin = AuthPin::new("5678".to_string());
+        pkcs11.init_token(slot, &so_pin, TOKEN_LABEL).unwrap();
+        let session = pkcs11.open_rw_session(slot).unwrap();
+        session.login(UserType::So, Some(&so_pin)).unwrap();
+        session.init_pin(&AuthPin::new(PIN.to_string())).unwrap();
+        session.logout().unwrap();
+        session
+            .login(UserType::User, Some(&AuthPin::new(PIN.to_string())))
+            .unwrap();
+        for (label, oid) in [("k1", SECP256K1_OID), ("r1", SECP256R1_OID)] {
+            let label = Attribute::Label(label.as_bytes().to_vec());
+            session
+                .generate_key_pair(
+                    &Mechanism::EccKeyPairGen,
+                    &[
+                        Attribute::Token(true),
+                        Attribute::KeyType(KeyType::EC),
+                        Attribute::EcParams(oid.to_vec()),
+                        Attribute::Verify(true),
+                        label.clone(),
+                    ],
+                    &[
+                        Attribute::Token(true),
+                        Attribute::Private(true),
+                        Attribute::Sensitive(true),
+                        Attribute::Sign(true),
+                        label,
+                    ],
+                )
+                .unwrap();
+        }
+        session.logout().unwrap();
+    }
+
+    /// Needs SoftHSM, with `SOFTHSM2_CONF` pointing to a configuration with an empty token
+    /// directory, which CI sets up. Run with `cargo nextest run --run-ignored only pkcs11`.
+    #[tokio::test]
+    #[ignore = "requires SoftHSM"]
+    async fn test_pkcs11_signer() {
+        let module = softhsm_module();
+        assert!(
+            std::env::var_os("SOFTHSM2_CONF").is_some(),
+            "SOFTHSM2_CONF must point to a SoftHSM configuration"
+        );
+        init_softhsm_token(&module);
+        // Checked first, as the token is logged in while a signer is open.
+        assert!(Pkcs11TxSigner::new(&module, TOKEN_LABEL, "k1", "0000".to_string(), 1).is_err());
+
+        // Both signers share the context of the module.
+        let signers = [
+            ("k1", SignatureScheme::Secp256k1),
+            ("r1", SignatureScheme::Secp256r1),
+        ]
+        .map(|(key_label, scheme)| {
+            let signer =
+                Pkcs11TxSigner::new(&module, TOKEN_LABEL, key_label, PIN.to_string(), 2).unwrap();
+            assert_eq!(signer.scheme, scheme);
+            signer
+        });
+        for signer in &signers {
+            let sponsor = signer.get_address();
+            // Sign a few times, so that signatures with a high `s` are normalized.
+            for gas_price in 1..=8 {
+                let tx_data = TransactionData::new_with_gas_coins_allow_sponsor(
+                    TransactionKind::programmable(ProgrammableTransactionBuilder::new().finish()),
+                    IotaAddress::random_for_testing_only(),
+                    vec![],
+                    1000,
+                    gas_price,
+                    sponsor,
+                );
+                let signature = match signer.sign_transaction(&tx_data).await.unwrap() {
+                    GenericSignature::Signature(signature) => signature,
+                    other => panic!("Unexpected signature {:?}", other),
+                };
+                let intent_msg = IntentMessage::new(Intent::iota_transaction(), &tx_data);
+                signature
+                    .verify_secure(&intent_msg, sponsor, signer.scheme)
+                    .unwrap();
+            }
+        }
+
+        assert!(Pkcs11TxSigner::new(&module, TOKEN_LABEL, "missing", PIN.to_string(), 1).is_err());
+        assert!(Pkcs11TxSigner::new(&module, "missing", "k1", PIN.to_string(), 1).is_err());
+    }
+
+    #[test]
+    fn test_normalize_s() {
+        let mut s = SECP256K1_ORDER;
+        s[31] -= 1;
+        normalize_s(&mut s, &SECP256K1_ORDER);
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(s, one);
+        normalize_s(&mut s, &SECP256K1_ORDER);
+        assert_eq!(s, one);
+    }
+}
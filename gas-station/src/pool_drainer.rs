@@ -0,0 +1,292 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::iota_client::IotaClient;
+use crate::storage::{run_with_init_lock, Storage, INIT_LOCK_LEASE_SEC, MAX_GAS_PER_QUERY};
+use crate::tx_signer::TxSigner;
+use crate::types::GasCoin;
+use anyhow::{bail, Context};
+use iota_json_rpc_types::IotaTransactionBlockEffectsAPI;
+use iota_types::base_types::IotaAddress;
+use iota_types::digests::TransactionDigest;
+use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use iota_types::transaction::{Transaction, TransactionData};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// How often the outstanding reservations are checked while waiting for them.
+const RESERVATION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The budget is estimated for a transaction paid with a single coin. Each transaction of the
+/// drain is paid with up to `MAX_GAS_PER_QUERY` coins, which are merged as part of the payment.
+const DRAIN_BUDGET_FACTOR: u64 = 10;
+
+/// Outcome of `drain_pool`.
+#[derive(Clone, Debug)]
+pub struct DrainSummary {
+    /// Number of coins owned by the sponsor that were merged.
+    pub merged_coins: usize,
+    /// Balance received by the target address.
+    pub transferred_balance: u64,
+    /// Digest of the transaction that transferred the merged coin to the target address.
+    pub transfer_digest: Option<TransactionDigest>,
+}
+
+/// Retires the pool of the sponsor: stops new reservations on every gas station sharing the
+/// storage, waits up to `wait_timeout` for the outstanding ones, then merges all coins owned by
+/// the sponsor in batches and transfers the result to `target_address`. The storage is marked
+/// uninitialized afterwards.
+/// Reservations stay disabled until the pool is initialized again. If the drain fails,
+/// reservations are allowed again, and the drain can be started again.
+pub async fn drain_pool(
+    iota_client: &IotaClient,
+    storage: &Arc<dyn Storage>,
+    signer: &Arc<dyn TxSigner>,
+    target_address: IotaAddress,
+    wait_timeout: Duration,
+) -> anyhow::Result<DrainSummary> {
+    // Draining holds the init lock so that no coins are split, merged or reconciled in the
+    // meantime.
+    if !storage.acquire_init_lock(INIT_LOCK_LEASE_SEC).await? {
+        bail!("The pool is being initialized. Try again later");
+    }
+    let result = run_with_init_lock(
+        storage,
+        INIT_LOCK_LEASE_SEC,
+        drain_pool_locked(iota_client, storage, signer, target_address, wait_timeout),
+    )
+    .await;
+    if result.is_err() {
+        if let Err(err) = storage.set_draining(false).await {
+            error!("Failed to allow reservations again: {:?}", err);
+        }
+    }
+    if let Err(err) = storage.release_init_lock().await {
+        error!("Failed to release the init lock: {:?}", err);
+    }
+    result
+}
+
+/// Ends the drain mode of the pool, e.g. after a drain was interrupted, so that gas coins are
+/// reserved again. Fails while the pool is being drained or initialized.
+pub async fn cancel_drain(storage: &Arc<dyn Storage>) -> anyhow::Result<()> {
+    if !storage.acquire_init_lock(INIT_LOCK_LEASE_SEC).await? {
+        bail!("The pool is being drained or initialized. Try again later");
+    }
+    let result = storage.set_draining(false).await;
+    if let Err(err) = storage.release_init_lock().await {
+        error!("Failed to release the init lock: {:?}", err);
+    }
+    result
+}
+
+async fn drain_pool_locked(
+    iota_client: &IotaClient,
+    storage: &Arc<dyn Storage>,
+    signer: &Arc<dyn TxSigner>,
+    target_address: IotaAddress,
+    wait_timeout: Duration,
+) -> anyhow::Result<DrainSummary> {
+    storage.set_draining(true).await?;
+    info!("Stopped new reservations. Waiting for the outstanding ones");
+    let start = Instant::now();
+    loop {
+        let count = storage.get_reservation_count().await?;
+        if count == 0 {
+            break;
+        }
+        if start.elapsed() > wait_timeout {
+            bail!(
+                "{} reservations are still outstanding after {:?}",
+                count,
+                wait_timeout
+            );
+        }
+        tokio::time::sleep(RESERVATION_POLL_INTERVAL).await;
+    }
+
+    let sponsor_address = signer.get_address();
+    let mut coins = iota_client
+        .get_all_owned_iota_coins_above_balance_threshold(sponsor_address, 0)
+        .await;
+    let merged_coins = coins.len();
+    info!(
+        "Merging {} coins of {} and transferring them to {}",
+        merged_coins, sponsor_address, target_address
+    );
+    // The largest coins come first, so that the first batch covers the budget.
+    coins.sort_by(|a, b| b.balance.cmp(&a.balance));
+    let mut remaining = coins.into_iter();
+    let mut merged: Option<GasCoin> = None;
+    let mut transfer_digest = None;
+    let mut transferred_balance = 0;
+    loop {
+        let merged_before = merged.is_some();
+        let mut payment: Vec<_> = merged.take().into_iter().collect();
+        payment.extend(remaining.by_ref().take(MAX_GAS_PER_QUERY - payment.len()));
+        if payment.is_empty() {
+            break;
+        }
+        let is_last = remaining.len() == 0;
+        let recipient = if is_last {
+            target_address
+        } else {
+            sponsor_address
+        };
+        let (coin, digest) = transfer_gas_coins(iota_client, signer, payment, recipient)
+            .await
+            .with_context(|| {
+                if merged_before {
+                    "Failed to merge the coins. Some coins were merged already, so the pool \
+                    differs from the coins owned on chain until it is reconciled"
+                } else {
+                    "Failed to merge the coins"
+                }
+            })?;
+        if is_last {
+            transferred_balance = coin.balance;
+            transfer_digest = Some(digest);
+            break;
+        }
+        merged = Some(coin);
+    }
+
+    storage.mark_uninitialized().await?;
+    info!(
+        ?transfer_digest,
+        "Drained {} coins with a total balance of {} to {}",
+        merged_coins,
+        transferred_balance,
+        target_address
+    );
+    Ok(DrainSummary {
+        merged_coins,
+        transferred_balance,
+        transfer_digest,
+    })
+}
+
+/// Merges the coins by paying for gas with all of them, and transfers the merged gas coin to
+/// `recipient`. Returns the merged coin and the digest of the transaction.
+async fn transfer_gas_coins(
+    iota_client: &IotaClient,
+    signer: &Arc<dyn TxSigner>,
+    coins: Vec<GasCoin>,
+    recipient: IotaAddress,
+) -> anyhow::Result<(GasCoin, TransactionDigest)> {
+    let sponsor_address = signer.get_address();
+    let build_pt = || {
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        pt_builder.pay_all_iota(recipient);
+        pt_builder.finish()
+    };
+    let total_balance: u64 = coins.iter().map(|coin| coin.balance).sum();
+    let budget = iota_client
+        .estimate_gas_budget(sponsor_address, build_pt())
+        .await?;
+    if total_balance < budget {
+        bail!(
+            "The remaining balance {} does not cover the gas budget {}",
+            total_balance,
+            budget
+        );
+    }
+    let budget = (budget * DRAIN_BUDGET_FACTOR).min(total_balance);
+    let rgp = iota_client.get_reference_gas_price().await;
+    let tx_data = TransactionData::new_programmable(
+        sponsor_address,
+        coins.iter().map(|coin| coin.object_ref).collect(),
+        build_pt(),
+        budget,
+        rgp,
+    );
+    let sig = signer.sign_transaction(&tx_data).await?;
+    let tx = Transaction::from_generic_sig_data(tx_data, vec![sig]);
+    let effects = iota_client.execute_transaction(tx, 3, None).await?;
+    if !effects.status().is_ok() {
+        bail!("Drain transaction failed: {:?}", effects.status());
+    }
+    let balance = total_balance as i64 - effects.gas_cost_summary().net_gas_usage();
+    Ok((
+        GasCoin {
+            object_ref: effects.gas_object().reference.to_object_ref(),
+            balance: balance as u64,
+        },
+        *effects.transaction_digest(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::iota_client::IotaClient;
+    use crate::pool_drainer::{cancel_drain, drain_pool};
+    use crate::storage::connect_storage_for_testing;
+    use crate::test_env::start_iota_cluster;
+    use iota_types::gas_coin::NANOS_PER_IOTA;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_drain_pool() {
+        telemetry_subscribers::init_for_testing();
+        // More coins than fit into the payment of one transaction.
+        let (cluster, signer) = start_iota_cluster(vec![NANOS_PER_IOTA; 300]).await;
+        let sponsor = signer.get_address();
+        let fullnode_url = cluster.fullnode_handle.rpc_url.clone();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let iota_client = IotaClient::new(&fullnode_url, None).await;
+        let coins = iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(sponsor, 0)
+            .await;
+        let total_balance: u64 = coins.iter().map(|coin| coin.balance).sum();
+        storage.add_new_coins(coins).await.unwrap();
+        let target = *cluster
+            .get_addresses()
+            .iter()
+            .find(|addr| **addr != sponsor)
+            .unwrap();
+
+        // A drain that fails allows reservations again.
+        storage.reserve_gas_coins(1, 100).await.unwrap();
+        assert!(drain_pool(
+            &iota_client,
+            &storage,
+            &signer,
+            target,
+            Duration::from_millis(0),
+        )
+        .await
+        .is_err());
+        assert!(!storage.is_draining().await.unwrap());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        storage.expire_coins().await.unwrap();
+
+        // The drain mode can be cancelled.
+        storage.set_draining(true).await.unwrap();
+        cancel_drain(&storage).await.unwrap();
+        assert!(!storage.is_draining().await.unwrap());
+
+        let summary = drain_pool(
+            &iota_client,
+            &storage,
+            &signer,
+            target,
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.merged_coins, 300);
+        assert!(summary.transferred_balance < total_balance);
+        assert!(summary.transferred_balance > total_balance - NANOS_PER_IOTA);
+        assert!(summary.transfer_digest.is_some());
+
+        assert!(iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(sponsor, 0)
+            .await
+            .is_empty());
+        assert!(!storage.is_initialized().await.unwrap());
+        assert!(storage.is_draining().await.unwrap());
+        assert_eq!(storage.get_available_coin_count().await.unwrap(), 0);
+        assert!(storage.reserve_gas_coins(1, 1000).await.is_err());
+    }
+}
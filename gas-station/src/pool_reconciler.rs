@@ -0,0 +1,194 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::{CoinInitConfig, PoolReconciliationConfig};
+use crate::gas_station_initializer::NEW_COIN_BALANCE_FACTOR_THRESHOLD;
+use crate::iota_client::IotaClient;
+use crate::metrics::PoolReconcilerMetrics;
+use crate::storage::{run_with_init_lock, CoinReconciliation, Storage, INIT_LOCK_LEASE_SEC};
+use iota_types::base_types::IotaAddress;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Periodically makes the pool match the coins the sponsor owns on chain. Coins can go missing
+/// or become outdated when they are used outside of the gas station, e.g. by manual transfers.
+pub struct PoolReconciler {
+    task_handle: JoinHandle<()>,
+    // This is always Some. It is None only after the drop or shutdown method is called.
+    cancel_sender: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl Drop for PoolReconciler {
+    fn drop(&mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            cancel_sender.send(()).unwrap();
+        }
+    }
+}
+
+impl PoolReconciler {
+    pub fn start(
+        iota_client: IotaClient,
+        storage: Arc<dyn Storage>,
+        sponsor_address: IotaAddress,
+        config: PoolReconciliationConfig,
+        max_adopted_balance: u64,
+        metrics: Arc<PoolReconcilerMetrics>,
+    ) -> Self {
+        let (cancel_sender, mut cancel_receiver) = tokio::sync::oneshot::channel();
+        let task_handle = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(config.interval_sec)) => {}
+                    _ = &mut cancel_receiver => {
+                        info!("Pool reconciliation task is cancelled");
+                        break;
+                    }
+                }
+                metrics.num_pool_reconciliations.inc();
+                match reconcile_pool(
+                    &iota_client,
+                    &storage,
+                    sponsor_address,
+                    max_adopted_balance,
+                    false,
+                )
+                .await
+                {
+                    Ok(Some(reconciliation)) => {
+                        let sponsor = sponsor_address.to_string();
+                        for (kind, count) in [
+                            ("updated", reconciliation.updated),
+                            ("removed", reconciliation.removed),
+                            ("adopted", reconciliation.adopted),
+                            ("duplicate", reconciliation.duplicates),
+                        ] {
+                            metrics
+                                .num_reconciled_gas_coins
+                                .with_label_values(&[&sponsor, kind])
+                                .inc_by(count);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        metrics.num_failed_pool_reconciliations.inc();
+                        error!("Failed to reconcile the pool: {:?}", err);
+                    }
+                }
+            }
+        });
+        Self {
+            task_handle,
+            cancel_sender: Some(cancel_sender),
+        }
+    }
+
+    /// Cancels the reconciliation task and waits for it to stop.
+    pub async fn shutdown(mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            let _ = cancel_sender.send(());
+        }
+        if let Err(err) = (&mut self.task_handle).await {
+            error!("Pool reconciliation task failed: {:?}", err);
+        }
+    }
+}
+
+/// Owned coins with a balance from this threshold on are left to the coin initialization, which
+/// splits them into pool coins. Without coin initialization, every owned coin belongs to the pool.
+pub fn max_adopted_balance(coin_init_config: Option<&CoinInitConfig>) -> u64 {
+    coin_init_config
+        .map(|config| config.target_init_balance * NEW_COIN_BALANCE_FACTOR_THRESHOLD)
+        .unwrap_or(u64::MAX)
+}
+
+/// Compares the pool with the coins the sponsor owns on chain and fixes the differences, unless
+/// `dry_run` is set. Returns None if the pool is not initialized yet or is being initialized.
+pub async fn reconcile_pool(
+    iota_client: &IotaClient,
+    storage: &Arc<dyn Storage>,
+    sponsor_address: IotaAddress,
+    max_adopted_balance: u64,
+    dry_run: bool,
+) -> anyhow::Result<Option<CoinReconciliation>> {
+    if !storage.is_initialized().await? {
+        info!("The pool is not initialized yet. Skipping reconciliation");
+        return Ok(None);
+    }
+    // Reconciliation holds the init lock so that it never sees coins that are being split.
+    if !storage.acquire_init_lock(INIT_LOCK_LEASE_SEC).await? {
+        info!("The pool is being initialized. Skipping reconciliation");
+        return Ok(None);
+    }
+    let reconcile = async {
+        let owned_coins = iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(sponsor_address, 0)
+            .await;
+        storage
+            .reconcile_coins(owned_coins, max_adopted_balance, dry_run)
+            .await
+    };
+    let result = run_with_init_lock(storage, INIT_LOCK_LEASE_SEC, reconcile).await;
+    storage.release_init_lock().await?;
+    let reconciliation = result?;
+    if reconciliation.is_empty() {
+        info!("The pool matches the coins owned on chain");
+    } else {
+        warn!(
+            dry_run,
+            "The pool differs from the coins owned on chain: {:?}", reconciliation
+        );
+    }
+    Ok(Some(reconciliation))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::iota_client::IotaClient;
+    use crate::pool_reconciler::reconcile_pool;
+    use crate::storage::{connect_storage_for_testing, CoinReconciliation};
+    use crate::test_env::start_iota_cluster;
+    use crate::types::GasCoin;
+    use iota_types::base_types::random_object_ref;
+    use iota_types::gas_coin::NANOS_PER_IOTA;
+
+    #[tokio::test]
+    async fn test_reconcile_pool() {
+        telemetry_subscribers::init_for_testing();
+        let (cluster, signer) = start_iota_cluster(vec![NANOS_PER_IOTA; 10]).await;
+        let sponsor = signer.get_address();
+        let fullnode_url = cluster.fullnode_handle.rpc_url;
+        let storage = connect_storage_for_testing(sponsor).await;
+        let iota_client = IotaClient::new(&fullnode_url, None).await;
+
+        // The pool only knows about a coin that doesn't exist on chain.
+        storage
+            .add_new_coins(vec![GasCoin {
+                object_ref: random_object_ref(),
+                balance: NANOS_PER_IOTA,
+            }])
+            .await
+            .unwrap();
+        let reconciliation = reconcile_pool(&iota_client, &storage, sponsor, u64::MAX, false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            reconciliation,
+            CoinReconciliation {
+                removed: 1,
+                adopted: 10,
+                ..Default::default()
+            }
+        );
+        assert_eq!(storage.get_available_coin_count().await.unwrap(), 10);
+
+        let reconciliation = reconcile_pool(&iota_client, &storage, sponsor, u64::MAX, false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(reconciliation.is_empty());
+    }
+}
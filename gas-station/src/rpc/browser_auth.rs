@@ -0,0 +1,290 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::access_controller::hook::HookAction;
+use crate::access_controller::policy::AccessPolicy;
+use crate::access_controller::AccessController;
+use crate::config::{BrowserAuthConfig, BrowserReservationLimits};
+use crate::errors::GasStationError;
+use crate::metrics::GasStationRpcMetrics;
+use crate::types::ReservationID;
+use anyhow::{bail, Context};
+use axum::http::header::ORIGIN;
+use axum::http::HeaderMap;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const RESERVATION_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Authenticates browser clients, which cannot hold the bearer secret, by the public key
+/// configured for their origin.
+pub struct BrowserAuth {
+    clients: Vec<Arc<BrowserClient>>,
+}
+
+pub struct BrowserClient {
+    pub origin: String,
+    public_key: String,
+    /// The rules of the client. Anything that is not explicitly allowed is denied.
+    pub access_controller: AccessController,
+    reservation_limits: BrowserReservationLimits,
+    reservations: Mutex<ClientReservations>,
+}
+
+/// The reservations of a browser client that count against its limits.
+#[derive(Default)]
+struct ClientReservations {
+    /// Reservations that are not executed yet, with the time they expire.
+    active: HashMap<ReservationID, Instant>,
+    /// Reservations that are being made.
+    pending: usize,
+    /// When the reservations within the rate window were started.
+    recent: VecDeque<Instant>,
+}
+
+impl BrowserAuth {
+    pub async fn new(
+        config: BrowserAuthConfig,
+        metrics: Arc<GasStationRpcMetrics>,
+    ) -> anyhow::Result<Self> {
+        let mut clients = Vec::with_capacity(config.clients.len());
+        for client in config.clients {
+            if client.access_rules.is_empty() {
+                bail!(
+                    "Browser client for origin {} must have at least one access rule",
+                    client.origin
+                );
+            }
+            let mut access_controller =
+                AccessController::new(AccessPolicy::DenyAll, client.access_rules);
+            access_controller.set_metrics(metrics.clone());
+            access_controller.initialize().await.with_context(|| {
+                format!(
+                    "Failed to initialize access rules of browser client for origin {}",
+                    client.origin
+                )
+            })?;
+            clients.push(Arc::new(BrowserClient {
+                origin: normalize_origin(&client.origin),
+                public_key: client.public_key,
+                access_controller,
+                reservation_limits: client.reservation_limits,
+                reservations: Mutex::default(),
+            }));
+        }
+        Ok(Self { clients })
+    }
+
+    /// Returns the client whose origin matches the `Origin` header of the request and whose
+    /// public key matches the given token.
+    pub fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        token: Option<&str>,
+    ) -> Option<Arc<BrowserClient>> {
+        let token = token?;
+        let origin = normalize_origin(headers.get(ORIGIN)?.to_str().ok()?);
+        self.clients
+            .iter()
+            .find(|client| client.origin == origin && client.public_key == token)
+            .cloned()
+    }
+
+    /// Returns the execution notification webhooks of the rules of all clients.
+    pub fn notification_hooks(&self) -> Vec<HookAction> {
+        self.clients
+            .iter()
+            .flat_map(|client| &client.access_controller.rules)
+            .filter_map(|rule| rule.notify.clone())
+            .collect()
+    }
+}
+
+impl BrowserClient {
+    /// Checks a new reservation with `gas_budget` against the limits of the client, and counts it
+    /// as pending if it is within them. It must be followed by `finish_reservation`.
+    pub fn start_reservation(&self, gas_budget: u64, now: Instant) -> Result<(), GasStationError> {
+        let limits = &self.reservation_limits;
+        if gas_budget > limits.max_gas_budget {
+            return Err(GasStationError::InvalidRequest(format!(
+                "Gas budget {} exceeds the limit of {} for browser clients",
+                gas_budget, limits.max_gas_budget
+            )));
+        }
+        let mut reservations = self.reservations.lock();
+        reservations
+            .active
+            .retain(|_, expiration| *expiration > now);
+        while reservations
+            .recent
+            .front()
+            .is_some_and(|start| now.duration_since(*start) >= RESERVATION_RATE_WINDOW)
+        {
+            reservations.recent.pop_front();
+        }
+        if reservations.active.len() + reservations.pending >= limits.max_concurrent_reservations {
+            return Err(GasStationError::RateLimited(format!(
+                "Browser client {} has too many open reservations",
+                self.origin
+            )));
+        }
+        if reservations.recent.len() >= limits.max_reservations_per_minute as usize {
+            return Err(GasStationError::RateLimited(format!(
+                "Browser client {} made too many reservations within a minute",
+                self.origin
+            )));
+        }
+        reservations.pending += 1;
+        reservations.recent.push_back(now);
+        Ok(())
+    }
+
+    /// Ends a reservation started with `start_reservation`. A successful reservation counts as
+    /// open until it is executed or expires.
+    pub fn finish_reservation(&self, reservation: Option<(ReservationID, Instant)>) {
+        let mut reservations = self.reservations.lock();
+        reservations.pending = reservations.pending.saturating_sub(1);
+        if let Some((reservation_id, expiration)) = reservation {
+            reservations.active.insert(reservation_id, expiration);
+        }
+    }
+
+    /// Stops counting a reservation once its transaction was executed.
+    pub fn release_reservation(&self, reservation_id: ReservationID) {
+        self.reservations.lock().active.remove(&reservation_id);
+    }
+
+    /// Returns a guard that releases the reservation when it is dropped, i.e. once the execution
+    /// of its transaction ends, whether it failed or not.
+    pub fn reservation_guard(self: &Arc<Self>, reservation_id: ReservationID) -> ReservationGuard {
+        ReservationGuard {
+            client: self.clone(),
+            reservation_id,
+        }
+    }
+}
+
+/// Releases a reservation of a browser client when dropped, see `BrowserClient::reservation_guard`.
+pub struct ReservationGuard {
+    client: Arc<BrowserClient>,
+    reservation_id: ReservationID,
+}
+
+impl Drop for ReservationGuard {
+    fn drop(&mut self) {
+        self.client.release_reservation(self.reservation_id);
+    }
+}
+
+fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access_controller::rule::AccessRuleBuilder;
+    use crate::config::BrowserClientConfig;
+    use crate::errors::ErrorCode;
+    use axum::http::HeaderValue;
+
+    fn client_config(origin: &str, public_key: &str) -> BrowserClientConfig {
+        BrowserClientConfig {
+            origin: origin.to_string(),
+            public_key: public_key.to_string(),
+            access_rules: vec![AccessRuleBuilder::new().allow().build()],
+            reservation_limits: BrowserReservationLimits::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_browser_auth_matches_origin_and_key() {
+        let browser_auth = BrowserAuth::new(
+            BrowserAuthConfig {
+                clients: vec![
+                    client_config("https://app.example.com/", "key-1"),
+                    client_config("https://other.example.com", "key-2"),
+                ],
+            },
+            GasStationRpcMetrics::new_for_testing(),
+        )
+        .await
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert!(browser_auth.authenticate(&headers, Some("key-1")).is_none());
+
+        headers.insert(ORIGIN, HeaderValue::from_static("https://APP.example.com"));
+        let client = browser_auth.authenticate(&headers, Some("key-1")).unwrap();
+        assert_eq!(client.origin, "https://app.example.com");
+        // Keys are scoped to their origin.
+        assert!(browser_auth.authenticate(&headers, Some("key-2")).is_none());
+        assert!(browser_auth.authenticate(&headers, None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_browser_auth_requires_access_rules() {
+        let mut client = client_config("https://app.example.com", "key-1");
+        client.access_rules.clear();
+        let result = BrowserAuth::new(
+            BrowserAuthConfig {
+                clients: vec![client],
+            },
+            GasStationRpcMetrics::new_for_testing(),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_browser_reservation_limits() {
+        let mut config = client_config("https://app.example.com", "key-1");
+        config.reservation_limits = BrowserReservationLimits {
+            max_gas_budget: 1000,
+            max_concurrent_reservations: 2,
+            max_reservations_per_minute: 3,
+        };
+        let browser_auth = BrowserAuth::new(
+            BrowserAuthConfig {
+                clients: vec![config],
+            },
+            GasStationRpcMetrics::new_for_testing(),
+        )
+        .await
+        .unwrap();
+        let client = &browser_auth.clients[0];
+        let now = Instant::now();
+        let expiration = now + Duration::from_secs(10);
+
+        let err = client.start_reservation(1001, now).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidRequest);
+
+        // Pending and open reservations count against the concurrency limit.
+        client.start_reservation(1000, now).unwrap();
+        client.start_reservation(1000, now).unwrap();
+        let err = client.start_reservation(1000, now).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::RateLimited);
+        client.finish_reservation(None);
+        client.finish_reservation(Some((1, expiration)));
+
+        // The rate limit applies even though a slot is free.
+        client.start_reservation(1000, now).unwrap();
+        client.finish_reservation(Some((2, expiration)));
+        client.release_reservation(1);
+        let err = client.start_reservation(1000, now).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::RateLimited);
+
+        // Executed and expired reservations free their slot.
+        client.release_reservation(2);
+        let later = now + RESERVATION_RATE_WINDOW;
+        client.start_reservation(1000, later).unwrap();
+        client.finish_reservation(Some((3, later + Duration::from_secs(10))));
+        client.start_reservation(1000, later).unwrap();
+        assert_eq!(
+            client.start_reservation(1000, later).unwrap_err().code(),
+            ErrorCode::RateLimited
+        );
+    }
+}
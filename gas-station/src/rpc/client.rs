@@ -3,14 +3,15 @@
 
 use crate::read_auth_env;
 use crate::rpc::rpc_types::{
-    ExecuteTransactionRequestType, ExecuteTxRequest, ExecuteTxResponse, ReserveGasRequest,
-    ReserveGasResponse,
+    error_from_response, ExecuteTransactionRequestType, ExecuteTxAsyncResponse, ExecuteTxRequest,
+    ExecuteTxResponse, GasStationResponse, ReserveGasRequest, ReserveGasResponse, TxStatusResponse,
 };
 use crate::types::ReservationID;
 use anyhow::bail;
 use fastcrypto::encoding::Base64;
 use iota_json_rpc_types::IotaTransactionBlockEffects;
 use iota_types::base_types::{IotaAddress, ObjectRef};
+use iota_types::digests::TransactionDigest;
 use iota_types::signature::GenericSignature;
 use iota_types::transaction::TransactionData;
 use reqwest::header::{HeaderMap, AUTHORIZATION};
@@ -57,6 +58,15 @@ impl GasStationRpcClient {
             .await
     }
 
+    pub async fn openapi_spec(&self) -> Result<serde_json::Value, reqwest::Error> {
+        self.client
+            .get(format!("{}/openapi.json", self.server_address))
+            .send()
+            .await?
+            .json()
+            .await
+    }
+
     pub async fn debug_health_check(&self) -> anyhow::Result<()> {
         let mut headers = HeaderMap::new();
         if let Some(auth) = read_auth_env() {
@@ -103,11 +113,7 @@ impl GasStationRpcClient {
             .await?;
         response
             .result
-            .ok_or_else(|| {
-                anyhow::anyhow!(response
-                    .error
-                    .unwrap_or_else(|| "Unknown error".to_string()))
-            })
+            .ok_or_else(|| error_from_response(response.error, response.code).into())
             .map(|result| {
                 (
                     result.sponsor_address,
@@ -148,11 +154,64 @@ impl GasStationRpcClient {
             .await?
             .json::<ExecuteTxResponse>()
             .await?;
-        response.effects.ok_or_else(|| {
-            anyhow::anyhow!(response
-                .error
-                .unwrap_or_else(|| "Unknown error".to_string()))
-        })
+        response
+            .effects
+            .ok_or_else(|| error_from_response(response.error, response.code).into())
+    }
+
+    /// Submits the transaction for execution without waiting for its effects.
+    /// Returns the transaction digest, which can be used to poll the outcome with `get_tx_status`.
+    pub async fn execute_tx_async(
+        &self,
+        reservation_id: ReservationID,
+        tx_data: &TransactionData,
+        user_sig: &GenericSignature,
+        request_type: Option<ExecuteTransactionRequestType>,
+        headers: Option<HeaderMap>,
+    ) -> anyhow::Result<TransactionDigest> {
+        let mut headers = headers.unwrap_or_default();
+        if let Some(auth) = read_auth_env() {
+            headers.insert(AUTHORIZATION, format!("Bearer {}", auth).parse().unwrap());
+        }
+        let request = ExecuteTxRequest {
+            reservation_id,
+            tx_bytes: Base64::from_bytes(&bcs::to_bytes(&tx_data).unwrap()),
+            user_sig: Base64::from_bytes(user_sig.as_ref()),
+            request_type,
+        };
+        let response = self
+            .client
+            .post(format!("{}/v1/execute_tx_async", self.server_address))
+            .headers(headers)
+            .json(&request)
+            .send()
+            .await?
+            .json::<ExecuteTxAsyncResponse>()
+            .await?;
+        response
+            .transaction_digest
+            .ok_or_else(|| error_from_response(response.error, response.code).into())
+    }
+
+    pub async fn get_tx_status(
+        &self,
+        digest: &TransactionDigest,
+    ) -> anyhow::Result<TxStatusResponse> {
+        let mut headers = HeaderMap::new();
+        if let Some(auth) = read_auth_env() {
+            headers.insert(AUTHORIZATION, format!("Bearer {}", auth).parse().unwrap());
+        }
+        let response = self
+            .client
+            .get(format!("{}/v1/tx/{}", self.server_address, digest))
+            .headers(headers)
+            .send()
+            .await?
+            .json::<GasStationResponse<TxStatusResponse>>()
+            .await?;
+        response
+            .result
+            .ok_or_else(|| error_from_response(response.error, response.code).into())
     }
 
     pub async fn reload_access_controller(&self) -> anyhow::Result<()> {
@@ -174,4 +233,24 @@ impl GasStationRpcClient {
         };
         Ok(())
     }
+
+    /// Asks the gas station to look for new coins of the sponsor right away.
+    pub async fn refresh_coin_pool(&self) -> anyhow::Result<()> {
+        let mut headers = HeaderMap::new();
+        if let Some(auth) = read_auth_env() {
+            headers.insert(AUTHORIZATION, format!("Bearer {}", auth).parse().unwrap());
+        }
+        let response = self
+            .client
+            .post(format!("{}/v1/refresh_coin_pool", self.server_address))
+            .headers(headers)
+            .send()
+            .await?
+            .json::<GasStationResponse<String>>()
+            .await?;
+        response
+            .result
+            .map(|_| ())
+            .ok_or_else(|| error_from_response(response.error, response.code).into())
+    }
 }
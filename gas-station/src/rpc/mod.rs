@@ -1,11 +1,15 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod browser_auth;
 pub mod client;
+pub mod openapi;
 pub(crate) mod rpc_types;
 mod server;
+pub mod tls;
+mod tx_status_tracker;
 
-pub use rpc_types::ExecuteTransactionRequestType;
+pub use rpc_types::{ExecuteTransactionRequestType, TxExecutionStatus, TxStatusResponse};
 pub use server::GasStationServer;
 
 #[cfg(test)]
@@ -16,12 +20,18 @@ mod tests {
     use crate::access_controller::predicates::{ValueAggregate, ValueNumber};
     use crate::access_controller::rule::AccessRuleBuilder;
     use crate::access_controller::AccessController;
-    use crate::config::GasStationConfig;
-    use crate::rpc::ExecuteTransactionRequestType;
+    use crate::config::{
+        BrowserAuthConfig, BrowserClientConfig, BrowserReservationLimits, GasStationConfig,
+    };
+    use crate::errors::ErrorCode;
+    use crate::metrics::GasStationRpcMetrics;
+    use crate::rpc::browser_auth::BrowserAuth;
+    use crate::rpc::rpc_types::{ReserveGasRequest, ReserveGasResponse};
+    use crate::rpc::{ExecuteTransactionRequestType, TxExecutionStatus};
     use crate::test_env::{
         create_test_transaction, start_rpc_server_for_testing,
         start_rpc_server_for_testing_no_auth, start_rpc_server_for_testing_with_access_controller,
-        DEFAULT_TEST_CONFIG_PATH,
+        start_rpc_server_for_testing_with_browser_auth, DEFAULT_TEST_CONFIG_PATH,
     };
     use crate::AUTH_ENV_NAME;
     use iota_config::Config;
@@ -50,6 +60,67 @@ mod tests {
         assert!(effects.status().is_ok());
     }
 
+    #[tokio::test]
+    async fn test_async_execute_tx() {
+        let (test_cluster, _container, server) =
+            start_rpc_server_for_testing(vec![NANOS_PER_IOTA; 10], NANOS_PER_IOTA).await;
+        let client = server.get_local_client();
+
+        let (sponsor, reservation_id, gas_coins) =
+            client.reserve_gas(NANOS_PER_IOTA, 10).await.unwrap();
+        let (tx_data, user_sig) = create_test_transaction(&test_cluster, sponsor, gas_coins).await;
+        let digest = client
+            .execute_tx_async(reservation_id, &tx_data, &user_sig, None, None)
+            .await
+            .unwrap();
+        assert_eq!(digest, tx_data.digest());
+
+        let mut status = client.get_tx_status(&digest).await.unwrap();
+        while status.status == TxExecutionStatus::Pending {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            status = client.get_tx_status(&digest).await.unwrap();
+        }
+        assert_eq!(status.status, TxExecutionStatus::Executed);
+        assert!(status.effects.unwrap().status().is_ok());
+
+        // A replay of the request is rejected without changing the status of the transaction.
+        assert!(client
+            .execute_tx_async(reservation_id, &tx_data, &user_sig, None, None)
+            .await
+            .is_err());
+        assert_eq!(
+            client.get_tx_status(&digest).await.unwrap().status,
+            TxExecutionStatus::Executed
+        );
+
+        // Polling an unknown digest fails.
+        assert!(client
+            .get_tx_status(&iota_types::digests::TransactionDigest::random())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown() {
+        let (test_cluster, container, server) =
+            start_rpc_server_for_testing(vec![NANOS_PER_IOTA; 10], NANOS_PER_IOTA).await;
+        let client = server.get_local_client();
+
+        let (sponsor, reservation_id, gas_coins) =
+            client.reserve_gas(NANOS_PER_IOTA, 10).await.unwrap();
+        let (tx_data, user_sig) = create_test_transaction(&test_cluster, sponsor, gas_coins).await;
+        client
+            .execute_tx_async(reservation_id, &tx_data, &user_sig, None, None)
+            .await
+            .unwrap();
+
+        // The execution in progress finishes and its coin is returned to the pool.
+        assert!(server.shutdown(Duration::from_secs(30)).await);
+        let station = container.get_gas_station_arc();
+        assert_eq!(station.query_pool_available_coin_count().await, 10);
+        assert!(client.health().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_invalid_auth() {
         let (_test_cluster, _container, server) =
@@ -79,6 +150,67 @@ mod tests {
         assert!(client.reserve_gas(NANOS_PER_IOTA, 10).await.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_browser_client_reservation_limits() {
+        let browser_auth = BrowserAuth::new(
+            BrowserAuthConfig {
+                clients: vec![BrowserClientConfig {
+                    origin: "https://app.example.com".to_string(),
+                    public_key: "pk_app".to_string(),
+                    access_rules: vec![AccessRuleBuilder::new().allow().build()],
+                    reservation_limits: BrowserReservationLimits {
+                        max_gas_budget: NANOS_PER_IOTA,
+                        max_concurrent_reservations: 1,
+                        max_reservations_per_minute: 10,
+                    },
+                }],
+            },
+            GasStationRpcMetrics::new_for_testing(),
+        )
+        .await
+        .unwrap();
+        let (_test_cluster, _container, server) = start_rpc_server_for_testing_with_browser_auth(
+            vec![NANOS_PER_IOTA; 10],
+            NANOS_PER_IOTA,
+            browser_auth,
+        )
+        .await;
+
+        let reserve = |origin: &'static str, gas_budget: u64| {
+            let url = format!("http://localhost:{}/v1/reserve_gas", server.rpc_port);
+            async move {
+                reqwest::Client::new()
+                    .post(url)
+                    .bearer_auth("pk_app")
+                    .header("origin", origin)
+                    .json(&ReserveGasRequest {
+                        gas_budget,
+                        reserve_duration_secs: 10,
+                    })
+                    .send()
+                    .await
+                    .unwrap()
+                    .json::<ReserveGasResponse>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // The public key is only accepted from its origin.
+        let response = reserve("https://evil.example.com", NANOS_PER_IOTA).await;
+        assert_eq!(response.code, Some(ErrorCode::Unauthorized));
+        let response = reserve("https://app.example.com", NANOS_PER_IOTA * 2).await;
+        assert_eq!(response.code, Some(ErrorCode::InvalidRequest));
+        let response = reserve("https://app.example.com", NANOS_PER_IOTA).await;
+        assert!(response.result.is_some());
+        // The open reservation blocks further ones of the client.
+        let response = reserve("https://app.example.com", NANOS_PER_IOTA).await;
+        assert_eq!(response.code, Some(ErrorCode::RateLimited));
+        // Operators are not limited.
+        let client = server.get_local_client();
+        assert!(client.reserve_gas(NANOS_PER_IOTA * 2, 10).await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_access_denied_from_controller() {
         let (test_cluster, _container, server) =
@@ -197,6 +329,26 @@ mod tests {
         client.debug_health_check().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_refresh_coin_pool_without_coin_init() {
+        let (_test_cluster, _container, server) =
+            start_rpc_server_for_testing(vec![NANOS_PER_IOTA; 10], NANOS_PER_IOTA).await;
+
+        // The test server is started without a coin init task.
+        let client = server.get_local_client();
+        assert!(client.refresh_coin_pool().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_openapi_spec() {
+        let (_test_cluster, _container, server) =
+            start_rpc_server_for_testing(vec![NANOS_PER_IOTA; 10], NANOS_PER_IOTA).await;
+
+        let client = server.get_local_client();
+        let spec = client.openapi_spec().await.unwrap();
+        assert_eq!(spec, crate::rpc::openapi::openapi_spec());
+    }
+
     #[tokio::test]
     async fn test_explicit_wait_for_effects_cert() {
         let (test_cluster, _container, server) =
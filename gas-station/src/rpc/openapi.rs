@@ -0,0 +1,93 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::rpc::server;
+use serde_json::Value;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+const BEARER_AUTH: &str = "bearerAuth";
+
+/// The OpenAPI document describing every endpoint of the gas station RPC server. The endpoints
+/// are described next to their handlers, and the schemas are derived from the types in
+/// `rpc_types`, so the document always matches what the server actually accepts and returns.
+/// Failed requests of the endpoints protected by the bearer secret return the same response type
+/// with `error` and `code` set, and a matching 4xx or 5xx status.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "IOTA Gas Station",
+        contact(name = "IOTA Stiftung"),
+        license(name = "Apache-2.0")
+    ),
+    paths(
+        server::health,
+        server::version,
+        server::openapi,
+        server::debug_health_check,
+        server::reserve_gas,
+        server::execute_tx,
+        server::execute_tx_async,
+        server::get_tx_status,
+        server::reload_access_controller,
+        server::refresh_coin_pool,
+    ),
+    modifiers(&BearerAuth)
+)]
+struct ApiDoc;
+
+/// Adds the bearer secret the endpoints refer to with `security(("bearerAuth" = []))`.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                BEARER_AUTH,
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+/// Builds the OpenAPI document of the gas station RPC server.
+pub fn openapi_spec() -> Value {
+    serde_json::to_value(ApiDoc::openapi()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_spec_covers_all_endpoints() {
+        let spec = openapi_spec();
+        let paths = spec["paths"].as_object().unwrap();
+        for path in [
+            "/",
+            "/version",
+            "/openapi.json",
+            "/debug_health_check",
+            "/v1/reserve_gas",
+            "/v1/execute_tx",
+            "/v1/execute_tx_async",
+            "/v1/tx/{digest}",
+            "/v1/reload_access_controller",
+            "/v1/refresh_coin_pool",
+        ] {
+            assert!(paths.contains_key(path), "missing path {}", path);
+        }
+
+        // Every referenced schema must be part of the components.
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        let text = spec.to_string();
+        for reference in text.split("\"$ref\":\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(schemas.contains_key(name), "missing schema {}", name);
+        }
+        assert!(schemas.contains_key("ReserveGasRequest"));
+        assert!(schemas.contains_key("ErrorCode"));
+        assert!(spec["components"]["securitySchemes"][BEARER_AUTH].is_object());
+    }
+}
@@ -1,15 +1,18 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::errors::{ErrorCode, GasStationError};
 use crate::types::ReservationID;
 use fastcrypto::encoding::Base64;
 use iota_json_rpc_types::{IotaObjectRef, IotaTransactionBlockEffects};
 use iota_types::{
     base_types::{IotaAddress, ObjectRef},
+    digests::TransactionDigest,
     quorum_driver_types::ExecuteTransactionRequestType as IotaExecuteTransactionRequestType,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 // 2 IOTA.
 pub const MAX_BUDGET: u64 = 2_000_000_000;
@@ -17,7 +20,7 @@ pub const MAX_BUDGET: u64 = 2_000_000_000;
 // 10 mins.
 pub const MAX_DURATION_S: u64 = 10 * 60;
 
-#[derive(Clone, Debug, JsonSchema, Serialize, Deserialize)]
+#[derive(Clone, Debug, JsonSchema, ToSchema, Serialize, Deserialize)]
 pub struct ReserveGasRequest {
     pub gas_budget: u64,
     pub reserve_duration_secs: u64,
@@ -44,19 +47,35 @@ impl ReserveGasRequest {
     }
 }
 
-#[derive(Debug, JsonSchema, Serialize, Deserialize)]
+#[derive(Debug, JsonSchema, ToSchema, Serialize, Deserialize)]
 pub struct ReserveGasResponse {
     pub result: Option<ReserveGasResult>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
 }
 
-#[derive(Debug, JsonSchema, Serialize, Deserialize)]
+#[derive(Debug, JsonSchema, ToSchema, Serialize, Deserialize)]
 pub struct ReserveGasResult {
+    #[schema(value_type = String)]
     pub sponsor_address: IotaAddress,
+    #[schema(value_type = u64)]
     pub reservation_id: ReservationID,
+    #[schema(value_type = Vec<ObjectRefSchema>)]
     pub gas_coins: Vec<IotaObjectRef>,
 }
 
+// Describes how `IotaObjectRef` is serialized, for the OpenAPI document.
+/// Reference to an object at a specific version.
+#[derive(ToSchema)]
+#[schema(as = IotaObjectRef, rename_all = "camelCase")]
+#[allow(dead_code)]
+struct ObjectRefSchema {
+    object_id: String,
+    version: u64,
+    digest: String,
+}
+
 impl ReserveGasResponse {
     pub fn new_ok(
         sponsor_address: IotaAddress,
@@ -70,26 +89,32 @@ impl ReserveGasResponse {
                 gas_coins: gas_coins.into_iter().map(|c| c.into()).collect(),
             }),
             error: None,
+            code: None,
         }
     }
 
-    pub fn new_err(error: anyhow::Error) -> Self {
+    pub fn new_err(error: impl Into<GasStationError>) -> Self {
+        let error = error.into();
         Self {
             result: None,
             error: Some(error.to_string()),
+            code: Some(error.code()),
         }
     }
 }
 
-#[derive(Debug, JsonSchema, Serialize, Deserialize)]
+#[derive(Debug, JsonSchema, ToSchema, Serialize, Deserialize)]
 pub struct ExecuteTxRequest {
+    #[schema(value_type = u64)]
     pub reservation_id: ReservationID,
+    #[schema(value_type = String, format = Byte)]
     pub tx_bytes: Base64,
+    #[schema(value_type = String, format = Byte)]
     pub user_sig: Base64,
     pub request_type: Option<ExecuteTransactionRequestType>,
 }
 
-#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub enum ExecuteTransactionRequestType {
     WaitForEffectsCert,
@@ -109,10 +134,13 @@ impl Into<Option<IotaExecuteTransactionRequestType>> for ExecuteTransactionReque
     }
 }
 
-#[derive(Debug, JsonSchema, Serialize, Deserialize)]
+#[derive(Debug, JsonSchema, ToSchema, Serialize, Deserialize)]
 pub struct ExecuteTxResponse {
+    #[schema(value_type = Option<Object>)]
     pub effects: Option<IotaTransactionBlockEffects>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
 }
 
 impl ExecuteTxResponse {
@@ -120,23 +148,88 @@ impl ExecuteTxResponse {
         Self {
             effects: Some(effects),
             error: None,
+            code: None,
         }
     }
 
-    pub fn new_err(error: anyhow::Error) -> Self {
+    pub fn new_err(error: impl Into<GasStationError>) -> Self {
+        let error = error.into();
         Self {
             effects: None,
             error: Some(error.to_string()),
+            code: Some(error.code()),
+        }
+    }
+}
+
+#[derive(Debug, JsonSchema, ToSchema, Serialize, Deserialize)]
+pub struct ExecuteTxAsyncResponse {
+    #[schema(value_type = Option<String>)]
+    pub transaction_digest: Option<TransactionDigest>,
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+}
+
+impl ExecuteTxAsyncResponse {
+    pub fn new_ok(transaction_digest: TransactionDigest) -> Self {
+        Self {
+            transaction_digest: Some(transaction_digest),
+            error: None,
+            code: None,
+        }
+    }
+
+    pub fn new_err(error: impl Into<GasStationError>) -> Self {
+        let error = error.into();
+        Self {
+            transaction_digest: None,
+            error: Some(error.to_string()),
+            code: Some(error.code()),
         }
     }
 }
 
-#[derive(Debug, JsonSchema, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TxExecutionStatus {
+    /// The transaction was accepted and signed, but its execution has not finished yet.
+    Pending,
+    /// The transaction was executed and its effects are available.
+    Executed,
+    /// The transaction could not be executed, see the error for details.
+    Failed,
+}
+
+#[derive(Debug, JsonSchema, ToSchema, Serialize, Deserialize, Clone)]
+pub struct TxStatusResponse {
+    pub status: TxExecutionStatus,
+    #[schema(value_type = Option<Object>)]
+    pub effects: Option<IotaTransactionBlockEffects>,
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+}
+
+impl TxStatusResponse {
+    pub fn new(status: TxExecutionStatus) -> Self {
+        Self {
+            status,
+            effects: None,
+            error: None,
+            code: None,
+        }
+    }
+}
+
+#[derive(Debug, JsonSchema, ToSchema, Serialize, Deserialize)]
 pub struct GasStationResponse<D = ()> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<D>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
 }
 
 impl<D> GasStationResponse<D> {
@@ -144,20 +237,28 @@ impl<D> GasStationResponse<D> {
         Self {
             result: Some(d),
             error: None,
+            code: None,
         }
     }
 
-    pub fn new_err(error: anyhow::Error) -> Self {
+    pub fn new_err(error: impl Into<GasStationError>) -> Self {
+        let error = error.into();
         Self {
             result: None,
             error: Some(error.to_string()),
+            code: Some(error.code()),
         }
     }
+}
 
-    pub fn new_err_from_str(error: impl AsRef<str>) -> Self {
-        Self {
-            result: None,
-            error: Some(error.as_ref().to_string()),
-        }
-    }
+/// Turns the `error` and `code` fields of a failed response back into a typed error.
+/// Responses from older servers without a code are treated as `Internal`.
+pub(crate) fn error_from_response(
+    error: Option<String>,
+    code: Option<ErrorCode>,
+) -> GasStationError {
+    GasStationError::new(
+        code.unwrap_or(ErrorCode::Internal),
+        error.unwrap_or_else(|| "Unknown error".to_string()),
+    )
 }
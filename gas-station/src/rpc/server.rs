@@ -4,18 +4,26 @@
 use crate::access_controller::decision::Decision;
 use crate::access_controller::rule::TransactionContext;
 use crate::access_controller::{AccessController, TransactionExecutionResult};
+use crate::audit_log::{with_signing_context, SigningContext};
 use crate::config::GasStationConfig;
-use crate::errors::generate_event_id;
+use crate::errors::{generate_event_id, GasStationError};
+use crate::execution_notifier::{ExecutionNotification, ExecutionNotifier};
 use crate::gas_station::gas_station_core::GasStation;
 use crate::logging::TxLogMessage;
 use crate::metrics::GasStationRpcMetrics;
+use crate::rpc::browser_auth::{BrowserAuth, BrowserClient};
 use crate::rpc::client::GasStationRpcClient;
+use crate::rpc::openapi::openapi_spec;
 use crate::rpc::rpc_types::{
-    ExecuteTxRequest, ExecuteTxResponse, GasStationResponse, ReserveGasRequest, ReserveGasResponse,
+    error_from_response, ExecuteTxAsyncResponse, ExecuteTxRequest, ExecuteTxResponse,
+    GasStationResponse, ReserveGasRequest, ReserveGasResponse, TxStatusResponse,
 };
+use crate::rpc::tls::{ClientIdentity, TlsServer};
+use crate::rpc::tx_status_tracker::TxStatusTracker;
 use crate::tracker::StatsTracker;
 use crate::{read_auth_env, VERSION};
 use arc_swap::ArcSwap;
+use axum::extract::Path;
 use axum::headers::authorization::Bearer;
 use axum::headers::Authorization;
 use axum::http::{HeaderMap, StatusCode};
@@ -26,18 +34,25 @@ use fastcrypto::encoding::Base64;
 use iota_config::Config;
 use iota_json_rpc_types::IotaTransactionBlockEffectsAPI;
 use iota_types::crypto::ToFromBytes;
+use iota_types::digests::TransactionDigest;
 use iota_types::signature::GenericSignature;
-use iota_types::transaction::TransactionData;
+use iota_types::transaction::{TransactionData, TransactionDataAPI};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
 use tokio::task::JoinHandle;
+use tokio_util::task::TaskTracker;
+use tower_http::cors::CorsLayer;
 use tracing::{debug, error, info, trace, warn};
 
 pub struct GasStationServer {
     pub handle: JoinHandle<()>,
     pub rpc_port: u16,
+    shutdown_sender: tokio::sync::oneshot::Sender<()>,
+    execution_tasks: TaskTracker,
 }
 
 impl GasStationServer {
@@ -49,6 +64,11 @@ impl GasStationServer {
         access_controller: Arc<ArcSwap<AccessController>>,
         stats_tracker: StatsTracker,
         config_path: PathBuf,
+        cors_layer: Option<CorsLayer>,
+        browser_auth: Option<BrowserAuth>,
+        tls_server: Option<TlsServer>,
+        coin_init_refresh: Option<Arc<Notify>>,
+        execution_notifier: Option<Arc<ExecutionNotifier>>,
     ) -> Self {
         let state = ServerState::new(
             station,
@@ -56,35 +76,89 @@ impl GasStationServer {
             access_controller,
             stats_tracker,
             config_path,
+            browser_auth,
+            coin_init_refresh,
+            execution_notifier,
         );
+        let execution_tasks = state.execution_tasks.clone();
         if state.secret.is_none() {
             warn!(
                 "⚠️  {} environment variable is not set. Authorization is disabled! ⚠️",
                 crate::AUTH_ENV_NAME
             );
         }
-        let app = Router::new()
+        let mut app = Router::new()
             .route("/", get(health))
             .route("/version", get(version))
+            .route("/openapi.json", get(openapi))
             .route("/debug_health_check", post(debug_health_check))
             .route("/v1/reserve_gas", post(reserve_gas))
             .route("/v1/execute_tx", post(execute_tx))
+            .route("/v1/execute_tx_async", post(execute_tx_async))
+            .route("/v1/tx/:digest", get(get_tx_status))
             .route(
                 "/v1/reload_access_controller",
                 get(reload_access_controller),
             )
+            .route("/v1/refresh_coin_pool", post(refresh_coin_pool))
             .layer(Extension(state));
+        if let Some(cors_layer) = cors_layer {
+            app = app.layer(cors_layer);
+        }
 
         let address = SocketAddr::new(IpAddr::V4(host_ip), rpc_port);
 
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let shutdown_signal = async move {
+            let _ = shutdown_receiver.await;
+        };
         let handle = tokio::spawn(async move {
-            info!("listening on {}", address);
-            axum::Server::bind(&address)
-                .serve(app.into_make_service())
-                .await
-                .unwrap();
+            match tls_server {
+                Some(tls_server) => {
+                    info!("listening on {} with TLS", address);
+                    tls_server
+                        .serve(address, app, shutdown_signal)
+                        .await
+                        .unwrap();
+                }
+                None => {
+                    info!("listening on {}", address);
+                    axum::Server::bind(&address)
+                        .serve(app.into_make_service())
+                        .with_graceful_shutdown(shutdown_signal)
+                        .await
+                        .unwrap();
+                }
+            }
+            info!("Stopped accepting requests");
         });
-        Self { handle, rpc_port }
+        Self {
+            handle,
+            rpc_port,
+            shutdown_sender,
+            execution_tasks,
+        }
+    }
+
+    /// Stops accepting new requests and waits up to `timeout` for the requests and transaction
+    /// executions in progress. Returns false if they didn't finish in time.
+    pub async fn shutdown(self, timeout: Duration) -> bool {
+        let Self {
+            handle,
+            shutdown_sender,
+            execution_tasks,
+            ..
+        } = self;
+        let _ = shutdown_sender.send(());
+        execution_tasks.close();
+        tokio::time::timeout(timeout, async {
+            if let Err(err) = handle.await {
+                error!("RPC server task failed: {:?}", err);
+            }
+            execution_tasks.wait().await;
+        })
+        .await
+        .is_ok()
     }
 
     pub fn get_local_client(&self) -> GasStationRpcClient {
@@ -100,6 +174,13 @@ struct ServerState {
     access_controller: Arc<ArcSwap<AccessController>>,
     stats_tracker: StatsTracker,
     config_path: PathBuf,
+    tx_status: TxStatusTracker,
+    browser_auth: Option<Arc<BrowserAuth>>,
+    execution_tasks: TaskTracker,
+    /// Starts a coin init refresh. None if the coin initialization is disabled.
+    coin_init_refresh: Option<Arc<Notify>>,
+    /// Queues notifications about executed transactions for webhooks.
+    execution_notifier: Option<Arc<ExecutionNotifier>>,
 }
 
 impl ServerState {
@@ -109,6 +190,9 @@ impl ServerState {
         access_controller: Arc<ArcSwap<AccessController>>,
         stats_tracker: StatsTracker,
         config_path: PathBuf,
+        browser_auth: Option<BrowserAuth>,
+        coin_init_refresh: Option<Arc<Notify>>,
+        execution_notifier: Option<Arc<ExecutionNotifier>>,
     ) -> Self {
         let secret = Arc::new(read_auth_env());
         Self {
@@ -118,21 +202,113 @@ impl ServerState {
             access_controller,
             stats_tracker,
             config_path,
+            tx_status: TxStatusTracker::default(),
+            browser_auth: browser_auth.map(Arc::new),
+            execution_tasks: TaskTracker::new(),
+            coin_init_refresh,
+            execution_notifier,
+        }
+    }
+
+    /// Returns who is calling, or `None` if the request is not authorized.
+    fn authorize(
+        &self,
+        authorization: &Option<TypedHeader<Authorization<Bearer>>>,
+        headers: &HeaderMap,
+    ) -> Option<Caller> {
+        let token = authorization.as_ref().map(|auth| auth.token());
+        match self.secret.as_ref() {
+            Some(secret) if token != Some(secret.as_str()) => self
+                .browser_auth
+                .as_ref()?
+                .authenticate(headers, token)
+                .map(Caller::Browser),
+            _ => Some(Caller::Operator),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Caller {
+    /// The caller presented the bearer secret, or authorization is disabled.
+    Operator,
+    /// A browser client that presented the public key of its origin.
+    Browser(Arc<BrowserClient>),
+}
+
+impl Caller {
+    fn browser_client(self) -> Option<Arc<BrowserClient>> {
+        match self {
+            Caller::Operator => None,
+            Caller::Browser(client) => Some(client),
+        }
+    }
+
+    /// The origin of a browser client, `None` for the operator.
+    fn origin(&self) -> Option<&str> {
+        match self {
+            Caller::Operator => None,
+            Caller::Browser(client) => Some(&client.origin),
         }
     }
 }
 
-async fn health() -> &'static str {
+/// Check that the server is up and running.
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((
+        status = 200,
+        description = "Returns `OK`.",
+        body = String,
+        content_type = "text/plain"
+    ))
+)]
+pub(super) async fn health() -> &'static str {
     info!("Received health request");
     "OK"
 }
 
-async fn version() -> &'static str {
+/// Get the version of the gas station.
+#[utoipa::path(
+    get,
+    path = "/version",
+    responses((
+        status = 200,
+        description = "The version string.",
+        body = String,
+        content_type = "text/plain"
+    ))
+)]
+pub(super) async fn version() -> &'static str {
     info!("Received version request");
     VERSION
 }
 
-async fn debug_health_check(
+/// Get this OpenAPI document.
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    responses((status = 200, description = "The OpenAPI document.", body = Object))
+)]
+pub(super) async fn openapi() -> Json<serde_json::Value> {
+    info!("Received openapi request");
+    Json(openapi_spec())
+}
+
+/// Check the bearer secret, the storage layer and the signer.
+#[utoipa::path(
+    post,
+    path = "/debug_health_check",
+    security(("bearerAuth" = [])),
+    responses((
+        status = 200,
+        description = "Returns `OK` or a description of the failure.",
+        body = String,
+        content_type = "text/plain"
+    ))
+)]
+pub(super) async fn debug_health_check(
     authorization: Option<TypedHeader<Authorization<Bearer>>>,
     Extension(server): Extension<ServerState>,
 ) -> String {
@@ -149,29 +325,37 @@ async fn debug_health_check(
     "OK".to_string()
 }
 
-async fn reserve_gas(
+/// Reserve gas coins for a transaction.
+#[utoipa::path(
+    post,
+    path = "/v1/reserve_gas",
+    security(("bearerAuth" = [])),
+    request_body = ReserveGasRequest,
+    responses(
+        (status = 200, description = "Successful response.", body = ReserveGasResponse),
+        (status = "4XX", description = "The request was rejected.", body = ReserveGasResponse),
+        (status = "5XX", description = "The request failed.", body = ReserveGasResponse),
+    )
+)]
+pub(super) async fn reserve_gas(
+    headers: HeaderMap,
     authorization: Option<TypedHeader<Authorization<Bearer>>>,
     Extension(server): Extension<ServerState>,
     Json(payload): Json<ReserveGasRequest>,
 ) -> impl IntoResponse {
-    if let Some(secret) = server.secret.as_ref() {
-        let token = authorization.as_ref().map(|auth| auth.token());
-        if token != Some(secret.as_str()) {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ReserveGasResponse::new_err(anyhow::anyhow!(
-                    "Authorization token is required or invalid"
-                ))),
-            );
-        }
-    }
+    let Some(caller) = server.authorize(&authorization, &headers) else {
+        return error_response(
+            GasStationError::Unauthorized("Authorization token is required or invalid".to_string()),
+            ReserveGasResponse::new_err,
+        );
+    };
     server.metrics.num_authorized_reserve_gas_requests.inc();
     debug!("Received v1 reserve_gas request: {:?}", payload);
     if let Err(err) = payload.check_validity() {
         debug!("Invalid reserve_gas request: {:?}", err);
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ReserveGasResponse::new_err(err)),
+        return error_response(
+            GasStationError::InvalidRequest(err.to_string()),
+            ReserveGasResponse::new_err,
         );
     }
     let ReserveGasRequest {
@@ -182,25 +366,40 @@ async fn reserve_gas(
         .metrics
         .target_gas_budget_per_request
         .observe(gas_budget);
+    server
+        .metrics
+        .target_gas_budget_distribution
+        .observe(gas_budget as f64);
     server
         .metrics
         .reserve_duration_per_request
         .observe(reserve_duration_secs);
+    // The access rules of browser clients are only checked on execution, so their reservations
+    // are limited to keep them from locking up the pool.
+    let browser_client = caller.browser_client();
+    if let Some(client) = &browser_client {
+        if let Err(err) = client.start_reservation(gas_budget, Instant::now()) {
+            debug!(
+                "Reservation of browser client {} refused: {}",
+                client.origin, err
+            );
+            return error_response(err, ReserveGasResponse::new_err);
+        }
+    }
     // Spawn a thread to process the request so that it will finish even when client drops the connection.
     tokio::task::spawn(reserve_gas_impl(
         server.gas_station.clone(),
         server.metrics.clone(),
+        browser_client,
         gas_budget,
         reserve_duration_secs,
     ))
     .await
     .unwrap_or_else(|err| {
         error!("Failed to spawn reserve_gas task: {:?}", err);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ReserveGasResponse::new_err(anyhow::anyhow!(
-                "Failed to spawn reserve_gas task"
-            ))),
+        error_response(
+            GasStationError::Internal("Failed to spawn reserve_gas task".to_string()),
+            ReserveGasResponse::new_err,
         )
     })
 }
@@ -208,13 +407,21 @@ async fn reserve_gas(
 async fn reserve_gas_impl(
     gas_station: Arc<GasStation>,
     metrics: Arc<GasStationRpcMetrics>,
+    browser_client: Option<Arc<BrowserClient>>,
     gas_budget: u64,
     reserve_duration_secs: u64,
 ) -> (StatusCode, Json<ReserveGasResponse>) {
-    match gas_station
-        .reserve_gas(gas_budget, Duration::from_secs(reserve_duration_secs))
-        .await
-    {
+    let reserve_duration = Duration::from_secs(reserve_duration_secs);
+    let result = gas_station.reserve_gas(gas_budget, reserve_duration).await;
+    if let Some(client) = &browser_client {
+        client.finish_reservation(
+            result
+                .as_ref()
+                .ok()
+                .map(|(_, reservation_id, _)| (*reservation_id, Instant::now() + reserve_duration)),
+        );
+    }
+    match result {
         Ok((sponsor, reservation_id, gas_coins)) => {
             info!(
                 ?reservation_id,
@@ -231,32 +438,37 @@ async fn reserve_gas_impl(
         Err(err) => {
             error!("Failed to reserve gas: {:?}", err);
             metrics.num_failed_reserve_gas_requests.inc();
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ReserveGasResponse::new_err(err)),
-            )
+            error_response(err, ReserveGasResponse::new_err)
         }
     }
 }
 
-async fn execute_tx(
+/// Sponsor and execute a transaction using previously reserved gas coins.
+#[utoipa::path(
+    post,
+    path = "/v1/execute_tx",
+    security(("bearerAuth" = [])),
+    request_body = ExecuteTxRequest,
+    responses(
+        (status = 200, description = "Successful response.", body = ExecuteTxResponse),
+        (status = "4XX", description = "The request was rejected.", body = ExecuteTxResponse),
+        (status = "5XX", description = "The request failed.", body = ExecuteTxResponse),
+    )
+)]
+pub(super) async fn execute_tx(
     headers: HeaderMap,
     authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    client_identity: Option<Extension<ClientIdentity>>,
     Extension(server): Extension<ServerState>,
     Json(payload): Json<ExecuteTxRequest>,
 ) -> impl IntoResponse {
     server.metrics.num_execute_tx_requests.inc();
-    if let Some(secret) = server.secret.as_ref() {
-        let token = authorization.as_ref().map(|auth| auth.token());
-        if token != Some(secret.as_str()) {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ExecuteTxResponse::new_err(anyhow::anyhow!(
-                    "Invalid authorization token"
-                ))),
-            );
-        }
-    }
+    let Some(caller) = server.authorize(&authorization, &headers) else {
+        return error_response(
+            GasStationError::Unauthorized("Invalid authorization token".to_string()),
+            ExecuteTxResponse::new_err,
+        );
+    };
 
     server.metrics.num_authorized_execute_tx_requests.inc();
 
@@ -268,16 +480,14 @@ async fn execute_tx(
         request_type,
     } = payload;
     let Ok((tx_data, user_sig)) = convert_tx_and_sig(tx_bytes.clone(), user_sig_raw.clone()) else {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ExecuteTxResponse::new_err(anyhow::anyhow!(
-                "Invalid bcs bytes for TransactionData"
-            ))),
+        return error_response(
+            GasStationError::InvalidTxBytes("Invalid bcs bytes for TransactionData".to_string()),
+            ExecuteTxResponse::new_err,
         );
     };
 
     // collect information about request and transaction
-    let ctx = TransactionContext::new(
+    let mut ctx = TransactionContext::new(
         &user_sig,
         &tx_data,
         server.stats_tracker.clone(),
@@ -287,26 +497,32 @@ async fn execute_tx(
         request_type,
         headers,
     );
+    if let Some(Extension(ClientIdentity(client_identity))) = client_identity {
+        ctx = ctx.with_client_identity(client_identity);
+    }
 
     // Spawn a thread to process the request so that it will finish even when client drops the connection.
-    tokio::task::spawn(execute_tx_impl(
-        server.gas_station.clone(),
-        server.metrics.clone(),
-        tx_data,
-        user_sig,
-        server.access_controller.clone(),
-        ctx,
-    ))
-    .await
-    .unwrap_or_else(|err| {
-        error!("Failed to spawn execute_tx task: {:?}", err);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ExecuteTxResponse::new_err(anyhow::anyhow!(
-                "Failed to spawn execute_tx task"
-            ))),
-        )
-    })
+    server
+        .execution_tasks
+        .spawn(execute_tx_impl(
+            server.gas_station.clone(),
+            server.metrics.clone(),
+            tx_data,
+            user_sig,
+            server.access_controller.clone(),
+            caller.browser_client(),
+            server.execution_notifier.clone(),
+            ctx,
+            None,
+        ))
+        .await
+        .unwrap_or_else(|err| {
+            error!("Failed to spawn execute_tx task: {:?}", err);
+            error_response(
+                GasStationError::Internal("Failed to spawn execute_tx task".to_string()),
+                ExecuteTxResponse::new_err,
+            )
+        })
 }
 
 async fn execute_tx_impl(
@@ -315,19 +531,69 @@ async fn execute_tx_impl(
     tx_data: TransactionData,
     user_sig: GenericSignature,
     access_controller: Arc<ArcSwap<AccessController>>,
+    browser_client: Option<Arc<BrowserClient>>,
+    execution_notifier: Option<Arc<ExecutionNotifier>>,
     ctx: TransactionContext,
+    signed_notifier: Option<tokio::sync::oneshot::Sender<()>>,
 ) -> (StatusCode, Json<ExecuteTxResponse>) {
-    match access_controller.load().check_access(&ctx).await {
-        Ok(Decision::Allow) => {
+    // The reservation of a browser client stops counting against its limits once the execution
+    // ends, however it ends.
+    let _reservation_guard = browser_client
+        .as_ref()
+        .map(|client| client.reservation_guard(ctx.reservation_id));
+    // Browser clients must pass their own rules in addition to the global access controller.
+    // The webhooks of the rules that allowed the transaction are sent its outcome.
+    let decision = match &browser_client {
+        Some(client) => match client
+            .access_controller
+            .check_access_with_source(&ctx)
+            .await
+        {
+            Ok((Decision::Allow, client_source)) => {
+                let access_controller = access_controller.load_full();
+                access_controller
+                    .check_access_with_source(&ctx)
+                    .await
+                    .map(|(decision, source)| {
+                        let webhooks = client
+                            .access_controller
+                            .notification_hook(client_source)
+                            .into_iter()
+                            .chain(access_controller.notification_hook(source))
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let source =
+                            format!("{} of {} and {}", client_source, client.origin, source);
+                        (decision, source, webhooks)
+                    })
+            }
+            decision => decision.map(|(decision, source)| (decision, source.to_string(), vec![])),
+        },
+        None => {
+            let access_controller = access_controller.load_full();
+            access_controller
+                .check_access_with_source(&ctx)
+                .await
+                .map(|(decision, source)| {
+                    let webhooks = access_controller
+                        .notification_hook(source)
+                        .cloned()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    (decision, source.to_string(), webhooks)
+                })
+        }
+    };
+    let (allowed_by, notification_webhooks) = match decision {
+        Ok((Decision::Allow, source, webhooks)) => {
             metrics.num_allowed_execute_tx_requests.inc();
+            (source, webhooks)
         }
-        Ok(Decision::Deny) => {
+        Ok((Decision::Deny, _, _)) => {
             metrics.num_failed_execute_tx_requests.inc();
-            return (
-                StatusCode::FORBIDDEN,
-                Json(ExecuteTxResponse::new_err(anyhow::anyhow!(
-                    "Access denied by access controller"
-                ))),
+            return error_response(
+                GasStationError::AccessDenied("Access denied by access controller".to_string()),
+                ExecuteTxResponse::new_err,
             );
         }
         Err(err) => {
@@ -336,21 +602,83 @@ async fn execute_tx_impl(
                 "EventId={} Error while checking access: {:?}",
                 event_id, err
             );
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ExecuteTxResponse::new_err(anyhow::anyhow!(
-                    "Error while checking access. EventId={}",
-                    event_id
-                ))),
-            );
+            let message = format!("Error while checking access. EventId={}", event_id);
+            let err = match GasStationError::from(err) {
+                GasStationError::HookError(_) => GasStationError::HookError(message),
+                _ => GasStationError::AccessControllerFailure(message),
+            };
+            return error_response(err, ExecuteTxResponse::new_err);
         }
-    }
+    };
 
     let transaction_digest = tx_data.digest();
-    match gas_station
-        .execute_transaction(ctx.reservation_id, tx_data, user_sig, ctx.request_type)
-        .await
-    {
+    let sponsor = tx_data.gas_owner();
+    let signing_context = SigningContext {
+        reservation_id: Some(ctx.reservation_id),
+        allowed_by: Some(allowed_by.clone()),
+    };
+    // The notifications are queued as pending before the transaction is signed, so that they are
+    // not lost if the gas station stops during the execution.
+    let pending_notifications = match &execution_notifier {
+        Some(execution_notifier) => {
+            let notification = ExecutionNotification::pending(
+                transaction_digest,
+                ctx.reservation_id,
+                ctx.sender_address,
+                sponsor,
+                allowed_by,
+            );
+            execution_notifier
+                .queue_pending(notification, notification_webhooks)
+                .await
+                .map(Some)
+                .map_err(|err| {
+                    error!(
+                        "Failed to queue the execution notification of {}: {:?}",
+                        transaction_digest, err
+                    );
+                    anyhow::Error::from(GasStationError::Internal(
+                        "Failed to queue the execution notification".to_string(),
+                    ))
+                })
+        }
+        None => Ok(None),
+    };
+    let result = match pending_notifications {
+        Ok(pending_notifications) => {
+            let (signed_sender, signed_receiver) = tokio::sync::oneshot::channel();
+            let execution = with_signing_context(
+                signing_context,
+                gas_station.execute_transaction_with_sign_notifier(
+                    ctx.reservation_id,
+                    tx_data,
+                    user_sig,
+                    ctx.request_type.clone(),
+                    Some(signed_sender),
+                ),
+            );
+            // Forwards the signing to the caller, and remembers it for the notifications.
+            let signing = async move {
+                let signed = signed_receiver.await.is_ok();
+                if let (true, Some(signed_notifier)) = (signed, signed_notifier) {
+                    // The receiver may have given up waiting already, which is fine.
+                    let _ = signed_notifier.send(());
+                }
+                signed
+            };
+            let (result, signed) = tokio::join!(execution, signing);
+            if let (Some(execution_notifier), Some(pending_notifications)) =
+                (&execution_notifier, pending_notifications)
+            {
+                execution_notifier
+                    .resolve(pending_notifications, signed, &result)
+                    .await;
+            }
+            result
+        }
+        Err(err) => Err(err),
+    };
+    match result {
         Ok(effects) => {
             info!(
                 ?ctx.reservation_id,
@@ -361,11 +689,11 @@ async fn execute_tx_impl(
             trace!(target: "transactions", "{}", TxLogMessage::new(&effects));
 
             metrics.num_successful_execute_tx_requests.inc();
+            let gas_used = effects.gas_cost_summary().gas_used();
             let confirmation_result = access_controller
                 .load()
                 .confirm_transaction(
-                    TransactionExecutionResult::new(transaction_digest)
-                        .with_gas_usage(effects.gas_cost_summary().gas_used()),
+                    TransactionExecutionResult::new(transaction_digest).with_gas_usage(gas_used),
                     &ctx.stats_tracker.clone(),
                 )
                 .await;
@@ -374,6 +702,22 @@ async fn execute_tx_impl(
             if let Err(err) = confirmation_result {
                 error!("Error while confirming transaction in AC: {:?}", err);
             }
+            if let Some(client) = &browser_client {
+                let confirmation_result = client
+                    .access_controller
+                    .confirm_transaction(
+                        TransactionExecutionResult::new(transaction_digest)
+                            .with_gas_usage(gas_used),
+                        &ctx.stats_tracker,
+                    )
+                    .await;
+                if let Err(err) = confirmation_result {
+                    error!(
+                        "Error while confirming transaction in rules of {}: {:?}",
+                        client.origin, err
+                    );
+                }
+            }
 
             (StatusCode::OK, Json(ExecuteTxResponse::new_ok(effects)))
         }
@@ -390,28 +734,241 @@ async fn execute_tx_impl(
             if let Err(err) = confirmation_result {
                 error!("Error while canceling transaction in AC: {:?}", err);
             }
+            if let Some(client) = &browser_client {
+                let confirmation_result = client
+                    .access_controller
+                    .confirm_transaction(
+                        TransactionExecutionResult::new(transaction_digest),
+                        &ctx.stats_tracker,
+                    )
+                    .await;
+                if let Err(err) = confirmation_result {
+                    error!(
+                        "Error while canceling transaction in rules of {}: {:?}",
+                        client.origin, err
+                    );
+                }
+            }
 
             metrics.num_failed_execute_tx_requests.inc();
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ExecuteTxResponse::new_err(err)),
+            error_response(err, ExecuteTxResponse::new_err)
+        }
+    }
+}
+
+/// Same as `execute_tx`, but responds with the transaction digest as soon as the transaction has
+/// been accepted and signed by the sponsor. The outcome can be polled with `get_tx_status`.
+#[utoipa::path(
+    post,
+    path = "/v1/execute_tx_async",
+    security(("bearerAuth" = [])),
+    request_body = ExecuteTxRequest,
+    responses(
+        (status = 202, description = "Successful response.", body = ExecuteTxAsyncResponse),
+        (status = "4XX", description = "The request was rejected.", body = ExecuteTxAsyncResponse),
+        (status = "5XX", description = "The request failed.", body = ExecuteTxAsyncResponse),
+    )
+)]
+pub(super) async fn execute_tx_async(
+    headers: HeaderMap,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    client_identity: Option<Extension<ClientIdentity>>,
+    Extension(server): Extension<ServerState>,
+    Json(payload): Json<ExecuteTxRequest>,
+) -> impl IntoResponse {
+    server.metrics.num_execute_tx_requests.inc();
+    let Some(caller) = server.authorize(&authorization, &headers) else {
+        return error_response(
+            GasStationError::Unauthorized("Invalid authorization token".to_string()),
+            ExecuteTxAsyncResponse::new_err,
+        );
+    };
+
+    server.metrics.num_authorized_execute_tx_requests.inc();
+
+    debug!("Received v1 execute_tx_async request: {:?}", payload);
+    let ExecuteTxRequest {
+        reservation_id,
+        tx_bytes,
+        user_sig: user_sig_raw,
+        request_type,
+    } = payload;
+    let Ok((tx_data, user_sig)) = convert_tx_and_sig(tx_bytes.clone(), user_sig_raw.clone()) else {
+        return error_response(
+            GasStationError::InvalidTxBytes("Invalid bcs bytes for TransactionData".to_string()),
+            ExecuteTxAsyncResponse::new_err,
+        );
+    };
+
+    let mut ctx = TransactionContext::new(
+        &user_sig,
+        &tx_data,
+        server.stats_tracker.clone(),
+        reservation_id,
+        tx_bytes,
+        user_sig_raw,
+        request_type,
+        headers,
+    );
+    if let Some(Extension(ClientIdentity(client_identity))) = client_identity {
+        ctx = ctx.with_client_identity(client_identity);
+    }
+    let transaction_digest = ctx.transaction_digest;
+
+    let (signed_sender, signed_receiver) = tokio::sync::oneshot::channel();
+    let (accepted_sender, accepted_receiver) = tokio::sync::oneshot::channel();
+    let gas_station = server.gas_station.clone();
+    let metrics = server.metrics.clone();
+    let access_controller = server.access_controller.clone();
+    let execution_notifier = server.execution_notifier.clone();
+    let tx_status = server.tx_status.clone();
+    let submitted_by = caller.origin().map(str::to_string);
+    // Spawn a thread to process the request so that it will finish even when client drops the connection.
+    let execution = server.execution_tasks.spawn(async move {
+        let execution = execute_tx_impl(
+            gas_station,
+            metrics,
+            tx_data,
+            user_sig,
+            access_controller,
+            caller.browser_client(),
+            execution_notifier,
+            ctx,
+            Some(signed_sender),
+        );
+        // The transaction is accepted once it is signed, i.e. after the access control and once
+        // its reservation is used. Only then it is tracked, so that rejected requests, e.g.
+        // replays, never change the status of a transaction.
+        let acceptance = async {
+            let accepted = signed_receiver.await.is_ok()
+                && tx_status.mark_pending(transaction_digest, submitted_by);
+            if accepted {
+                let _ = accepted_sender.send(());
+            }
+            accepted
+        };
+        let ((status_code, Json(response)), accepted) = tokio::join!(execution, acceptance);
+        if accepted {
+            match &response {
+                ExecuteTxResponse {
+                    effects: Some(effects),
+                    ..
+                } => tx_status.mark_executed(transaction_digest, effects.clone()),
+                ExecuteTxResponse { error, code, .. } => tx_status.mark_failed(
+                    transaction_digest,
+                    error_from_response(error.clone(), *code),
+                ),
+            }
+        }
+        (status_code, response)
+    });
+
+    if accepted_receiver.await.is_ok() {
+        return (
+            StatusCode::ACCEPTED,
+            Json(ExecuteTxAsyncResponse::new_ok(transaction_digest)),
+        );
+    }
+    // The request was rejected before signing, or the transaction is tracked already. Wait for
+    // the task so that the caller gets the error right away.
+    match execution.await {
+        Ok((status_code, response)) => (
+            status_code,
+            Json(ExecuteTxAsyncResponse {
+                transaction_digest: None,
+                error: response.error,
+                code: response.code,
+            }),
+        ),
+        Err(err) => {
+            error!("Failed to spawn execute_tx_async task: {:?}", err);
+            error_response(
+                GasStationError::Internal("Failed to spawn execute_tx_async task".to_string()),
+                ExecuteTxAsyncResponse::new_err,
             )
         }
     }
 }
 
-async fn reload_access_controller(
+/// Get the status of a transaction submitted with `execute_tx_async`. The status is only known to
+/// the gas station instance that accepted the transaction, for one hour, and is lost when it
+/// restarts. Browser clients only get the status of the transactions they submitted.
+#[utoipa::path(
+    get,
+    path = "/v1/tx/{digest}",
+    security(("bearerAuth" = [])),
+    params(("digest" = String, Path, description = "Digest of the transaction.")),
+    responses(
+        (
+            status = 200,
+            description = "Successful response.",
+            body = GasStationResponse<TxStatusResponse>
+        ),
+        (
+            status = "4XX",
+            description = "The request was rejected.",
+            body = GasStationResponse<TxStatusResponse>
+        ),
+        (
+            status = "5XX",
+            description = "The request failed.",
+            body = GasStationResponse<TxStatusResponse>
+        ),
+    )
+)]
+pub(super) async fn get_tx_status(
+    headers: HeaderMap,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    Extension(server): Extension<ServerState>,
+    Path(digest): Path<String>,
+) -> impl IntoResponse {
+    let Some(caller) = server.authorize(&authorization, &headers) else {
+        return error_response(
+            GasStationError::Unauthorized("Invalid authorization token".to_string()),
+            GasStationResponse::<TxStatusResponse>::new_err,
+        );
+    };
+    let Ok(digest) = TransactionDigest::from_str(&digest) else {
+        return error_response(
+            GasStationError::InvalidRequest("Invalid transaction digest".to_string()),
+            GasStationResponse::new_err,
+        );
+    };
+    // Browser clients only see their own transactions, the others are reported as unknown.
+    match server.tx_status.get(&digest, caller.origin()) {
+        Some(response) => (StatusCode::OK, Json(GasStationResponse::new_ok(response))),
+        None => error_response(
+            GasStationError::NotFound("Unknown transaction digest".to_string()),
+            GasStationResponse::new_err,
+        ),
+    }
+}
+
+/// Reload the access controller rules from the config file.
+#[utoipa::path(
+    get,
+    path = "/v1/reload_access_controller",
+    security(("bearerAuth" = [])),
+    responses(
+        (status = 200, description = "Successful response.", body = GasStationResponse<String>),
+        (
+            status = "4XX",
+            description = "The request was rejected.",
+            body = GasStationResponse<String>
+        ),
+        (status = "5XX", description = "The request failed.", body = GasStationResponse<String>),
+    )
+)]
+pub(super) async fn reload_access_controller(
     authorization: Option<TypedHeader<Authorization<Bearer>>>,
     Extension(server): Extension<ServerState>,
 ) -> impl IntoResponse {
     if let Some(secret) = server.secret.as_ref() {
         let token = authorization.as_ref().map(|auth| auth.token());
         if token != Some(secret.as_str()) {
-            return (
-                StatusCode::FORBIDDEN,
-                Json(GasStationResponse::new_err_from_str(
-                    "Invalid authorization token",
-                )),
+            return error_response(
+                GasStationError::Unauthorized("Invalid authorization token".to_string()),
+                GasStationResponse::new_err,
             );
         }
     }
@@ -421,12 +978,13 @@ async fn reload_access_controller(
             error!("Failed to load config file: {:?}", err);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(GasStationResponse::new_err_from_str(
-                    "Failed to load config file",
-                )),
+                Json(GasStationResponse::new_err(GasStationError::Internal(
+                    "Failed to load config file".to_string(),
+                ))),
             );
         }
     };
+    access_controller.set_metrics(server.metrics.clone());
     let result = access_controller.initialize().await;
     if let Err(err) = result {
         error!("Failed to initialize access controller: {:?}", err);
@@ -443,6 +1001,56 @@ async fn reload_access_controller(
     return (StatusCode::OK, Json(GasStationResponse::new_ok("success")));
 }
 
+/// Look for new coins of the sponsor and add them to the pool right away.
+#[utoipa::path(
+    post,
+    path = "/v1/refresh_coin_pool",
+    security(("bearerAuth" = [])),
+    responses(
+        (status = 200, description = "Successful response.", body = GasStationResponse<String>),
+        (
+            status = "4XX",
+            description = "The request was rejected.",
+            body = GasStationResponse<String>
+        ),
+        (status = "5XX", description = "The request failed.", body = GasStationResponse<String>),
+    )
+)]
+pub(super) async fn refresh_coin_pool(
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    Extension(server): Extension<ServerState>,
+) -> impl IntoResponse {
+    if let Some(secret) = server.secret.as_ref() {
+        let token = authorization.as_ref().map(|auth| auth.token());
+        if token != Some(secret.as_str()) {
+            return error_response(
+                GasStationError::Unauthorized("Invalid authorization token".to_string()),
+                GasStationResponse::new_err,
+            );
+        }
+    }
+    let Some(coin_init_refresh) = server.coin_init_refresh.as_ref() else {
+        return error_response(
+            GasStationError::InvalidRequest("Coin initialization is disabled".to_string()),
+            GasStationResponse::new_err,
+        );
+    };
+    // The refresh runs in the background. If one is already running, the next one starts as soon
+    // as it completes.
+    coin_init_refresh.notify_one();
+    info!("Coin pool refresh was requested");
+    (StatusCode::OK, Json(GasStationResponse::new_ok("success")))
+}
+
+/// Builds an error response, deriving the HTTP status code from the error code.
+fn error_response<R>(
+    error: impl Into<GasStationError>,
+    new_err: impl FnOnce(GasStationError) -> R,
+) -> (StatusCode, Json<R>) {
+    let error = error.into();
+    (error.status_code(), Json(new_err(error)))
+}
+
 fn convert_tx_and_sig(
     tx_bytes: Base64,
     user_sig: Base64,
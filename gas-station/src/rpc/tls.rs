@@ -0,0 +1,303 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::TlsConfig;
+use anyhow::{bail, Context};
+use arc_swap::ArcSwap;
+use axum::body::Body;
+use axum::http::Request;
+use axum::Router;
+use hyper::server::conn::Http;
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_rustls::rustls::server::AllowAnyAuthenticatedClient;
+use tokio_rustls::rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+/// How often the certificate files are checked for changes.
+const TLS_RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How long a client may take for the TLS handshake, so that stalled handshakes don't hold
+/// connections open.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Subject of the certificate a client presented during the mTLS handshake.
+/// It is added to the extensions of every request sent over that connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientIdentity(pub String);
+
+/// Terminates TLS for the RPC server. The certificates are reloaded when their files change,
+/// so that they can be renewed without a restart.
+#[derive(Clone)]
+pub struct TlsServer {
+    config: TlsConfig,
+    server_config: Arc<ArcSwap<ServerConfig>>,
+}
+
+impl TlsServer {
+    pub fn new(config: TlsConfig) -> anyhow::Result<Self> {
+        let server_config = load_server_config(&config)?;
+        Ok(Self {
+            config,
+            server_config: Arc::new(ArcSwap::from_pointee(server_config)),
+        })
+    }
+
+    /// Serves the app until `shutdown` completes. Then the connections that are already
+    /// established are shut down gracefully: the requests in progress are completed, and the
+    /// connections are closed afterwards. Returns once all connections are closed.
+    pub async fn serve(
+        self,
+        address: SocketAddr,
+        app: Router,
+        shutdown: impl Future<Output = ()>,
+    ) -> anyhow::Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        let reload_task = self.spawn_reload_task();
+        let connections = TaskTracker::new();
+        let shutdown_token = CancellationToken::new();
+        tokio::pin!(shutdown);
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = &mut shutdown => break,
+            };
+            let (stream, remote_address) = match accepted {
+                Ok(connection) => connection,
+                Err(err) => {
+                    warn!("Failed to accept connection: {:?}", err);
+                    continue;
+                }
+            };
+            let acceptor = TlsAcceptor::from(self.server_config.load_full());
+            let app = app.clone();
+            let shutdown_token = shutdown_token.clone();
+            connections.spawn(async move {
+                let handshake =
+                    tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream));
+                let handshake = tokio::select! {
+                    handshake = handshake => handshake,
+                    _ = shutdown_token.cancelled() => return,
+                };
+                let stream = match handshake {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(err)) => {
+                        debug!("TLS handshake with {} failed: {:?}", remote_address, err);
+                        return;
+                    }
+                    Err(_) => {
+                        debug!("TLS handshake with {} timed out", remote_address);
+                        return;
+                    }
+                };
+                let client_identity = stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|certs| certs.first())
+                    .and_then(certificate_subject);
+                let service = app.map_request(move |mut request: Request<Body>| {
+                    if let Some(client_identity) = &client_identity {
+                        request.extensions_mut().insert(client_identity.clone());
+                    }
+                    request
+                });
+                let connection = Http::new().serve_connection(stream, service);
+                tokio::pin!(connection);
+                let result = tokio::select! {
+                    result = connection.as_mut() => result,
+                    _ = shutdown_token.cancelled() => {
+                        connection.as_mut().graceful_shutdown();
+                        connection.await
+                    }
+                };
+                if let Err(err) = result {
+                    debug!(
+                        "Failed to serve connection from {}: {:?}",
+                        remote_address, err
+                    );
+                }
+            });
+        }
+        reload_task.abort();
+        drop(listener);
+        shutdown_token.cancel();
+        connections.close();
+        connections.wait().await;
+        Ok(())
+    }
+
+    fn spawn_reload_task(&self) -> JoinHandle<()> {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let mut last_modified = server.files_modified();
+            let mut interval = tokio::time::interval(TLS_RELOAD_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                let modified = server.files_modified();
+                if modified == last_modified {
+                    continue;
+                }
+                match load_server_config(&server.config) {
+                    Ok(server_config) => {
+                        server.server_config.store(Arc::new(server_config));
+                        last_modified = modified;
+                        info!("Reloaded TLS certificates");
+                    }
+                    // The files may be in the middle of being replaced, so try again next time.
+                    Err(err) => warn!("Failed to reload TLS certificates: {:?}", err),
+                }
+            }
+        })
+    }
+
+    fn files_modified(&self) -> Vec<Option<SystemTime>> {
+        [
+            Some(&self.config.cert_path),
+            Some(&self.config.key_path),
+            self.config.client_ca_path.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+    }
+}
+
+fn load_server_config(config: &TlsConfig) -> anyhow::Result<ServerConfig> {
+    let certs = load_certificates(&config.cert_path)?;
+    let key = load_private_key(&config.key_path)?;
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &config.client_ca_path {
+        Some(client_ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certificates(client_ca_path)? {
+                roots.add(&cert)?;
+            }
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config = builder
+        .with_single_cert(certs, key)
+        .context("Invalid TLS certificate or key")?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+fn load_certificates(path: &Path) -> anyhow::Result<Vec<Certificate>> {
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+    );
+    let certs = rustls_pemfile::certs(&mut reader)?;
+    if certs.is_empty() {
+        bail!("No certificates found in {}", path.display());
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &Path) -> anyhow::Result<PrivateKey> {
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+    );
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => continue,
+        }
+    }
+    bail!("No private key found in {}", path.display())
+}
+
+fn certificate_subject(cert: &Certificate) -> Option<ClientIdentity> {
+    let (_, cert) = x509_parser::parse_x509_certificate(&cert.0).ok()?;
+    Some(ClientIdentity(cert.subject().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn test_load_tls_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let certified_key =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        std::fs::write(&cert_path, certified_key.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified_key.key_pair.serialize_pem()).unwrap();
+
+        let mut config = TlsConfig {
+            cert_path: cert_path.clone(),
+            key_path: key_path.clone(),
+            client_ca_path: None,
+        };
+        TlsServer::new(config.clone()).unwrap();
+
+        // The self signed certificate doubles as the CA for client certificates.
+        config.client_ca_path = Some(cert_path.clone());
+        TlsServer::new(config.clone()).unwrap();
+
+        // The key file doesn't contain a certificate.
+        config.cert_path = key_path;
+        assert!(TlsServer::new(config).is_err());
+
+        let cert = load_certificates(&cert_path).unwrap().remove(0);
+        assert_eq!(
+            certificate_subject(&cert),
+            Some(ClientIdentity("CN=rcgen self signed cert".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_with_pending_handshake() {
+        let dir = tempfile::tempdir().unwrap();
+        let certified_key =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        std::fs::write(&cert_path, certified_key.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified_key.key_pair.serialize_pem()).unwrap();
+        let tls_server = TlsServer::new(TlsConfig {
+            cert_path,
+            key_path,
+            client_ca_path: None,
+        })
+        .unwrap();
+
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(tls_server.serve(address, Router::new(), async move {
+            let _ = shutdown_receiver.await;
+        }));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // A client that never completes the handshake doesn't hold up the shutdown.
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"\x16").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        shutdown_sender.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+}
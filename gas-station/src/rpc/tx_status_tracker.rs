@@ -0,0 +1,151 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::errors::GasStationError;
+use crate::rpc::rpc_types::{TxExecutionStatus, TxStatusResponse};
+use iota_json_rpc_types::IotaTransactionBlockEffects;
+use iota_types::digests::TransactionDigest;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long the outcome of an asynchronously executed transaction is kept around for polling.
+pub const TX_STATUS_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Keeps track of transactions that were accepted by the asynchronous `execute_tx` endpoint,
+/// so that callers can poll for their effects or the final error. Browser clients only see the
+/// transactions they submitted themselves.
+/// The statuses are kept in memory: they are lost when the gas station restarts, and each gas
+/// station instance only knows the transactions it accepted itself.
+#[derive(Clone, Default)]
+pub struct TxStatusTracker {
+    inner: Arc<Mutex<HashMap<TransactionDigest, TrackedTx>>>,
+}
+
+struct TrackedTx {
+    updated_at: Instant,
+    /// Origin of the browser client that submitted the transaction, `None` for the operator.
+    submitted_by: Option<String>,
+    response: TxStatusResponse,
+}
+
+impl TxStatusTracker {
+    /// Tracks a newly accepted transaction as pending. Returns false without changing anything
+    /// if the transaction is tracked already, so that its final status is never overwritten.
+    pub fn mark_pending(&self, digest: TransactionDigest, submitted_by: Option<String>) -> bool {
+        let now = Instant::now();
+        let mut inner = self.inner.lock();
+        prune(&mut inner, now);
+        if inner.contains_key(&digest) {
+            return false;
+        }
+        inner.insert(
+            digest,
+            TrackedTx {
+                updated_at: now,
+                submitted_by,
+                response: TxStatusResponse::new(TxExecutionStatus::Pending),
+            },
+        );
+        true
+    }
+
+    pub fn mark_executed(&self, digest: TransactionDigest, effects: IotaTransactionBlockEffects) {
+        let mut response = TxStatusResponse::new(TxExecutionStatus::Executed);
+        response.effects = Some(effects);
+        self.update(digest, response);
+    }
+
+    pub fn mark_failed(&self, digest: TransactionDigest, error: GasStationError) {
+        let mut response = TxStatusResponse::new(TxExecutionStatus::Failed);
+        response.error = Some(error.to_string());
+        response.code = Some(error.code());
+        self.update(digest, response);
+    }
+
+    /// Returns the status of the transaction if it was submitted by the browser client of
+    /// `origin`. If `origin` is `None`, i.e. for the operator, the status of any transaction is
+    /// returned.
+    pub fn get(
+        &self,
+        digest: &TransactionDigest,
+        origin: Option<&str>,
+    ) -> Option<TxStatusResponse> {
+        self.inner
+            .lock()
+            .get(digest)
+            .filter(|tracked| origin.is_none() || tracked.submitted_by.as_deref() == origin)
+            .map(|tracked| tracked.response.clone())
+    }
+
+    /// Replaces the status of a tracked transaction.
+    fn update(&self, digest: TransactionDigest, response: TxStatusResponse) {
+        let now = Instant::now();
+        let mut inner = self.inner.lock();
+        prune(&mut inner, now);
+        if let Some(tracked) = inner.get_mut(&digest) {
+            tracked.updated_at = now;
+            tracked.response = response;
+        }
+    }
+}
+
+/// Drops stale entries. Called on every write so that the map never grows unbounded.
+fn prune(inner: &mut HashMap<TransactionDigest, TrackedTx>, now: Instant) {
+    inner.retain(|_, tracked| now.duration_since(tracked.updated_at) < TX_STATUS_RETENTION);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+
+    #[test]
+    fn test_tx_status_transitions() {
+        let tracker = TxStatusTracker::default();
+        let digest = TransactionDigest::random();
+        assert!(tracker.get(&digest, None).is_none());
+
+        assert!(tracker.mark_pending(digest, None));
+        assert_eq!(
+            tracker.get(&digest, None).unwrap().status,
+            TxExecutionStatus::Pending
+        );
+
+        tracker.mark_failed(digest, GasStationError::FullnodeFailure("boom".to_string()));
+        let response = tracker.get(&digest, None).unwrap();
+        assert_eq!(response.status, TxExecutionStatus::Failed);
+        assert_eq!(response.error.as_deref(), Some("boom"));
+        assert_eq!(response.code, Some(ErrorCode::FullnodeFailure));
+        assert!(response.effects.is_none());
+
+        // A transaction that is tracked already is not marked pending again.
+        assert!(!tracker.mark_pending(digest, None));
+        assert_eq!(
+            tracker.get(&digest, None).unwrap().status,
+            TxExecutionStatus::Failed
+        );
+    }
+
+    #[test]
+    fn test_tx_status_visible_to_submitter() {
+        let tracker = TxStatusTracker::default();
+        let browser_digest = TransactionDigest::random();
+        let operator_digest = TransactionDigest::random();
+        assert!(tracker.mark_pending(browser_digest, Some("https://app.example.com".to_string())));
+        assert!(tracker.mark_pending(operator_digest, None));
+
+        assert!(tracker
+            .get(&browser_digest, Some("https://app.example.com"))
+            .is_some());
+        assert!(tracker.get(&browser_digest, None).is_some());
+        assert!(tracker
+            .get(&browser_digest, Some("https://other.example.com"))
+            .is_none());
+        assert!(tracker
+            .get(&operator_digest, Some("https://app.example.com"))
+            .is_none());
+        assert!(tracker.get(&operator_digest, None).is_some());
+    }
+}
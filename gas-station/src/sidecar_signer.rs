@@ -0,0 +1,431 @@
+// Copyright (c) Mysten Labs, Inc.
+// Modifications Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::SidecarSignerConfig;
+use crate::tx_signer::TxSigner;
+use anyhow::{anyhow, bail, Context};
+use fastcrypto::encoding::{Base64, Encoding};
+use futures_util::future::join_all;
+use iota_types::base_types::IotaAddress;
+use iota_types::signature::GenericSignature;
+use iota_types::transaction::TransactionData;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Backoff between the attempts to reach the sidecars at startup. It doubles after every
+/// attempt, up to the maximum.
+const STARTUP_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const STARTUP_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureResponse {
+    signature: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IotaAddressResponse {
+    iota_pubkey_address: IotaAddress,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+enum ReplicaState {
+    Healthy = 0,
+    Unreachable = 1,
+    /// The replica reported another address than the other replicas. It is never used.
+    WrongAddress = 2,
+}
+
+struct SidecarReplica {
+    url: String,
+    state: AtomicU8,
+}
+
+impl SidecarReplica {
+    fn state(&self) -> ReplicaState {
+        match self.state.load(Ordering::Relaxed) {
+            0 => ReplicaState::Healthy,
+            1 => ReplicaState::Unreachable,
+            _ => ReplicaState::WrongAddress,
+        }
+    }
+
+    fn set_state(&self, state: ReplicaState) {
+        self.state.store(state as u8, Ordering::Relaxed);
+    }
+}
+
+/// Signs with one or more replicas of a signing sidecar, e.g. the KMS sidecar. Replicas are
+/// probed in the background, and signing requests fail over to the next replica when one fails.
+pub struct SidecarTxSigner {
+    client: Client,
+    replicas: Arc<Vec<SidecarReplica>>,
+    iota_address: IotaAddress,
+    probe_task: JoinHandle<()>,
+}
+
+impl Drop for SidecarTxSigner {
+    fn drop(&mut self) {
+        self.probe_task.abort();
+    }
+}
+
+impl SidecarTxSigner {
+    /// Asks the sidecars for their address, retrying with a backoff until at least one of them
+    /// responds. All replicas that respond must report the same address.
+    pub async fn new(config: SidecarSignerConfig) -> anyhow::Result<Arc<Self>> {
+        let urls = config.urls();
+        if urls.is_empty() {
+            bail!("At least one sidecar URL is required");
+        }
+        let client = build_client(&config)?;
+        let mut backoff = STARTUP_INITIAL_BACKOFF;
+        let mut attempt = 1;
+        let results = loop {
+            let results = join_all(urls.iter().map(|url| fetch_address(&client, url))).await;
+            if results.iter().any(|result| result.is_ok()) {
+                break results;
+            }
+            if attempt >= config.startup_max_attempts {
+                bail!(
+                    "No sidecar is reachable after {} attempts: {:?}",
+                    attempt,
+                    results
+                );
+            }
+            warn!(
+                "No sidecar is reachable (attempt {}/{}), retrying in {:?}: {:?}",
+                attempt, config.startup_max_attempts, backoff, results
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(STARTUP_MAX_BACKOFF);
+            attempt += 1;
+        };
+
+        let mut iota_address = None;
+        let mut replicas = Vec::with_capacity(urls.len());
+        for (url, result) in urls.into_iter().zip(results) {
+            let state = match result {
+                Ok(address) => {
+                    if *iota_address.get_or_insert(address) != address {
+                        bail!(
+                            "Sidecar replicas report different addresses: {} and {}",
+                            iota_address.unwrap(),
+                            address
+                        );
+                    }
+                    ReplicaState::Healthy
+                }
+                Err(err) => {
+                    warn!("Sidecar {} is not reachable yet: {:?}", url, err);
+                    ReplicaState::Unreachable
+                }
+            };
+            replicas.push(SidecarReplica {
+                url,
+                state: AtomicU8::new(state as u8),
+            });
+        }
+        let iota_address = iota_address.unwrap();
+        info!(
+            "Sidecar signer for {} with {} replicas",
+            iota_address,
+            replicas.len()
+        );
+        let replicas = Arc::new(replicas);
+        let probe_task = tokio::spawn({
+            let client = client.clone();
+            let replicas = replicas.clone();
+            let interval = Duration::from_secs(config.health_check_interval_sec.max(1));
+            async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    probe_replicas(&client, &replicas, iota_address).await;
+                }
+            }
+        });
+        Ok(Arc::new(Self {
+            client,
+            replicas,
+            iota_address,
+            probe_task,
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl TxSigner for SidecarTxSigner {
+    async fn sign_transaction(
+        &self,
+        tx_data: &TransactionData,
+    ) -> anyhow::Result<GenericSignature> {
+        let bytes = Base64::encode(bcs::to_bytes(&tx_data)?);
+        // Healthy replicas first, in the configured order. Unreachable ones are only tried as a
+        // last resort, as they may have recovered since the last probe.
+        let candidates = self
+            .replicas
+            .iter()
+            .filter(|replica| replica.state() == ReplicaState::Healthy)
+            .chain(
+                self.replicas
+                    .iter()
+                    .filter(|replica| replica.state() == ReplicaState::Unreachable),
+            );
+        let mut errors = vec![];
+        for replica in candidates {
+            match sign_with(&self.client, &replica.url, &bytes).await {
+                Ok(sig) => {
+                    replica.set_state(ReplicaState::Healthy);
+                    return Ok(sig);
+                }
+                Err(err) => {
+                    warn!("Sidecar {} failed to sign: {:?}", replica.url, err);
+                    replica.set_state(ReplicaState::Unreachable);
+                    errors.push(format!("{}: {}", replica.url, err));
+                }
+            }
+        }
+        bail!("All sidecars failed to sign: {}", errors.join("; "))
+    }
+
+    fn get_address(&self) -> IotaAddress {
+        self.iota_address
+    }
+
+    async fn check_health(&self) -> anyhow::Result<()> {
+        probe_replicas(&self.client, &self.replicas, self.iota_address).await;
+        if self
+            .replicas
+            .iter()
+            .any(|replica| replica.state() == ReplicaState::Healthy)
+        {
+            Ok(())
+        } else {
+            bail!("No sidecar is healthy")
+        }
+    }
+}
+
+fn build_client(config: &SidecarSignerConfig) -> anyhow::Result<Client> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        headers.insert(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+    if let Some(env_name) = &config.bearer_token_env {
+        let token = std::env::var(env_name)
+            .with_context(|| format!("The sidecar token is not set in {}", env_name))?;
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", token))?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    let mut builder = Client::builder()
+        .default_headers(headers)
+        .timeout(Duration::from_millis(config.request_timeout_ms))
+        .connect_timeout(Duration::from_millis(config.connect_timeout_ms));
+    if let Some(tls) = &config.tls {
+        builder = tls.configure(builder)?;
+    }
+    Ok(builder.build()?)
+}
+
+async fn fetch_address(client: &Client, url: &str) -> anyhow::Result<IotaAddress> {
+    let address = client
+        .get(format!("{}/{}", url, "get-pubkey-address"))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<IotaAddressResponse>()
+        .await?
+        .iota_pubkey_address;
+    Ok(address)
+}
+
+async fn sign_with(client: &Client, url: &str, bytes: &str) -> anyhow::Result<GenericSignature> {
+    let resp = client
+        .post(format!("{}/{}", url, "sign-transaction"))
+        .header("Content-Type", "application/json")
+        .json(&json!({"txBytes": bytes}))
+        .send()
+        .await?
+        .error_for_status()?;
+    let sig_bytes = resp.json::<SignatureResponse>().await?;
+    let sig =
+        GenericSignature::from_str(&sig_bytes.signature).map_err(|err| anyhow!(err.to_string()))?;
+    Ok(sig)
+}
+
+/// Updates the state of every replica by asking it for its address.
+async fn probe_replicas(client: &Client, replicas: &[SidecarReplica], iota_address: IotaAddress) {
+    let results = join_all(
+        replicas
+            .iter()
+            .map(|replica| fetch_address(client, &replica.url)),
+    )
+    .await;
+    for (replica, result) in replicas.iter().zip(results) {
+        let previous_state = replica.state();
+        let state = match result {
+            Ok(address) if address == iota_address => ReplicaState::Healthy,
+            Ok(address) => {
+                error!(
+                    "Sidecar {} reports the address {} instead of {}",
+                    replica.url, address, iota_address
+                );
+                ReplicaState::WrongAddress
+            }
+            Err(err) => {
+                if previous_state == ReplicaState::Healthy {
+                    warn!("Sidecar {} is unhealthy: {:?}", replica.url, err);
+                }
+                ReplicaState::Unreachable
+            }
+        };
+        if state == ReplicaState::Healthy && previous_state != ReplicaState::Healthy {
+            info!("Sidecar {} is healthy again", replica.url);
+        }
+        replica.set_state(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_signer::TestTxSigner;
+    use axum::extract::State;
+    use axum::http::{HeaderMap as AxumHeaderMap, StatusCode};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use iota_types::crypto::{get_key_pair, AccountKeyPair, IotaKeyPair};
+    use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use iota_types::transaction::TransactionKind;
+    use std::net::TcpListener;
+
+    const API_KEY: &str = "secret";
+
+    /// A sidecar signing with a local key, which requires the `x-api-key` header.
+    fn start_sidecar(signer: Arc<dyn TxSigner>) -> (String, JoinHandle<()>) {
+        async fn get_address(
+            State(signer): State<Arc<dyn TxSigner>>,
+            headers: AxumHeaderMap,
+        ) -> Result<Json<serde_json::Value>, StatusCode> {
+            if headers.get("x-api-key").map(|value| value.as_bytes()) != Some(API_KEY.as_bytes()) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            Ok(Json(
+                json!({"iotaPubkeyAddress": signer.get_address().to_string()}),
+            ))
+        }
+        async fn sign(
+            State(signer): State<Arc<dyn TxSigner>>,
+            Json(body): Json<serde_json::Value>,
+        ) -> Json<serde_json::Value> {
+            let bytes = Base64::decode(body["txBytes"].as_str().unwrap()).unwrap();
+            let tx_data: TransactionData = bcs::from_bytes(&bytes).unwrap();
+            let sig = signer.sign_transaction(&tx_data).await.unwrap();
+            Json(json!({"signature": Base64::encode(sig.as_ref())}))
+        }
+        let app = Router::new()
+            .route("/get-pubkey-address", get(get_address))
+            .route("/sign-transaction", post(sign))
+            .with_state(signer);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+        (url, handle)
+    }
+
+    fn new_signer() -> Arc<dyn TxSigner> {
+        let (_, keypair): (_, AccountKeyPair) = get_key_pair();
+        TestTxSigner::new(IotaKeyPair::Ed25519(keypair))
+    }
+
+    fn new_config(urls: Vec<String>) -> SidecarSignerConfig {
+        let mut config = SidecarSignerConfig::new(urls[0].clone());
+        config.sidecar_urls = urls;
+        config.startup_max_attempts = 2;
+        config
+            .headers
+            .insert("x-api-key".to_string(), API_KEY.to_string());
+        config
+    }
+
+    fn unused_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_sidecar_failover() {
+        let key = new_signer();
+        let (url1, sidecar1) = start_sidecar(key.clone());
+        let (url2, sidecar2) = start_sidecar(key.clone());
+        // A replica that is down at startup doesn't prevent the signer from starting.
+        let signer = SidecarTxSigner::new(new_config(vec![unused_url(), url1, url2]))
+            .await
+            .unwrap();
+        assert_eq!(signer.get_address(), key.get_address());
+        signer.check_health().await.unwrap();
+
+        let tx_data = TransactionData::new_with_gas_coins_allow_sponsor(
+            TransactionKind::programmable(ProgrammableTransactionBuilder::new().finish()),
+            IotaAddress::random_for_testing_only(),
+            vec![],
+            1,
+            1,
+            key.get_address(),
+        );
+        let expected = key.sign_transaction(&tx_data).await.unwrap();
+        assert_eq!(signer.sign_transaction(&tx_data).await.unwrap(), expected);
+
+        // Fail over to the second replica.
+        sidecar1.abort();
+        let _ = sidecar1.await;
+        assert_eq!(signer.sign_transaction(&tx_data).await.unwrap(), expected);
+        signer.check_health().await.unwrap();
+
+        sidecar2.abort();
+        let _ = sidecar2.await;
+        assert!(signer.sign_transaction(&tx_data).await.is_err());
+        assert!(signer.check_health().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sidecar_startup_errors() {
+        // Replicas must report the same address.
+        let (url1, _sidecar1) = start_sidecar(new_signer());
+        let (url2, _sidecar2) = start_sidecar(new_signer());
+        assert!(SidecarTxSigner::new(new_config(vec![url1.clone(), url2]))
+            .await
+            .is_err());
+
+        // The startup gives up when no replica is reachable.
+        assert!(SidecarTxSigner::new(new_config(vec![unused_url()]))
+            .await
+            .is_err());
+
+        // The auth header is required by the sidecar.
+        let mut config = new_config(vec![url1]);
+        config.headers.clear();
+        assert!(SidecarTxSigner::new(config).await.is_err());
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright (c) 2025 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::iota_client::IotaClient;
+use crate::pool_drainer::drain_pool;
+use crate::storage::Storage;
+use crate::tx_signer::TxSigner;
+use iota_types::base_types::IotaAddress;
+use iota_types::crypto::PublicKey;
+use iota_types::signature::GenericSignature;
+use iota_types::transaction::{TransactionData, TransactionDataAPI};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// How long to wait before trying again when the funds could not be moved yet.
+const ROTATION_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Signs with the current signer, and with the previous one for transactions whose gas is paid
+/// by the previous sponsor, so that its outstanding reservations can still be executed.
+pub struct RotatingTxSigner {
+    current: Arc<dyn TxSigner>,
+    previous: Arc<dyn TxSigner>,
+}
+
+impl RotatingTxSigner {
+    pub fn new(current: Arc<dyn TxSigner>, previous: Arc<dyn TxSigner>) -> Arc<Self> {
+        Arc::new(Self { current, previous })
+    }
+}
+
+#[async_trait::async_trait]
+impl TxSigner for RotatingTxSigner {
+    async fn sign_transaction(
+        &self,
+        tx_data: &TransactionData,
+    ) -> anyhow::Result<GenericSignature> {
+        if self.previous.is_valid_address(&tx_data.gas_data().owner) {
+            self.previous.sign_transaction(tx_data).await
+        } else {
+            self.current.sign_transaction(tx_data).await
+        }
+    }
+
+    fn get_address(&self) -> IotaAddress {
+        self.current.get_address()
+    }
+
+    fn is_valid_address(&self, address: &IotaAddress) -> bool {
+        self.current.is_valid_address(address) || self.previous.is_valid_address(address)
+    }
+
+    fn get_public_key(&self) -> Option<PublicKey> {
+        self.current.get_public_key()
+    }
+
+    async fn check_health(&self) -> anyhow::Result<()> {
+        self.current.check_health().await
+    }
+}
+
+/// Retires the previous sponsor in the background. It stops new reservations from its pool,
+/// waits for the outstanding ones, moves its funds to the current sponsor and deletes the pool.
+/// The coin initialization is notified to split the moved funds right away.
+pub struct SignerRotation {
+    task_handle: JoinHandle<()>,
+    // This is always Some. It is None only after the drop or shutdown method is called.
+    cancel_sender: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl Drop for SignerRotation {
+    fn drop(&mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            cancel_sender.send(()).unwrap();
+        }
+    }
+}
+
+struct RotationEnv {
+    iota_client: IotaClient,
+    previous_storage: Arc<dyn Storage>,
+    previous_signer: Arc<dyn TxSigner>,
+    sponsor_address: IotaAddress,
+    wait_timeout: Duration,
+    refresh_notifier: Option<Arc<Notify>>,
+}
+
+impl SignerRotation {
+    pub fn start(
+        iota_client: IotaClient,
+        previous_storage: Arc<dyn Storage>,
+        previous_signer: Arc<dyn TxSigner>,
+        sponsor_address: IotaAddress,
+        wait_timeout: Duration,
+        refresh_notifier: Option<Arc<Notify>>,
+    ) -> Self {
+        let env = RotationEnv {
+            iota_client,
+            previous_storage,
+            previous_signer,
+            sponsor_address,
+            wait_timeout,
+            refresh_notifier,
+        };
+        let (cancel_sender, mut cancel_receiver) = tokio::sync::oneshot::channel();
+        let task_handle = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    result = env.rotate_once() => match result {
+                        Ok(()) => break,
+                        Err(err) => error!("Failed to rotate the sponsor: {:?}", err),
+                    },
+                    _ = &mut cancel_receiver => {
+                        info!("Sponsor rotation task is cancelled");
+                        break;
+                    }
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(ROTATION_RETRY_INTERVAL) => {}
+                    _ = &mut cancel_receiver => {
+                        info!("Sponsor rotation task is cancelled");
+                        break;
+                    }
+                }
+            }
+        });
+        Self {
+            task_handle,
+            cancel_sender: Some(cancel_sender),
+        }
+    }
+
+    /// Cancels the rotation task and waits for it to stop. A rotation in progress is stopped as
+    /// well, and is started again at the next start.
+    pub async fn shutdown(mut self) {
+        if let Some(cancel_sender) = self.cancel_sender.take() {
+            let _ = cancel_sender.send(());
+        }
+        if let Err(err) = (&mut self.task_handle).await {
+            error!("Sponsor rotation task failed: {:?}", err);
+        }
+    }
+}
+
+impl RotationEnv {
+    async fn rotate_once(&self) -> anyhow::Result<()> {
+        let previous_address = self.previous_signer.get_address();
+        // Stop reservations from the previous pool right away, even if another task holds the
+        // init lock for now.
+        self.previous_storage.set_draining(true).await?;
+        info!(
+            "Rotating the sponsor from {} to {}",
+            previous_address, self.sponsor_address
+        );
+        let summary = drain_pool(
+            &self.iota_client,
+            &self.previous_storage,
+            &self.previous_signer,
+            self.sponsor_address,
+            self.wait_timeout,
+        )
+        .await?;
+        if let Some(refresh_notifier) = &self.refresh_notifier {
+            refresh_notifier.notify_one();
+        }
+        self.previous_storage.delete_sponsor_data().await?;
+        info!(
+            "Rotated the sponsor from {} to {}. Moved {} coins with a total balance of {}",
+            previous_address,
+            self.sponsor_address,
+            summary.merged_coins,
+            summary.transferred_balance
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::connect_storage_for_testing;
+    use crate::test_env::start_iota_cluster;
+    use crate::tx_signer::TestTxSigner;
+    use iota_types::crypto::{get_key_pair, AccountKeyPair, IotaKeyPair};
+    use iota_types::gas_coin::NANOS_PER_IOTA;
+    use iota_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use iota_types::transaction::TransactionKind;
+
+    fn new_signer() -> Arc<dyn TxSigner> {
+        let (_, keypair): (_, AccountKeyPair) = get_key_pair();
+        TestTxSigner::new(IotaKeyPair::Ed25519(keypair))
+    }
+
+    #[tokio::test]
+    async fn test_rotating_signer() {
+        let current = new_signer();
+        let previous = new_signer();
+        let signer = RotatingTxSigner::new(current.clone(), previous.clone());
+        assert_eq!(signer.get_address(), current.get_address());
+        assert!(signer.is_valid_address(&current.get_address()));
+        assert!(signer.is_valid_address(&previous.get_address()));
+        assert!(!signer.is_valid_address(&IotaAddress::random_for_testing_only()));
+
+        // Transactions are signed by the signer of their gas owner.
+        for sponsor in [current.get_address(), previous.get_address()] {
+            let tx_data = TransactionData::new_with_gas_coins_allow_sponsor(
+                TransactionKind::programmable(ProgrammableTransactionBuilder::new().finish()),
+                IotaAddress::random_for_testing_only(),
+                vec![],
+                1,
+                1,
+                sponsor,
+            );
+            let signature = signer.sign_transaction(&tx_data).await.unwrap();
+            let expected = if sponsor == current.get_address() {
+                current.sign_transaction(&tx_data).await.unwrap()
+            } else {
+                previous.sign_transaction(&tx_data).await.unwrap()
+            };
+            assert_eq!(signature, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotate_sponsor() {
+        telemetry_subscribers::init_for_testing();
+        let (cluster, previous_signer) = start_iota_cluster(vec![NANOS_PER_IOTA; 10]).await;
+        let previous_address = previous_signer.get_address();
+        let fullnode_url = cluster.fullnode_handle.rpc_url.clone();
+        let iota_client = IotaClient::new(&fullnode_url, None).await;
+        let previous_storage = connect_storage_for_testing(previous_address).await;
+        let coins = iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(previous_address, 0)
+            .await;
+        previous_storage.add_new_coins(coins).await.unwrap();
+
+        let sponsor_address = new_signer().get_address();
+        let refresh_notifier = Arc::new(Notify::new());
+        let env = RotationEnv {
+            iota_client: iota_client.clone(),
+            previous_storage: previous_storage.clone(),
+            previous_signer,
+            sponsor_address,
+            wait_timeout: Duration::from_secs(10),
+            refresh_notifier: Some(refresh_notifier.clone()),
+        };
+        env.rotate_once().await.unwrap();
+
+        // The funds were moved to the new sponsor, and the coin initialization was notified.
+        let new_coins = iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(sponsor_address, 0)
+            .await;
+        assert_eq!(new_coins.len(), 1);
+        assert!(new_coins[0].balance > 9 * NANOS_PER_IOTA);
+        tokio::time::timeout(Duration::from_secs(1), refresh_notifier.notified())
+            .await
+            .unwrap();
+        assert!(iota_client
+            .get_all_owned_iota_coins_above_balance_threshold(previous_address, 0)
+            .await
+            .is_empty());
+        assert!(!previous_storage.is_initialized().await.unwrap());
+        assert!(!previous_storage.is_draining().await.unwrap());
+    }
+}
@@ -4,14 +4,50 @@
 use crate::config::GasStationStorageConfig;
 use crate::metrics::StorageMetrics;
 use crate::storage::redis::RedisStorage;
-use crate::types::{GasCoin, ReservationID};
+use crate::types::{CoinDenomination, GasCoin, ReservationID};
 use iota_types::base_types::{IotaAddress, ObjectID};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
 
 mod redis;
 
 pub const MAX_GAS_PER_QUERY: usize = 256;
 
+/// Version of the format the pool is stored in. It is increased whenever instances of different
+/// versions can no longer share the storage, e.g. version 2 keeps the available coins ordered by
+/// balance instead of in a list.
+pub const STORAGE_VERSION: u64 = 2;
+
+/// Number of coins in each category of differences found by `Storage::reconcile_coins`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CoinReconciliation {
+    /// Available coins whose object ref or balance was outdated.
+    pub updated: u64,
+    /// Available coins that are no longer owned by the sponsor.
+    pub removed: u64,
+    /// Owned coins that were not tracked by the pool.
+    pub adopted: u64,
+    /// Available coins that were also available or reserved elsewhere in the pool.
+    pub duplicates: u64,
+}
+
+impl CoinReconciliation {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A notification in the outbox, claimed for delivery by `Storage::claim_notifications`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboxNotification {
+    pub id: u64,
+    /// The number of delivery attempts, including the current one.
+    pub attempts: u32,
+    pub content: String,
+}
+
 /// Defines the trait for a storage that manages gas coins.
 /// It is expected to support concurrent access and manage atomicity internally.
 /// It supports multiple addresses each with its own gas coin queue.
@@ -30,12 +66,58 @@ pub trait Storage: Sync + Send {
         reserved_duration_ms: u64,
     ) -> anyhow::Result<(ReservationID, Vec<GasCoin>)>;
 
-    async fn ready_for_execution(&self, reservation_id: ReservationID) -> anyhow::Result<()>;
+    /// Reserves between `min_count` and `max_count` coins with balance below `max_balance`, in the
+    /// same way as `reserve_gas_coins`. Returns no coins if there are fewer than `min_count`.
+    async fn reserve_dust_coins(
+        &self,
+        max_balance: u64,
+        min_count: usize,
+        max_count: usize,
+        reserved_duration_ms: u64,
+    ) -> anyhow::Result<(ReservationID, Vec<GasCoin>)>;
+
+    /// Takes the reservation out of the expiration queue right before execution.
+    /// Fails with `ReservationExpired` if the reservation no longer exists, and with
+    /// `PaymentMismatch` if `payment` is not exactly the set of reserved coins.
+    /// On success, the coins are kept under an execution lease of `lease_duration_ms` until
+    /// `complete_execution` is called, so that they can be recovered if that never happens.
+    async fn ready_for_execution(
+        &self,
+        reservation_id: ReservationID,
+        payment: &[ObjectID],
+        lease_duration_ms: u64,
+    ) -> anyhow::Result<()>;
 
     async fn add_new_coins(&self, new_coins: Vec<GasCoin>) -> anyhow::Result<()>;
 
     async fn expire_coins(&self) -> anyhow::Result<Vec<ObjectID>>;
 
+    /// Removes the execution lease of the reservation and adds `updated_coins` back to the pool
+    /// atomically. Returns false without adding the coins if the lease no longer exists,
+    /// because its coins were already recovered by `expire_execution_leases`.
+    async fn complete_execution(
+        &self,
+        reservation_id: ReservationID,
+        updated_coins: Vec<GasCoin>,
+    ) -> anyhow::Result<bool>;
+
+    /// Takes out all execution leases that have expired and returns their coins.
+    /// The caller is responsible for adding the latest state of these coins back to the pool.
+    async fn expire_execution_leases(&self) -> anyhow::Result<Vec<ObjectID>>;
+
+    /// Makes the available coins match `owned_coins`, the coins owned by the sponsor on chain.
+    /// Outdated coins are updated, coins that are not owned anymore or tracked twice are dropped,
+    /// and owned coins with a balance below `max_adopted_balance` that are neither available nor
+    /// reserved are added. Reserved coins are left untouched.
+    /// The pool is changed in batches, each of which skips coins that were changed concurrently.
+    /// If `dry_run` is true, the differences are only counted.
+    async fn reconcile_coins(
+        &self,
+        owned_coins: Vec<GasCoin>,
+        max_adopted_balance: u64,
+        dry_run: bool,
+    ) -> anyhow::Result<CoinReconciliation>;
+
     /// Initialize some of the Gas Station statistics at the startup.
     /// Such as the total number of gas coins and the total balance.
     /// This is needed for several reasons:
@@ -45,6 +127,31 @@ pub trait Storage: Sync + Send {
     /// Returns the total number of gas coins and the total balance.
     async fn init_coin_stats_at_startup(&self) -> anyhow::Result<(u64, u64)>;
 
+    /// Returns the version of the format the data of the sponsor is stored in. If there is no
+    /// data yet, `STORAGE_VERSION` is recorded and returned.
+    async fn get_storage_version(&self) -> anyhow::Result<u64>;
+
+    /// Converts the data of the sponsor written by older versions to `STORAGE_VERSION`. No
+    /// instance of an older version may use the storage anymore.
+    async fn migrate_storage(&self) -> anyhow::Result<()>;
+
+    /// Persists the denominations new coins are split into, replacing the previous ones.
+    async fn set_coin_denominations(
+        &self,
+        denominations: &[CoinDenomination],
+    ) -> anyhow::Result<()>;
+
+    /// Returns the persisted denominations, or an empty list if there are none.
+    async fn get_coin_denominations(&self) -> anyhow::Result<Vec<CoinDenomination>>;
+
+    /// Adds gas budgets observed by this instance to the statistics shared by all instances, as
+    /// (bucket upper bound, count) pairs.
+    async fn add_budget_samples(&self, samples: &[(u64, u64)]) -> anyhow::Result<()>;
+
+    /// Returns the gas budgets observed by all instances, as (bucket upper bound, count) pairs
+    /// sorted by upper bound.
+    async fn get_budget_samples(&self) -> anyhow::Result<Vec<(u64, u64)>>;
+
     /// Whether the Gas Station for the given sponsor address is initialized.
     async fn is_initialized(&self) -> anyhow::Result<bool>;
 
@@ -55,8 +162,61 @@ pub trait Storage: Sync + Send {
     /// the lock will be automatically considered as released after the lock duration.
     async fn acquire_init_lock(&self, lock_duration_sec: u64) -> anyhow::Result<bool>;
 
+    /// Extends the init lock held by the caller to expire `lock_duration_sec` from now.
+    /// Returns false if the lock has expired or was released in the meantime, in which case it
+    /// is not extended.
+    async fn renew_init_lock(&self, lock_duration_sec: u64) -> anyhow::Result<bool>;
+
     async fn release_init_lock(&self) -> anyhow::Result<()>;
 
+    /// Starts or stops draining the pool. While it is draining, no gas coins are reserved.
+    async fn set_draining(&self, draining: bool) -> anyhow::Result<()>;
+
+    async fn is_draining(&self) -> anyhow::Result<bool>;
+
+    /// The number of reservations whose coins are not yet returned to the pool.
+    async fn get_reservation_count(&self) -> anyhow::Result<usize>;
+
+    /// Drops the available gas coins and marks the Gas Station as not initialized, so that the
+    /// pool is initialized again from the coins owned on chain at the next start.
+    async fn mark_uninitialized(&self) -> anyhow::Result<()>;
+
+    /// Deletes everything stored for the sponsor address, once it is no longer used.
+    async fn delete_sponsor_data(&self) -> anyhow::Result<()>;
+
+    /// Adds a notification to the outbox, from where it is delivered after `delay_ms` until it is
+    /// completed. Returns the id of the notification.
+    async fn add_notification(&self, content: &str, delay_ms: u64) -> anyhow::Result<u64>;
+
+    /// Claims up to `max_count` notifications of the outbox that are due, for
+    /// `lease_duration_ms`. A claimed notification is due again once its lease expires, unless it
+    /// is completed or rescheduled before, so that it is delivered even if the caller crashes.
+    async fn claim_notifications(
+        &self,
+        max_count: usize,
+        lease_duration_ms: u64,
+    ) -> anyhow::Result<Vec<OutboxNotification>>;
+
+    /// Removes a delivered notification from the outbox.
+    async fn complete_notification(&self, id: u64) -> anyhow::Result<()>;
+
+    /// Makes a notification that could not be delivered due again after `delay_ms`.
+    async fn reschedule_notification(&self, id: u64, delay_ms: u64) -> anyhow::Result<()>;
+
+    /// Replaces the content of a notification, resets its delivery attempts and makes it due.
+    /// Returns false if the notification is no longer in the outbox.
+    async fn update_notification(&self, id: u64, content: &str) -> anyhow::Result<bool>;
+
+    /// The number of notifications in the outbox.
+    async fn get_notification_count(&self) -> anyhow::Result<usize>;
+
+    /// Moves a notification that can't be delivered from the outbox to the dead letters, where it
+    /// is kept for inspection.
+    async fn dead_letter_notification(&self, id: u64) -> anyhow::Result<()>;
+
+    /// The number of dead letters, i.e. notifications that could not be delivered.
+    async fn get_dead_notification_count(&self) -> anyhow::Result<usize>;
+
     async fn check_health(&self) -> anyhow::Result<()>;
 
     #[cfg(test)]
@@ -70,24 +230,106 @@ pub trait Storage: Sync + Send {
     async fn get_reserved_coin_count(&self) -> usize;
 }
 
+/// Duration of the init lock held by the background jobs that change the pool. The lock is
+/// renewed while a job runs, so that it is released soon after a gas station stops.
+pub const INIT_LOCK_LEASE_SEC: u64 = 60;
+
+/// Runs `task` while the init lock is held, which must have been acquired by the caller for
+/// `lock_duration_sec`. The lock is renewed every third of its duration. If it can't be renewed
+/// before it expires, someone else may hold it, so the task is aborted with an error.
+pub async fn run_with_init_lock<T>(
+    storage: &Arc<dyn Storage>,
+    lock_duration_sec: u64,
+    task: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    let renew_lock = async {
+        let mut interval = tokio::time::interval(Duration::from_secs(lock_duration_sec) / 3);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match storage.renew_init_lock(lock_duration_sec).await {
+                Ok(true) => {}
+                Ok(false) => return anyhow::anyhow!("The init lock expired"),
+                // The lock is renewed again at the next tick, before it expires.
+                Err(err) => warn!("Failed to renew the init lock: {:?}", err),
+            }
+        }
+    };
+    tokio::select! {
+        result = task => result,
+        err = renew_lock => Err(err),
+    }
+}
+
 pub async fn connect_storage(
     config: &GasStationStorageConfig,
     sponsor_address: IotaAddress,
     metrics: Arc<StorageMetrics>,
 ) -> Arc<dyn Storage> {
-    let storage: Arc<dyn Storage> = match config {
-        GasStationStorageConfig::Redis { redis_url } => {
-            Arc::new(RedisStorage::new(redis_url, sponsor_address, metrics).await)
-        }
-    };
+    let storage = new_storage(config, sponsor_address, metrics).await;
     storage
         .check_health()
         .await
         .expect("Unable to connect to the storage layer");
+    let version = storage.get_storage_version().await.unwrap();
+    assert!(
+        version <= STORAGE_VERSION,
+        "The storage was written by a newer version of the gas station (storage version {})",
+        version
+    );
+    assert!(
+        version == STORAGE_VERSION,
+        "The storage was written by an older version of the gas station (storage version {}). \
+        Stop all instances of older versions, then run `iota-gas-station-tool migrate-storage`",
+        version
+    );
     storage.init_coin_stats_at_startup().await.unwrap();
     storage
 }
 
+/// Converts the storage of the sponsor written by older versions of the gas station. All
+/// instances of older versions must be stopped first.
+pub async fn migrate_storage(
+    config: &GasStationStorageConfig,
+    sponsor_address: IotaAddress,
+    metrics: Arc<StorageMetrics>,
+) -> anyhow::Result<u64> {
+    let storage = new_storage(config, sponsor_address, metrics).await;
+    storage.check_health().await?;
+    let version = storage.get_storage_version().await?;
+    if version > STORAGE_VERSION {
+        anyhow::bail!(
+            "The storage was written by a newer version of the gas station (storage version {})",
+            version
+        );
+    }
+    if version < STORAGE_VERSION {
+        storage.migrate_storage().await?;
+    }
+    Ok(version)
+}
+
+async fn new_storage(
+    config: &GasStationStorageConfig,
+    sponsor_address: IotaAddress,
+    metrics: Arc<StorageMetrics>,
+) -> Arc<dyn Storage> {
+    match config {
+        GasStationStorageConfig::Redis {
+            redis_url,
+            coin_selection_strategy,
+        } => Arc::new(
+            RedisStorage::new(
+                redis_url,
+                sponsor_address,
+                *coin_selection_strategy,
+                metrics,
+            )
+            .await,
+        ),
+    }
+}
+
 #[cfg(test)]
 pub async fn connect_storage_for_testing_with_config(
     config: &GasStationStorageConfig,
@@ -116,8 +358,12 @@ pub async fn connect_storage_for_testing(sponsor_address: IotaAddress) -> Arc<dy
 
 #[cfg(test)]
 mod tests {
-    use crate::storage::{connect_storage_for_testing, Storage, MAX_GAS_PER_QUERY};
-    use crate::types::GasCoin;
+    use crate::errors::{ErrorCode, GasStationError};
+    use crate::storage::{
+        connect_storage_for_testing, run_with_init_lock, CoinReconciliation, OutboxNotification,
+        Storage, MAX_GAS_PER_QUERY,
+    };
+    use crate::types::{CoinDenomination, GasCoin};
     use iota_types::base_types::{random_object_ref, IotaAddress, ObjectID, SequenceNumber};
     use iota_types::digests::ObjectDigest;
     use rand::random;
@@ -125,6 +371,10 @@ mod tests {
     use std::sync::Arc;
     use std::time::Duration;
 
+    fn object_ids(coins: &[GasCoin]) -> Vec<ObjectID> {
+        coins.iter().map(|coin| coin.object_ref.0).collect()
+    }
+
     async fn assert_coin_count(storage: &Arc<dyn Storage>, available: usize, reserved: usize) {
         assert_eq!(storage.get_available_coin_count().await.unwrap(), available);
         assert_eq!(storage.get_reserved_coin_count().await, reserved);
@@ -167,6 +417,123 @@ mod tests {
         assert!(storage.is_initialized().await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_coin_denominations() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        assert!(storage.get_coin_denominations().await.unwrap().is_empty());
+        let denominations = vec![
+            CoinDenomination {
+                balance: 1000,
+                share: 0.75,
+            },
+            CoinDenomination {
+                balance: 8000,
+                share: 0.25,
+            },
+        ];
+        storage
+            .set_coin_denominations(&denominations)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_coin_denominations().await.unwrap(),
+            denominations
+        );
+    }
+
+    #[tokio::test]
+    async fn test_budget_samples() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        assert!(storage.get_budget_samples().await.unwrap().is_empty());
+        storage
+            .add_budget_samples(&[(2000, 3), (1000, 1)])
+            .await
+            .unwrap();
+        storage
+            .add_budget_samples(&[(1000, 2), (u64::MAX, 1)])
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_budget_samples().await.unwrap(),
+            vec![(1000, 3), (2000, 3), (u64::MAX, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notification_outbox() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = connect_storage_for_testing(sponsor).await;
+        let first = storage.add_notification("first", 0).await.unwrap();
+        let second = storage.add_notification("second", 0).await.unwrap();
+        let pending = storage.add_notification("pending", 60_000).await.unwrap();
+        assert_eq!(storage.get_notification_count().await.unwrap(), 3);
+
+        let claimed = storage.claim_notifications(10, 100).await.unwrap();
+        assert_eq!(
+            claimed,
+            vec![
+                OutboxNotification {
+                    id: first,
+                    attempts: 1,
+                    content: "first".to_string(),
+                },
+                OutboxNotification {
+                    id: second,
+                    attempts: 1,
+                    content: "second".to_string(),
+                },
+            ]
+        );
+        // Claimed notifications are not due until their lease expires.
+        assert!(storage
+            .claim_notifications(10, 100)
+            .await
+            .unwrap()
+            .is_empty());
+
+        storage.complete_notification(first).await.unwrap();
+        storage.reschedule_notification(second, 0).await.unwrap();
+        let claimed = storage.claim_notifications(10, 100).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!((claimed[0].id, claimed[0].attempts), (second, 2));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let claimed = storage.claim_notifications(10, 100).await.unwrap();
+        assert_eq!((claimed[0].id, claimed[0].attempts), (second, 3));
+        storage.complete_notification(second).await.unwrap();
+        assert!(!storage.update_notification(second, "gone").await.unwrap());
+
+        // Delayed notifications are due once they are updated.
+        assert!(storage
+            .update_notification(pending, "resolved")
+            .await
+            .unwrap());
+        let claimed = storage.claim_notifications(10, 100).await.unwrap();
+        assert_eq!(
+            claimed,
+            vec![OutboxNotification {
+                id: pending,
+                attempts: 1,
+                content: "resolved".to_string(),
+            }]
+        );
+        storage.complete_notification(pending).await.unwrap();
+        assert_eq!(storage.get_notification_count().await.unwrap(), 0);
+
+        // Dead letters are no longer due.
+        let dead = storage.add_notification("dead", 0).await.unwrap();
+        storage.dead_letter_notification(dead).await.unwrap();
+        assert_eq!(storage.get_notification_count().await.unwrap(), 0);
+        assert_eq!(storage.get_dead_notification_count().await.unwrap(), 1);
+        assert!(storage
+            .claim_notifications(10, 100)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_successful_reservation() {
         // Create a Gas Station of 100000 coins, each with balance of 1.
@@ -215,8 +582,14 @@ mod tests {
             let (res_id, reserved_gas_coins) = storage.reserve_gas_coins(99, 1000).await.unwrap();
             assert_eq!(reserved_gas_coins.len(), 99);
             assert_coin_count(&storage, 1, 99).await;
-            storage.ready_for_execution(res_id).await.unwrap();
-            storage.add_new_coins(reserved_gas_coins).await.unwrap();
+            storage
+                .ready_for_execution(res_id, &object_ids(&reserved_gas_coins), 1000)
+                .await
+                .unwrap();
+            assert!(storage
+                .complete_execution(res_id, reserved_gas_coins)
+                .await
+                .unwrap());
             assert_coin_count(&storage, 100, 0).await;
         }
     }
@@ -237,8 +610,14 @@ mod tests {
                     reserved_gas_coin.balance -= 1;
                 }
             }
-            storage.ready_for_execution(res_id).await.unwrap();
-            storage.add_new_coins(reserved_gas_coins).await.unwrap();
+            storage
+                .ready_for_execution(res_id, &object_ids(&reserved_gas_coins), 1000)
+                .await
+                .unwrap();
+            assert!(storage
+                .complete_execution(res_id, reserved_gas_coins)
+                .await
+                .unwrap());
         }
         assert_coin_count(&storage, 100, 0).await;
         assert_eq!(storage.get_available_coin_total_balance().await, 0);
@@ -252,13 +631,114 @@ mod tests {
         let (res_id, mut reserved_gas_coins) = storage.reserve_gas_coins(100, 1000).await.unwrap();
         assert_eq!(reserved_gas_coins.len(), 100);
 
-        storage.ready_for_execution(res_id).await.unwrap();
+        storage
+            .ready_for_execution(res_id, &object_ids(&reserved_gas_coins), 1000)
+            .await
+            .unwrap();
 
         reserved_gas_coins.drain(0..50);
-        storage.add_new_coins(reserved_gas_coins).await.unwrap();
+        assert!(storage
+            .complete_execution(res_id, reserved_gas_coins)
+            .await
+            .unwrap());
         assert_coin_count(&storage, 50, 0).await;
     }
 
+    #[tokio::test]
+    async fn test_ready_for_execution_checks_payment() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![1; 100]).await;
+        let (res_id1, reserved_gas_coins1) = storage.reserve_gas_coins(3, 1000).await.unwrap();
+        let (_res_id2, reserved_gas_coins2) = storage.reserve_gas_coins(1, 1000).await.unwrap();
+
+        // Incomplete payment.
+        let mut payment = object_ids(&reserved_gas_coins1);
+        payment.pop();
+        let err = storage
+            .ready_for_execution(res_id1, &payment, 1000)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            GasStationError::from(err).code(),
+            ErrorCode::PaymentMismatch
+        );
+
+        // Payment mixed up with coins from another reservation.
+        payment.push(reserved_gas_coins2[0].object_ref.0);
+        let err = storage
+            .ready_for_execution(res_id1, &payment, 1000)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            GasStationError::from(err).code(),
+            ErrorCode::PaymentMismatch
+        );
+
+        // The reservation is still there after a mismatch.
+        storage
+            .ready_for_execution(res_id1, &object_ids(&reserved_gas_coins1), 1000)
+            .await
+            .unwrap();
+        let err = storage
+            .ready_for_execution(res_id1, &object_ids(&reserved_gas_coins1), 1000)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            GasStationError::from(err).code(),
+            ErrorCode::ReservationExpired
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drain_pool() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![1; 10]).await;
+        let (res_id, reserved_gas_coins) = storage.reserve_gas_coins(2, 1000).await.unwrap();
+        assert_eq!(storage.get_reservation_count().await.unwrap(), 1);
+
+        storage.set_draining(true).await.unwrap();
+        assert!(storage.is_draining().await.unwrap());
+        let err = storage.reserve_gas_coins(1, 1000).await.unwrap_err();
+        assert_eq!(GasStationError::from(err).code(), ErrorCode::Draining);
+        assert_coin_count(&storage, 8, 2).await;
+
+        // Outstanding reservations can still be executed.
+        storage
+            .ready_for_execution(res_id, &object_ids(&reserved_gas_coins), 1000)
+            .await
+            .unwrap();
+        storage
+            .complete_execution(res_id, reserved_gas_coins)
+            .await
+            .unwrap();
+        assert_eq!(storage.get_reservation_count().await.unwrap(), 0);
+
+        storage.mark_uninitialized().await.unwrap();
+        assert!(!storage.is_initialized().await.unwrap());
+        assert_coin_count(&storage, 0, 0).await;
+        assert_eq!(storage.get_available_coin_total_balance().await, 0);
+
+        storage.set_draining(false).await.unwrap();
+        assert!(!storage.is_draining().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_sponsor_data() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![1; 10]).await;
+        let other_storage = setup(IotaAddress::random_for_testing_only(), vec![1; 10]).await;
+        storage.reserve_gas_coins(2, 1000).await.unwrap();
+        storage.set_draining(true).await.unwrap();
+
+        storage.delete_sponsor_data().await.unwrap();
+        assert!(!storage.is_initialized().await.unwrap());
+        assert!(!storage.is_draining().await.unwrap());
+        assert_eq!(storage.get_reservation_count().await.unwrap(), 0);
+        assert_eq!(storage.get_reserved_coin_count().await, 0);
+        // The data of other sponsors is kept.
+        assert_coin_count(&other_storage, 10, 0).await;
+    }
+
     #[tokio::test]
     async fn test_coin_expiration() {
         let sponsor = IotaAddress::random_for_testing_only();
@@ -304,6 +784,131 @@ mod tests {
         assert_coin_count(&storage, 10, 0).await;
     }
 
+    #[tokio::test]
+    async fn test_execution_lease_expiration() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![1; 100]).await;
+        let (res_id1, reserved_gas_coins1) = storage.reserve_gas_coins(10, 1000).await.unwrap();
+        let (res_id2, reserved_gas_coins2) = storage.reserve_gas_coins(20, 1000).await.unwrap();
+        storage
+            .ready_for_execution(res_id1, &object_ids(&reserved_gas_coins1), 900)
+            .await
+            .unwrap();
+        storage
+            .ready_for_execution(res_id2, &object_ids(&reserved_gas_coins2), 60_000)
+            .await
+            .unwrap();
+        // Coins under an execution lease are neither expired as reservations nor available.
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(storage.expire_coins().await.unwrap().is_empty());
+        assert_coin_count(&storage, 70, 30).await;
+
+        let recovered = storage.expire_execution_leases().await.unwrap();
+        assert_eq!(
+            recovered.into_iter().collect::<BTreeSet<_>>(),
+            object_ids(&reserved_gas_coins1)
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+        );
+        assert!(storage.expire_execution_leases().await.unwrap().is_empty());
+        assert_coin_count(&storage, 70, 20).await;
+
+        // The recovered coins are not added again when the execution completes late.
+        assert!(!storage
+            .complete_execution(res_id1, reserved_gas_coins1)
+            .await
+            .unwrap());
+        assert_coin_count(&storage, 70, 20).await;
+        assert!(storage
+            .complete_execution(res_id2, reserved_gas_coins2)
+            .await
+            .unwrap());
+        assert_coin_count(&storage, 90, 0).await;
+    }
+
+    #[tokio::test]
+    async fn test_reserve_dust_coins() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![1, 100, 2, 100, 3, 4]).await;
+        let (_, coins) = storage.reserve_dust_coins(10, 5, 10, 1000).await.unwrap();
+        assert!(coins.is_empty());
+        assert_coin_count(&storage, 6, 0).await;
+
+        let (_, coins) = storage.reserve_dust_coins(10, 2, 3, 1000).await.unwrap();
+        assert_eq!(
+            coins.iter().map(|coin| coin.balance).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_coin_count(&storage, 3, 3).await;
+        assert_eq!(storage.get_available_coin_total_balance().await, 204);
+
+        // Dust coins are expired like any other reservation.
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(storage.expire_coins().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_coins() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![]).await;
+        let coin = |balance: u64, version: u64| GasCoin {
+            object_ref: (
+                ObjectID::random(),
+                SequenceNumber::from_u64(version),
+                ObjectDigest::random(),
+            ),
+            balance,
+        };
+        let stale = coin(5, 1);
+        let gone = coin(5, 1);
+        let unchanged = coin(5, 1);
+        let reserved = coin(100, 1);
+        storage.add_new_coins(vec![reserved.clone()]).await.unwrap();
+        let (_, reserved_coins) = storage.reserve_gas_coins(100, 60_000).await.unwrap();
+        assert_eq!(reserved_coins, vec![reserved.clone()]);
+        // Identical coins are only kept once, so the duplicate has a different digest.
+        let mut duplicate = unchanged.clone();
+        duplicate.object_ref.2 = ObjectDigest::random();
+        storage
+            .add_new_coins(vec![stale.clone(), gone, unchanged.clone(), duplicate])
+            .await
+            .unwrap();
+
+        let mut updated = stale.clone();
+        updated.object_ref.1 = SequenceNumber::from_u64(2);
+        updated.balance = 3;
+        let untracked = coin(7, 1);
+        let too_large = coin(1000, 1);
+        let owned_coins = vec![updated, unchanged, reserved, untracked, too_large];
+        let expected = CoinReconciliation {
+            updated: 1,
+            removed: 1,
+            adopted: 1,
+            duplicates: 1,
+        };
+
+        let report = storage
+            .reconcile_coins(owned_coins.clone(), 1000, true)
+            .await
+            .unwrap();
+        assert_eq!(report, expected);
+        assert_coin_count(&storage, 4, 1).await;
+
+        let report = storage
+            .reconcile_coins(owned_coins.clone(), 1000, false)
+            .await
+            .unwrap();
+        assert_eq!(report, expected);
+        assert_coin_count(&storage, 3, 1).await;
+        assert_eq!(storage.get_available_coin_total_balance().await, 3 + 5 + 7);
+
+        let report = storage
+            .reconcile_coins(owned_coins, 1000, false)
+            .await
+            .unwrap();
+        assert!(report.is_empty());
+    }
+
     #[tokio::test]
     async fn test_multiple_sponsors() {
         let sponsors = (0..10)
@@ -358,6 +963,38 @@ mod tests {
         assert!(storage.acquire_init_lock(5).await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_renew_init_lock() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![1; 100]).await;
+        assert!(!storage.renew_init_lock(5).await.unwrap());
+        assert!(storage.acquire_init_lock(2).await.unwrap());
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(storage.renew_init_lock(5).await.unwrap());
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(!storage.acquire_init_lock(5).await.unwrap());
+        storage.release_init_lock().await.unwrap();
+        assert!(!storage.renew_init_lock(5).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_run_with_init_lock() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = setup(sponsor, vec![1; 100]).await;
+        assert!(storage.acquire_init_lock(3).await.unwrap());
+        let task = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            anyhow::Ok(1)
+        };
+        assert_eq!(run_with_init_lock(&storage, 3, task).await.unwrap(), 1);
+        assert!(!storage.acquire_init_lock(3).await.unwrap());
+
+        // A task that outlives the lock is aborted.
+        storage.release_init_lock().await.unwrap();
+        let task = std::future::pending::<anyhow::Result<()>>();
+        assert!(run_with_init_lock(&storage, 3, task).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_init_coin_stats_idempotent() {
         let sponsor = IotaAddress::random_for_testing_only();
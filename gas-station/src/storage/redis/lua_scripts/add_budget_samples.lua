@@ -0,0 +1,13 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to add the gas budgets observed by a gas station instance to the shared budget statistics.
+-- The first argument is the sponsor's address.
+-- The following arguments are pairs of the upper bound of a budget bucket and the number of budgets to add to it.
+
+local sponsor_address = ARGV[1]
+
+local t_budget_samples = sponsor_address .. ':budget_samples'
+for i = 2, #ARGV, 2 do
+    redis.call('HINCRBY', t_budget_samples, ARGV[i], ARGV[i + 1])
+end
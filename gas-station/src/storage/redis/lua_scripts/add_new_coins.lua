@@ -6,7 +6,7 @@
 -- The second argument is a JSON array of new coins.
 -- Each coin is just a string, using "," to separate these fields:
 --   balance, object id, object version, object digest.
--- In this script we don't care about the format, just push each to the queue.
+-- Each coin is added to the available gas coins sorted set with its balance as score.
 -- We also set the initialized flag to 1 if we added any coins.
 -- Returns a table with the new total balance and new coin count.
 
@@ -19,13 +19,17 @@ local decoded_new_coins = cjson.decode(new_coins)
 local count = #decoded_new_coins
 
 local total_balance = 0
+local added_count = 0
 for i = 1, count, 1 do
     local coin = decoded_new_coins[i]
     local idx1, _ = string.find(coin, ',', 1)
     local balance = string.sub(coin, 1, idx1 - 1)
-    total_balance = total_balance + tonumber(balance)
 
-    redis.call('RPUSH', t_available_gas_coins, coin)
+    -- A coin that is already available is not added again.
+    if redis.call('ZADD', t_available_gas_coins, balance, coin) == 1 then
+        total_balance = total_balance + tonumber(balance)
+        added_count = added_count + 1
+    end
 end
 
 if count > 0 then
@@ -41,7 +45,7 @@ redis.call('SET', t_available_coin_total_balance, new_total_balance)
 
 local t_available_coin_count = sponsor_address .. ':available_coin_count'
 local cur_coin_count = redis.call('GET', t_available_coin_count)
-local new_coin_count = cur_coin_count + count
+local new_coin_count = cur_coin_count + added_count
 redis.call('SET', t_available_coin_count, new_coin_count)
 
 return {new_total_balance, new_coin_count}
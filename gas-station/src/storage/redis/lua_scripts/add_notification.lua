@@ -0,0 +1,22 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to add a notification to the outbox of the sponsor, from where it is delivered.
+-- The first argument is the sponsor's address.
+-- The second argument is the content of the notification.
+-- The third argument is the time in milliseconds from which on the notification is due.
+-- Returns the id of the notification.
+
+local sponsor_address = ARGV[1]
+local content = ARGV[2]
+local due_time = tonumber(ARGV[3])
+
+local t_next_notification_id = sponsor_address .. ':next_notification_id'
+local t_notifications = sponsor_address .. ':notifications'
+local t_notification_queue = sponsor_address .. ':notification_queue'
+
+local id = redis.call('INCR', t_next_notification_id)
+redis.call('HSET', t_notifications, id, content)
+redis.call('ZADD', t_notification_queue, due_time, id)
+
+return id
@@ -0,0 +1,25 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to get the version of the format the data of a sponsor address is stored in.
+-- The first argument is the sponsor's address.
+-- The second argument is the storage version of the running gas station, which is recorded if there is no data yet.
+-- Returns the storage version of the data. Data of versions that didn't record it is reported as version 1.
+
+local sponsor_address = ARGV[1]
+local current_version = tonumber(ARGV[2])
+
+local t_storage_version = sponsor_address .. ':storage_version'
+local version = redis.call('GET', t_storage_version)
+if version then
+    return tonumber(version)
+end
+
+-- Older versions kept the available gas coins in a list, which no longer exists once it is empty.
+local pool_type = redis.call('TYPE', sponsor_address .. ':available_gas_coins').ok
+if pool_type == 'list' or (pool_type == 'none' and redis.call('EXISTS', sponsor_address .. ':initialized') == 1) then
+    return 1
+end
+
+redis.call('SET', t_storage_version, current_version)
+return current_version
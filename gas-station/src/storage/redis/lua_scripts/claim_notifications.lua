@@ -0,0 +1,37 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to claim the notifications in the outbox that are due, so that they can be delivered.
+-- A claimed notification is due again once its lease expires, unless it is completed or rescheduled before.
+-- The first argument is the sponsor's address.
+-- The second argument is the current time in milliseconds.
+-- The third argument is the time in milliseconds when the lease of the claimed notifications expires.
+-- The fourth argument is the maximum number of notifications to claim.
+-- Returns a flat list with the id, the number of delivery attempts including this one and the content of each
+-- claimed notification.
+
+local sponsor_address = ARGV[1]
+local current_time = tonumber(ARGV[2])
+local lease_expiration_time = tonumber(ARGV[3])
+local max_count = tonumber(ARGV[4])
+
+local t_notifications = sponsor_address .. ':notifications'
+local t_notification_attempts = sponsor_address .. ':notification_attempts'
+local t_notification_queue = sponsor_address .. ':notification_queue'
+
+local ids = redis.call('ZRANGEBYSCORE', t_notification_queue, '-inf', current_time, 'LIMIT', 0, max_count)
+local claimed = {}
+for _, id in ipairs(ids) do
+    local content = redis.call('HGET', t_notifications, id)
+    if content then
+        redis.call('ZADD', t_notification_queue, lease_expiration_time, id)
+        local attempts = redis.call('HINCRBY', t_notification_attempts, id, 1)
+        table.insert(claimed, id)
+        table.insert(claimed, tostring(attempts))
+        table.insert(claimed, content)
+    else
+        redis.call('ZREM', t_notification_queue, id)
+    end
+end
+
+return claimed
@@ -0,0 +1,55 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to release the gas coins of a transaction after its execution.
+-- It removes the execution lease created by ready_for_execution.lua and adds the updated coins to the
+-- available gas coins queue, in one step so that the coins are never lost nor added twice.
+-- The first argument is the sponsor's address.
+-- The second argument is the reservation id.
+-- The third argument is a JSON array of the updated coins, in the same format as in add_new_coins.lua.
+-- Returns a table with the new total balance, new coin count and whether the lease still existed.
+-- If the lease no longer exists, the coins were already recovered and are not added again.
+
+local sponsor_address = ARGV[1]
+local reservation_id = ARGV[2]
+local updated_coins = ARGV[3]
+
+local t_execution_leases = sponsor_address .. ':execution_leases'
+local t_available_gas_coins = sponsor_address .. ':available_gas_coins'
+local t_available_coin_total_balance = sponsor_address .. ':available_coin_total_balance'
+local t_available_coin_count = sponsor_address .. ':available_coin_count'
+
+local lease_key = sponsor_address .. ':lease:' .. reservation_id
+local lease_existed = redis.call('DEL', lease_key)
+redis.call('ZREM', t_execution_leases, reservation_id)
+if lease_existed == 0 then
+    local cur_coin_total_balance = redis.call('GET', t_available_coin_total_balance)
+    local cur_coin_count = redis.call('GET', t_available_coin_count)
+    return {tonumber(cur_coin_total_balance), tonumber(cur_coin_count), 0}
+end
+
+local decoded_coins = cjson.decode(updated_coins)
+local count = #decoded_coins
+
+local total_balance = 0
+local added_count = 0
+for i = 1, count, 1 do
+    local coin = decoded_coins[i]
+    local idx1, _ = string.find(coin, ',', 1)
+    local balance = string.sub(coin, 1, idx1 - 1)
+
+    if redis.call('ZADD', t_available_gas_coins, balance, coin) == 1 then
+        total_balance = total_balance + tonumber(balance)
+        added_count = added_count + 1
+    end
+end
+
+local cur_coin_total_balance = redis.call('GET', t_available_coin_total_balance)
+local new_total_balance = cur_coin_total_balance + total_balance
+redis.call('SET', t_available_coin_total_balance, new_total_balance)
+
+local cur_coin_count = redis.call('GET', t_available_coin_count)
+local new_coin_count = cur_coin_count + added_count
+redis.call('SET', t_available_coin_count, new_coin_count)
+
+return {new_total_balance, new_coin_count, 1}
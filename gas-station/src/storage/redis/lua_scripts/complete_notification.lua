@@ -0,0 +1,17 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to remove a notification from the outbox once it is delivered.
+-- The first argument is the sponsor's address.
+-- The second argument is the id of the notification.
+
+local sponsor_address = ARGV[1]
+local id = ARGV[2]
+
+local t_notifications = sponsor_address .. ':notifications'
+local t_notification_attempts = sponsor_address .. ':notification_attempts'
+local t_notification_queue = sponsor_address .. ':notification_queue'
+
+redis.call('ZREM', t_notification_queue, id)
+redis.call('HDEL', t_notifications, id)
+redis.call('HDEL', t_notification_attempts, id)
@@ -0,0 +1,23 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to move a notification that can't be delivered from the outbox to the dead letters of the sponsor,
+-- where it is kept for inspection.
+-- The first argument is the sponsor's address.
+-- The second argument is the id of the notification.
+
+local sponsor_address = ARGV[1]
+local id = ARGV[2]
+
+local t_notifications = sponsor_address .. ':notifications'
+local t_notification_attempts = sponsor_address .. ':notification_attempts'
+local t_notification_queue = sponsor_address .. ':notification_queue'
+local t_dead_notifications = sponsor_address .. ':dead_notifications'
+
+local content = redis.call('HGET', t_notifications, id)
+if content then
+    redis.call('HSET', t_dead_notifications, id, content)
+end
+redis.call('ZREM', t_notification_queue, id)
+redis.call('HDEL', t_notifications, id)
+redis.call('HDEL', t_notification_attempts, id)
@@ -0,0 +1,35 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to delete all data of a sponsor address, once its pool was drained and it is no longer used.
+-- This includes the reservations and execution leases that are still recorded.
+-- The first argument is the sponsor's address.
+
+local sponsor_address = ARGV[1]
+
+local t_expiration_queue = sponsor_address .. ':expiration_queue'
+local t_execution_leases = sponsor_address .. ':execution_leases'
+
+for _, reservation_id in ipairs(redis.call('ZRANGE', t_expiration_queue, 0, -1)) do
+    redis.call('DEL', sponsor_address .. ':' .. reservation_id)
+end
+for _, reservation_id in ipairs(redis.call('ZRANGE', t_execution_leases, 0, -1)) do
+    redis.call('DEL', sponsor_address .. ':lease:' .. reservation_id)
+end
+
+for _, key in ipairs({
+    'available_gas_coins',
+    'available_coin_count',
+    'available_coin_total_balance',
+    'budget_samples',
+    'coin_denominations',
+    'draining',
+    'execution_leases',
+    'expiration_queue',
+    'init_lock',
+    'initialized',
+    'next_reservation_id',
+    'storage_version',
+}) do
+    redis.call('DEL', sponsor_address .. ':' .. key)
+end
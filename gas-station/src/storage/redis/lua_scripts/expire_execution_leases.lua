@@ -0,0 +1,30 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to recover gas coins of transactions whose execution lease has expired.
+-- This happens when the gas station stopped between ready_for_execution.lua and complete_execution.lua,
+-- e.g. because it crashed. It takes out all expired leases and returns their coins to the caller.
+-- The first argument is the sponsor's address.
+-- The second argument is the current timestamp.
+
+local sponsor_address = ARGV[1]
+local current_time = tonumber(ARGV[2])
+
+local t_execution_leases = sponsor_address .. ':execution_leases'
+
+local elements = redis.call('ZRANGEBYSCORE', t_execution_leases, 0, current_time)
+
+local expired_leases = {}
+if #elements > 0 then
+    for _, reservation_id in ipairs(elements) do
+        local lease_key = sponsor_address .. ':lease:' .. reservation_id
+        local object_ids = redis.call('GET', lease_key)
+        if object_ids then
+            redis.call('DEL', lease_key)
+            table.insert(expired_leases, object_ids)
+        end
+    end
+    redis.call('ZREMRANGEBYSCORE', t_execution_leases, 0, current_time)
+end
+
+return expired_leases
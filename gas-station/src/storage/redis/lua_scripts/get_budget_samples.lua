@@ -0,0 +1,11 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to get the gas budgets observed by all gas station instances.
+-- The first argument is the sponsor's address.
+-- Returns a flat list of pairs of the upper bound of a budget bucket and the number of budgets in it.
+
+local sponsor_address = ARGV[1]
+
+local t_budget_samples = sponsor_address .. ':budget_samples'
+return redis.call('HGETALL', t_budget_samples)
@@ -0,0 +1,15 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to get the persisted denominations new gas coins are split into.
+-- The first argument is the sponsor's address.
+-- Returns the JSON encoded list of denominations, or an empty list if none were persisted.
+
+local sponsor_address = ARGV[1]
+
+local t_coin_denominations = sponsor_address .. ':coin_denominations'
+local denominations = redis.call('GET', t_coin_denominations)
+if not denominations then
+    return '[]'
+end
+return denominations
@@ -0,0 +1,11 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to get the number of dead letters of the sponsor, i.e. notifications that could not be delivered.
+-- The first argument is the sponsor's address.
+
+local sponsor_address = ARGV[1]
+
+local t_dead_notifications = sponsor_address .. ':dead_notifications'
+
+return redis.call('HLEN', t_dead_notifications)
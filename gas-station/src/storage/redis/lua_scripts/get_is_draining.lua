@@ -0,0 +1,10 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to check if the pool of a sponsor address is draining.
+-- The first argument is the sponsor's address.
+
+local sponsor_address = ARGV[1]
+
+local t_draining = sponsor_address .. ':draining'
+return redis.call('EXISTS', t_draining)
@@ -0,0 +1,11 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to get the number of notifications in the outbox of the sponsor.
+-- The first argument is the sponsor's address.
+
+local sponsor_address = ARGV[1]
+
+local t_notification_queue = sponsor_address .. ':notification_queue'
+
+return redis.call('ZCARD', t_notification_queue)
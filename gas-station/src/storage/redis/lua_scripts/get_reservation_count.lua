@@ -0,0 +1,19 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to get the number of outstanding reservations for a sponsor address.
+-- This includes the reservations whose transactions are being executed.
+-- The expiration queue keeps the reservations that are ready for execution until they expire, so only the
+-- reservations that still hold their coins are counted.
+-- The first argument is the sponsor's address.
+
+local sponsor_address = ARGV[1]
+
+local t_expiration_queue = sponsor_address .. ':expiration_queue'
+local t_execution_leases = sponsor_address .. ':execution_leases'
+
+local count = redis.call('ZCARD', t_execution_leases)
+for _, reservation_id in ipairs(redis.call('ZRANGE', t_expiration_queue, 0, -1)) do
+    count = count + redis.call('EXISTS', sponsor_address .. ':' .. reservation_id)
+end
+return count
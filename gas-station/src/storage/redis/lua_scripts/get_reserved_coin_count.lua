@@ -2,18 +2,17 @@
 -- SPDX-License-Identifier: Apache-2.0
 
 -- This script is used to get the number of reserved gas coins for a sponsor address.
+-- This includes the coins of transactions that are being executed.
 -- It is used for debugging purpose only.
 -- The first argument is the sponsor's address.
 
 local sponsor_address = ARGV[1]
 
 local t_expiration_queue = sponsor_address .. ':expiration_queue'
-
-local elements = redis.call('ZRANGE', t_expiration_queue, 0, -1)
+local t_execution_leases = sponsor_address .. ':execution_leases'
 
 local total = 0
-for _, reservation_id in ipairs(elements) do
-    local key = sponsor_address .. ':' .. reservation_id
+local function count_coins(key)
     local object_ids = redis.call('GET', key)
     if object_ids then
         -- The object IDs are concatenated with commas. gsub will replace all commas with empty strings and
@@ -23,4 +22,11 @@ for _, reservation_id in ipairs(elements) do
     end
 end
 
+for _, reservation_id in ipairs(redis.call('ZRANGE', t_expiration_queue, 0, -1)) do
+    count_coins(sponsor_address .. ':' .. reservation_id)
+end
+for _, reservation_id in ipairs(redis.call('ZRANGE', t_execution_leases, 0, -1)) do
+    count_coins(sponsor_address .. ':lease:' .. reservation_id)
+end
+
 return total
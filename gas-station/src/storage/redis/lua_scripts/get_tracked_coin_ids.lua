@@ -0,0 +1,30 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to get the object ids of the coins that are reserved or under an execution lease.
+-- These coins are not available, but they are tracked by the pool.
+-- The first argument is the sponsor's address.
+-- Returns a list of object ids.
+
+local sponsor_address = ARGV[1]
+
+local t_expiration_queue = sponsor_address .. ':expiration_queue'
+local t_execution_leases = sponsor_address .. ':execution_leases'
+
+local tracked = {}
+local function track(key)
+    local object_ids = redis.call('GET', key)
+    if object_ids then
+        for object_id in string.gmatch(object_ids, '[^,]+') do
+            table.insert(tracked, object_id)
+        end
+    end
+end
+for _, reservation_id in ipairs(redis.call('ZRANGE', t_expiration_queue, 0, -1)) do
+    track(sponsor_address .. ':' .. reservation_id)
+end
+for _, reservation_id in ipairs(redis.call('ZRANGE', t_execution_leases, 0, -1)) do
+    track(sponsor_address .. ':lease:' .. reservation_id)
+end
+
+return tracked
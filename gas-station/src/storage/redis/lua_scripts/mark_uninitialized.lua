@@ -0,0 +1,14 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to mark the Gas Station of a sponsor address as uninitialized once its pool was drained.
+-- The available gas coins are dropped and the coin statistics are reset, so that the next start
+-- initializes the pool from the coins owned on chain.
+-- The first argument is the sponsor's address.
+
+local sponsor_address = ARGV[1]
+
+redis.call('DEL', sponsor_address .. ':available_gas_coins')
+redis.call('SET', sponsor_address .. ':available_coin_count', 0)
+redis.call('SET', sponsor_address .. ':available_coin_total_balance', 0)
+redis.call('DEL', sponsor_address .. ':initialized')
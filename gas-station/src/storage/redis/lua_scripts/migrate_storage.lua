@@ -0,0 +1,27 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to convert the data of a sponsor address written by older versions to the current format.
+-- It must only run once no gas station instance of an older version uses the data anymore.
+-- The first argument is the sponsor's address.
+-- The second argument is the storage version of the running gas station.
+
+local sponsor_address = ARGV[1]
+local current_version = ARGV[2]
+
+local t_available_gas_coins = sponsor_address .. ':available_gas_coins'
+
+-- The available gas coins used to be stored in a list. Move them into the sorted set ordered by balance.
+if redis.call('TYPE', t_available_gas_coins).ok == 'list' then
+    local elements = redis.call('LRANGE', t_available_gas_coins, 0, -1)
+    redis.call('DEL', t_available_gas_coins)
+    for _, coin in ipairs(elements) do
+        local balance = string.match(coin, '^([^,]+),')
+        redis.call('ZADD', t_available_gas_coins, balance, coin)
+    end
+    -- Recomputed at startup, identical coins are only kept once.
+    redis.call('DEL', sponsor_address .. ':available_coin_count')
+    redis.call('DEL', sponsor_address .. ':available_coin_total_balance')
+end
+
+redis.call('SET', sponsor_address .. ':storage_version', current_version)
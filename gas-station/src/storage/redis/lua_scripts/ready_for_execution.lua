@@ -5,16 +5,48 @@
 -- It takes out the reservation from the sponsor's reservation map.
 -- We need this such that a concurrent task that calls expire_coins.lua does not expire the same reservation again
 -- right before the transaction is executed.
+-- The reservation is only taken out if the gas payment of the transaction consists of exactly the reserved coins.
+-- The coins are then put under an execution lease, so that they can be recovered by expire_execution_leases.lua
+-- if the gas station crashes before releasing them.
 -- The first argument is the sponsor's address.
 -- The second argument is the reservation id.
+-- The third argument is a JSON array of the object ids used as gas payment.
+-- The fourth argument is the timestamp at which the execution lease expires.
+-- Returns 1 on success, 0 if the reservation no longer exists and -1 if the payment does not match.
 
 local sponsor_address = ARGV[1]
 local reservation_id = ARGV[2]
+local payment = cjson.decode(ARGV[3])
+local lease_expiration_time = tonumber(ARGV[4])
 
 local key = sponsor_address .. ':' .. reservation_id
-local exists = redis.call('EXISTS', key)
-if exists == 1 then
-    redis.call('DEL', key)
-else
-    error('Reservation no longer exist: ' .. reservation_id)
-end
\ No newline at end of file
+local object_ids = redis.call('GET', key)
+if not object_ids then
+    return 0
+end
+
+local reserved = {}
+local reserved_count = 0
+for object_id in string.gmatch(object_ids, '[^,]+') do
+    reserved[object_id] = true
+    reserved_count = reserved_count + 1
+end
+
+if #payment ~= reserved_count then
+    return -1
+end
+for _, object_id in ipairs(payment) do
+    if not reserved[object_id] then
+        return -1
+    end
+    -- Each reserved coin can only be used once.
+    reserved[object_id] = nil
+end
+
+redis.call('DEL', key)
+
+local t_execution_leases = sponsor_address .. ':execution_leases'
+local lease_key = sponsor_address .. ':lease:' .. reservation_id
+redis.call('SET', lease_key, object_ids)
+redis.call('ZADD', t_execution_leases, lease_expiration_time, reservation_id)
+return 1
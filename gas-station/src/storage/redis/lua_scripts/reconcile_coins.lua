@@ -0,0 +1,107 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to apply one batch of the differences between the available gas coins and the coins the
+-- sponsor owns on chain. Each change is skipped if a concurrent request changed the coin in the meantime.
+-- The first argument is the sponsor's address.
+-- The second argument is a JSON array of available coins to drop.
+-- The third argument is a JSON array of available coins to drop if they are reserved or under an execution lease.
+-- The fourth argument is a JSON array of available coins followed by their up-to-date state, which replaces them.
+-- The fifth argument is a JSON array of owned coins to add if they are neither reserved nor under an execution lease,
+-- nor available in any version.
+-- All coins are in the same format as in add_new_coins.lua.
+-- Returns a table with the new total balance and coin count.
+
+local sponsor_address = ARGV[1]
+local dropped_coins = cjson.decode(ARGV[2])
+local tracked_coins = cjson.decode(ARGV[3])
+local updated_coins = cjson.decode(ARGV[4])
+local adopted_coins = cjson.decode(ARGV[5])
+
+local t_available_gas_coins = sponsor_address .. ':available_gas_coins'
+local t_expiration_queue = sponsor_address .. ':expiration_queue'
+local t_execution_leases = sponsor_address .. ':execution_leases'
+local t_available_coin_total_balance = sponsor_address .. ':available_coin_total_balance'
+local t_available_coin_count = sponsor_address .. ':available_coin_count'
+
+-- Each coin is in the form of: balance,object_id,version,digest
+local function parse_coin(coin)
+    local balance, object_id = string.match(coin, '^([^,]+),([^,]+),')
+    return tonumber(balance), object_id
+end
+
+local total_balance_delta = 0
+local count_delta = 0
+local function remove(coin)
+    if redis.call('ZREM', t_available_gas_coins, coin) == 1 then
+        local balance, _ = parse_coin(coin)
+        total_balance_delta = total_balance_delta - balance
+        count_delta = count_delta - 1
+        return true
+    end
+    return false
+end
+local function add(coin)
+    local balance, _ = parse_coin(coin)
+    if redis.call('ZADD', t_available_gas_coins, balance, coin) == 1 then
+        total_balance_delta = total_balance_delta + balance
+        count_delta = count_delta + 1
+    end
+end
+
+-- Reserved coins and coins under an execution lease are looked up again, as they may have been released since.
+local tracked = {}
+if #tracked_coins > 0 or #adopted_coins > 0 then
+    local function track(key)
+        local object_ids = redis.call('GET', key)
+        if object_ids then
+            for object_id in string.gmatch(object_ids, '[^,]+') do
+                tracked[object_id] = true
+            end
+        end
+    end
+    for _, reservation_id in ipairs(redis.call('ZRANGE', t_expiration_queue, 0, -1)) do
+        track(sponsor_address .. ':' .. reservation_id)
+    end
+    for _, reservation_id in ipairs(redis.call('ZRANGE', t_execution_leases, 0, -1)) do
+        track(sponsor_address .. ':lease:' .. reservation_id)
+    end
+end
+
+for _, coin in ipairs(dropped_coins) do
+    remove(coin)
+end
+for _, coin in ipairs(tracked_coins) do
+    local _, object_id = parse_coin(coin)
+    if tracked[object_id] then
+        remove(coin)
+    end
+end
+for i = 1, #updated_coins, 2 do
+    if remove(updated_coins[i]) then
+        add(updated_coins[i + 1])
+    end
+end
+-- A coin may have been released with a newer version since the owned coins were queried. Coins are rarely
+-- adopted, so the available coins are only looked up if there are any.
+if #adopted_coins > 0 then
+    local available = {}
+    for _, coin in ipairs(redis.call('ZRANGE', t_available_gas_coins, 0, -1)) do
+        local _, object_id = parse_coin(coin)
+        available[object_id] = true
+    end
+    for _, coin in ipairs(adopted_coins) do
+        local _, object_id = parse_coin(coin)
+        if not tracked[object_id] and not available[object_id] then
+            add(coin)
+        end
+    end
+end
+
+-- TODO: For some reason INCRBY is not working, so we have to do this in two steps.
+local new_total_balance = redis.call('GET', t_available_coin_total_balance) + total_balance_delta
+redis.call('SET', t_available_coin_total_balance, new_total_balance)
+local new_coin_count = redis.call('GET', t_available_coin_count) + count_delta
+redis.call('SET', t_available_coin_count, new_coin_count)
+
+return {new_total_balance, new_coin_count}
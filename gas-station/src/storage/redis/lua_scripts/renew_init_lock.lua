@@ -0,0 +1,24 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- Extends the lock for initializing a sponsor's account, which must be held by the caller.
+-- If the lock has expired or was released, it may have been acquired by someone else, so it is not extended
+-- and the function returns 0. Otherwise it returns 1 and sets the lock's new expiration time.
+-- The first argument is the sponsor's address.
+-- The second argument is the current timestamp.
+-- The third argument is the duration for which the lock should be held from now on. This should be in the
+-- same units as the current timestamp.
+
+local sponsor_address = ARGV[1]
+local current_time = tonumber(ARGV[2])
+local lock_duration = tonumber(ARGV[3])
+
+local t_init_lock = sponsor_address .. ':init_lock'
+local locked_timestamp = redis.call('GET', t_init_lock)
+
+if locked_timestamp == false or tonumber(locked_timestamp) < current_time then
+    return 0
+else
+    redis.call('SET', t_init_lock, current_time + lock_duration)
+    return 1
+end
@@ -0,0 +1,16 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to make a notification in the outbox due again at a later time, after it could not be delivered.
+-- Notifications that are no longer in the outbox are ignored.
+-- The first argument is the sponsor's address.
+-- The second argument is the id of the notification.
+-- The third argument is the time in milliseconds from which on the notification is due.
+
+local sponsor_address = ARGV[1]
+local id = ARGV[2]
+local due_time = tonumber(ARGV[3])
+
+local t_notification_queue = sponsor_address .. ':notification_queue'
+
+redis.call('ZADD', t_notification_queue, 'XX', due_time, id)
@@ -0,0 +1,54 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to reserve low-balance gas coins, so that they can be merged into a larger coin.
+-- It takes the smallest coins with a balance below the threshold out of the available gas coins and reserves them
+-- in the same way as reserve_gas_coins.lua, so that they expire if they are not released.
+-- The first argument is the sponsor's address.
+-- The second argument is the balance below which a coin is reserved.
+-- The third argument is the minimum number of coins to reserve. Nothing is reserved if there are fewer.
+-- The fourth argument is the maximum number of coins to reserve.
+-- The fifth argument is the expiration time of the reservation.
+-- Returns the same as reserve_gas_coins.lua.
+
+local sponsor_address = ARGV[1]
+local min_count = tonumber(ARGV[3])
+local max_count = tonumber(ARGV[4])
+local expiration_time = tonumber(ARGV[5])
+
+local t_available_gas_coins = sponsor_address .. ':available_gas_coins'
+local t_expiration_queue = sponsor_address .. ':expiration_queue'
+local t_next_reservation_id = sponsor_address .. ':next_reservation_id'
+local t_available_coin_total_balance = sponsor_address .. ':available_coin_total_balance'
+local t_available_coin_count = sponsor_address .. ':available_coin_count'
+
+local dust_coins = redis.call('ZRANGEBYSCORE', t_available_gas_coins, '-inf', '(' .. ARGV[2], 'LIMIT', 0, max_count)
+local cur_coin_total_balance = redis.call('GET', t_available_coin_total_balance)
+local cur_coin_count = redis.call('GET', t_available_coin_count)
+if #dust_coins < min_count then
+    return {0, {}, tonumber(cur_coin_total_balance), tonumber(cur_coin_count)}
+end
+
+local total_balance = 0
+local coins = {}
+local object_ids = {}
+for _, coin in ipairs(dust_coins) do
+    local balance, object_id = string.match(coin, '^([^,]+),([^,]+),')
+    redis.call('ZREM', t_available_gas_coins, coin)
+    total_balance = total_balance + tonumber(balance)
+    table.insert(coins, coin)
+    table.insert(object_ids, object_id)
+end
+
+local new_total_balance = cur_coin_total_balance - total_balance
+redis.call('SET', t_available_coin_total_balance, new_total_balance)
+local new_coin_count = cur_coin_count - #coins
+redis.call('SET', t_available_coin_count, new_coin_count)
+
+redis.call('INCR', t_next_reservation_id)
+local reservation_id = redis.call('GET', t_next_reservation_id)
+local key = sponsor_address .. ':' .. reservation_id
+redis.call('SET', key, table.concat(object_ids, ','))
+redis.call('ZADD', t_expiration_queue, expiration_time, reservation_id)
+
+return {reservation_id, coins, new_total_balance, new_coin_count}
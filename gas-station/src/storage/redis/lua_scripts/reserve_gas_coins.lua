@@ -2,18 +2,25 @@
 -- SPDX-License-Identifier: Apache-2.0
 
 -- This script is used to reserve gas coins for a sponsor address.
--- It takes out gas coins from the available_gas_coins list and returns them to the caller.
+-- It takes out gas coins from the available_gas_coins sorted set, which is ordered by balance, and returns them
+-- to the caller.
 -- It also creates a unique reservation id and stores the reserved coins in a separate reservation map.
 -- The reservation id is used to track the reserved coins and to release them back to the available pool if not used.
 -- The reservation id is added to the expiration_queue to track the expiration time of the reserved coins.
 -- The first argument is the sponsor's address.
 -- The second argument is the target budget.
 -- The third argument is the expiration time.
+-- The fourth argument is the coin selection strategy:
+--   best-fit: the smallest coin that covers the remaining budget, otherwise the largest coin, until covered.
+--   largest-first: the largest coins until the budget is covered.
+--   smallest-first: the smallest coins until the budget is covered.
 -- Returns a table with the reservation id, reserved coins, new total balance, and new coin count.
+-- No coins are reserved while the pool is draining.
 
 local sponsor_address = ARGV[1]
 local target_budget = tonumber(ARGV[2])
 local expiration_time = tonumber(ARGV[3])
+local strategy = ARGV[4]
 
 local MAX_GAS_PER_QUERY = 256
 
@@ -21,29 +28,47 @@ local t_available_gas_coins = sponsor_address .. ':available_gas_coins'
 local t_expiration_queue = sponsor_address .. ':expiration_queue'
 local t_next_reservation_id = sponsor_address .. ':next_reservation_id'
 
+if redis.call('EXISTS', sponsor_address .. ':draining') == 1 then
+    return {0, {}, 0, 0}
+end
+
 local total_balance = 0
 local coins = {}
 local object_ids = {}
 
-while total_balance < target_budget and #coins < MAX_GAS_PER_QUERY do
-    local coin = redis.call('LPOP', t_available_gas_coins)
-    if not coin then break end
+local function take(coin)
+    redis.call('ZREM', t_available_gas_coins, coin)
 
-    local idx1, _ = string.find(coin, ',', 1)
-    local balance = string.sub(coin, 1, idx1 - 1)
+    -- Each coin is in the form of: balance,object_id,version,digest
+    local balance, object_id = string.match(coin, '^([^,]+),([^,]+),')
     total_balance = total_balance + tonumber(balance)
 
-    local idx2, _ = string.find(coin, ',', idx1 + 1)
-    local object_id = string.sub(coin, idx1 + 1, idx2 - 1)
-
     table.insert(coins, coin)
     table.insert(object_ids, object_id)
 end
 
+while total_balance < target_budget and #coins < MAX_GAS_PER_QUERY do
+    local candidates
+    if strategy == 'smallest-first' then
+        candidates = redis.call('ZRANGE', t_available_gas_coins, 0, 0)
+    elseif strategy == 'largest-first' then
+        candidates = redis.call('ZREVRANGE', t_available_gas_coins, 0, 0)
+    else
+        local remaining_budget = string.format('%d', target_budget - total_balance)
+        candidates = redis.call('ZRANGEBYSCORE', t_available_gas_coins, remaining_budget, '+inf', 'LIMIT', 0, 1)
+        if #candidates == 0 then
+            candidates = redis.call('ZREVRANGE', t_available_gas_coins, 0, 0)
+        end
+    end
+    if #candidates == 0 then break end
+    take(candidates[1])
+end
+
 if total_balance < target_budget then
-    -- If the threshold is not reached, push the coins back to the front of the queue in the original order.
-    for i = #coins, 1, -1 do
-        redis.call('LPUSH', t_available_gas_coins, coins[i])
+    -- If the threshold is not reached, put the coins back.
+    for _, coin in ipairs(coins) do
+        local balance = string.match(coin, '^([^,]+),')
+        redis.call('ZADD', t_available_gas_coins, balance, coin)
     end
     return {0, {}, 0, 0}
 end
@@ -0,0 +1,12 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to persist the denominations new gas coins are split into.
+-- The first argument is the sponsor's address.
+-- The second argument is the JSON encoded list of denominations.
+
+local sponsor_address = ARGV[1]
+local denominations = ARGV[2]
+
+local t_coin_denominations = sponsor_address .. ':coin_denominations'
+redis.call('SET', t_coin_denominations, denominations)
@@ -0,0 +1,17 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to start or stop draining the pool of a sponsor address.
+-- While the pool is draining, reserve_gas_coins.lua doesn't reserve any coins.
+-- The first argument is the sponsor's address.
+-- The second argument is 1 to start draining, or 0 to stop.
+
+local sponsor_address = ARGV[1]
+local draining = ARGV[2] == '1'
+
+local t_draining = sponsor_address .. ':draining'
+if draining then
+    redis.call('SET', t_draining, 1)
+else
+    redis.call('DEL', t_draining)
+end
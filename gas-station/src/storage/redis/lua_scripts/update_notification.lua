@@ -0,0 +1,28 @@
+-- Copyright (c) 2025 IOTA Stiftung
+-- SPDX-License-Identifier: Apache-2.0
+
+-- This script is used to replace the content of a notification in the outbox and make it due, e.g. once the outcome
+-- of a pending transaction is known. Its delivery attempts are reset.
+-- The first argument is the sponsor's address.
+-- The second argument is the id of the notification.
+-- The third argument is the new content of the notification.
+-- The fourth argument is the time in milliseconds from which on the notification is due.
+-- Returns 1 if the notification was updated, or 0 if it is no longer in the outbox.
+
+local sponsor_address = ARGV[1]
+local id = ARGV[2]
+local content = ARGV[3]
+local due_time = tonumber(ARGV[4])
+
+local t_notifications = sponsor_address .. ':notifications'
+local t_notification_attempts = sponsor_address .. ':notification_attempts'
+local t_notification_queue = sponsor_address .. ':notification_queue'
+
+if redis.call('HEXISTS', t_notifications, id) == 0 then
+    return 0
+end
+redis.call('HSET', t_notifications, id, content)
+redis.call('HDEL', t_notification_attempts, id)
+redis.call('ZADD', t_notification_queue, due_time, id)
+
+return 1
@@ -3,23 +3,27 @@
 
 mod script_manager;
 
+use crate::config::CoinSelectionStrategy;
+use crate::errors::GasStationError;
 use crate::metrics::StorageMetrics;
 use crate::storage::redis::script_manager::ScriptManager;
-use crate::storage::Storage;
-use crate::types::{GasCoin, ReservationID};
+use crate::storage::{CoinReconciliation, OutboxNotification, Storage, STORAGE_VERSION};
+use crate::types::{CoinDenomination, GasCoin, ReservationID};
 use chrono::Utc;
 use iota_types::base_types::{IotaAddress, ObjectDigest, ObjectID, SequenceNumber};
 use redis::aio::ConnectionManager;
+use std::collections::{HashMap, HashSet};
 use std::ops::Add;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 pub struct RedisStorage {
     conn_manager: ConnectionManager,
     // String format of the sponsor address to avoid converting it to string multiple times.
     sponsor_str: String,
+    coin_selection_strategy: CoinSelectionStrategy,
     metrics: Arc<StorageMetrics>,
 }
 
@@ -27,6 +31,7 @@ impl RedisStorage {
     pub async fn new(
         redis_url: &str,
         sponsor_address: IotaAddress,
+        coin_selection_strategy: CoinSelectionStrategy,
         metrics: Arc<StorageMetrics>,
     ) -> Self {
         let client = redis::Client::open(redis_url).unwrap();
@@ -34,9 +39,125 @@ impl RedisStorage {
         Self {
             conn_manager,
             sponsor_str: sponsor_address.to_string(),
+            coin_selection_strategy,
             metrics,
         }
     }
+
+    /// Applies one batch of changes found by `reconcile_coins` to the available coins.
+    async fn apply_reconcile_batch(&self, batch: ReconcileBatch) -> anyhow::Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.conn_manager.clone();
+        let (new_total_balance, new_coin_count): (i64, i64) =
+            ScriptManager::reconcile_coins_script()
+                .arg(self.sponsor_str.clone())
+                .arg(serde_json::to_string(&batch.dropped)?)
+                .arg(serde_json::to_string(&batch.tracked)?)
+                .arg(serde_json::to_string(&batch.updated)?)
+                .arg(serde_json::to_string(&batch.adopted)?)
+                .invoke_async(&mut conn)
+                .await?;
+        self.update_coin_stats_metrics(new_total_balance, new_coin_count);
+        Ok(())
+    }
+
+    fn update_coin_stats_metrics(&self, total_balance: i64, coin_count: i64) {
+        self.metrics
+            .gas_station_available_gas_coin_count
+            .with_label_values(&[&self.sponsor_str])
+            .set(coin_count);
+        self.metrics
+            .gas_station_available_gas_total_balance
+            .with_label_values(&[&self.sponsor_str])
+            .set(total_balance);
+    }
+}
+
+/// Number of available coins that `reconcile_coins` changes at once.
+const RECONCILE_BATCH_SIZE: usize = 1000;
+
+/// Changes to the available coins, in the format of `format_coins`.
+#[derive(Default)]
+struct ReconcileBatch {
+    /// Coins to drop.
+    dropped: Vec<String>,
+    /// Coins to drop if they are still reserved or under an execution lease.
+    tracked: Vec<String>,
+    /// Outdated coins, each followed by its up-to-date state.
+    updated: Vec<String>,
+    /// Owned coins to add if they are neither reserved nor under an execution lease.
+    adopted: Vec<String>,
+}
+
+impl ReconcileBatch {
+    fn is_empty(&self) -> bool {
+        self.dropped.is_empty()
+            && self.tracked.is_empty()
+            && self.updated.is_empty()
+            && self.adopted.is_empty()
+    }
+}
+
+/// Balances up to which the score of a coin in the available gas coins, a double, is exact.
+const MAX_POOL_COIN_BALANCE: u64 = 1 << 53;
+
+/// Drops the coins whose balance is too large to order them correctly in the pool. They stay
+/// owned by the sponsor, and are split once they are found by the coin initialization.
+fn pool_coins(coins: Vec<GasCoin>) -> Vec<GasCoin> {
+    coins
+        .into_iter()
+        .filter(|coin| {
+            let too_large = coin.balance > MAX_POOL_COIN_BALANCE;
+            if too_large {
+                warn!(
+                    "Not adding coin {:?} to the pool, as its balance {} is too large",
+                    coin.object_ref.0, coin.balance
+                );
+            }
+            !too_large
+        })
+        .collect()
+}
+
+fn format_coins(coins: &[GasCoin]) -> Vec<String> {
+    coins
+        .iter()
+        .map(|c| {
+            // The format is: balance,object_id,version,digest
+            // The way we turn them into strings must be consistent with the way we parse them in
+            // reserve_gas_coins_script.
+            format!(
+                "{},{},{},{}",
+                c.balance,
+                c.object_ref.0,
+                c.object_ref.1.value(),
+                c.object_ref.2
+            )
+        })
+        .collect()
+}
+
+fn parse_coin(s: &str) -> GasCoin {
+    // Each coin is in the form of: balance,object_id,version,digest
+    let mut splits = s.split(',');
+    let balance = splits.next().unwrap().parse::<u64>().unwrap();
+    let object_id = ObjectID::from_str(splits.next().unwrap()).unwrap();
+    let version = SequenceNumber::from(splits.next().unwrap().parse::<u64>().unwrap());
+    let digest = ObjectDigest::from_str(splits.next().unwrap()).unwrap();
+    GasCoin {
+        balance,
+        object_ref: (object_id, version, digest),
+    }
+}
+
+fn parse_object_ids(coin_id_strings: &[String]) -> Vec<ObjectID> {
+    // Each string is a list of comma separated coin ids.
+    coin_id_strings
+        .iter()
+        .flat_map(|s| s.split(',').map(|id| ObjectID::from_str(id).unwrap()))
+        .collect()
 }
 
 #[async_trait::async_trait]
@@ -61,53 +182,106 @@ impl Storage for RedisStorage {
             .arg(self.sponsor_str.clone())
             .arg(target_budget)
             .arg(expiration_time)
+            .arg(self.coin_selection_strategy.as_str())
             .invoke_async(&mut conn)
             .await?;
         // The script returns (0, []) if it is unable to find enough coins to reserve.
         // We choose to handle the error here instead of inside the script so that we could
         // provide a more readable error message.
         if coins.is_empty() {
-            return Err(anyhow::anyhow!(
-                "Unable to reserve gas coins for the given budget."
-            ));
+            if self.is_draining().await? {
+                return Err(
+                    GasStationError::Draining("The gas station is draining.".to_string()).into(),
+                );
+            }
+            return Err(GasStationError::InsufficientPoolBalance(
+                "Unable to reserve gas coins for the given budget.".to_string(),
+            )
+            .into());
         }
-        let gas_coins: Vec<_> = coins
-            .into_iter()
-            .map(|s| {
-                // Each coin is in the form of: balance,object_id,version,digest
-                let mut splits = s.split(',');
-                let balance = splits.next().unwrap().parse::<u64>().unwrap();
-                let object_id = ObjectID::from_str(splits.next().unwrap()).unwrap();
-                let version = SequenceNumber::from(splits.next().unwrap().parse::<u64>().unwrap());
-                let digest = ObjectDigest::from_str(splits.next().unwrap()).unwrap();
-                GasCoin {
-                    balance,
-                    object_ref: (object_id, version, digest),
-                }
-            })
-            .collect();
+        let gas_coins: Vec<_> = coins.iter().map(|s| parse_coin(s)).collect();
 
-        self.metrics
-            .gas_station_available_gas_coin_count
-            .with_label_values(&[&self.sponsor_str])
-            .set(new_coin_count);
-        self.metrics
-            .gas_station_available_gas_total_balance
-            .with_label_values(&[&self.sponsor_str])
-            .set(new_total_balance);
+        self.update_coin_stats_metrics(new_total_balance, new_coin_count);
         self.metrics.num_successful_reserve_gas_coins_requests.inc();
         Ok((reservation_id, gas_coins))
     }
 
-    async fn ready_for_execution(&self, reservation_id: ReservationID) -> anyhow::Result<()> {
+    async fn reserve_dust_coins(
+        &self,
+        max_balance: u64,
+        min_count: usize,
+        max_count: usize,
+        reserved_duration_ms: u64,
+    ) -> anyhow::Result<(ReservationID, Vec<GasCoin>)> {
+        self.metrics.num_reserve_dust_coins_requests.inc();
+
+        let expiration_time = Utc::now()
+            .add(Duration::from_millis(reserved_duration_ms))
+            .timestamp_millis() as u64;
+        let mut conn = self.conn_manager.clone();
+        let (reservation_id, coins, new_total_balance, new_coin_count): (
+            ReservationID,
+            Vec<String>,
+            i64,
+            i64,
+        ) = ScriptManager::reserve_dust_coins_script()
+            .arg(self.sponsor_str.clone())
+            .arg(max_balance)
+            .arg(min_count)
+            .arg(max_count)
+            .arg(expiration_time)
+            .invoke_async(&mut conn)
+            .await?;
+        let gas_coins = coins.iter().map(|s| parse_coin(s)).collect();
+
+        self.update_coin_stats_metrics(new_total_balance, new_coin_count);
+        self.metrics
+            .num_successful_reserve_dust_coins_requests
+            .inc();
+        Ok((reservation_id, gas_coins))
+    }
+
+    async fn ready_for_execution(
+        &self,
+        reservation_id: ReservationID,
+        payment: &[ObjectID],
+        lease_duration_ms: u64,
+    ) -> anyhow::Result<()> {
         self.metrics.num_ready_for_execution_requests.inc();
 
+        let lease_expiration_time = Utc::now()
+            .add(Duration::from_millis(lease_duration_ms))
+            .timestamp_millis() as u64;
+
+        let formatted_payment = payment
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>();
         let mut conn = self.conn_manager.clone();
-        ScriptManager::ready_for_execution_script()
+        let result = ScriptManager::ready_for_execution_script()
             .arg(self.sponsor_str.clone())
             .arg(reservation_id)
-            .invoke_async::<_, ()>(&mut conn)
+            .arg(serde_json::to_string(&formatted_payment)?)
+            .arg(lease_expiration_time)
+            .invoke_async::<_, i64>(&mut conn)
             .await?;
+        match result {
+            1 => {}
+            0 => {
+                return Err(GasStationError::ReservationExpired(format!(
+                    "Reservation no longer exist: {}",
+                    reservation_id
+                ))
+                .into())
+            }
+            _ => {
+                return Err(GasStationError::PaymentMismatch(format!(
+                    "Gas payment does not match the coins of reservation {}",
+                    reservation_id
+                ))
+                .into())
+            }
+        }
 
         self.metrics
             .num_successful_ready_for_execution_requests
@@ -117,21 +291,7 @@ impl Storage for RedisStorage {
 
     async fn add_new_coins(&self, new_coins: Vec<GasCoin>) -> anyhow::Result<()> {
         self.metrics.num_add_new_coins_requests.inc();
-        let formatted_coins = new_coins
-            .iter()
-            .map(|c| {
-                // The format is: balance,object_id,version,digest
-                // The way we turn them into strings must be consistent with the way we parse them in
-                // reserve_gas_coins_script.
-                format!(
-                    "{},{},{},{}",
-                    c.balance,
-                    c.object_ref.0,
-                    c.object_ref.1.value(),
-                    c.object_ref.2
-                )
-            })
-            .collect::<Vec<String>>();
+        let formatted_coins = format_coins(&pool_coins(new_coins));
 
         let mut conn = self.conn_manager.clone();
         let (new_total_balance, new_coin_count): (i64, i64) = ScriptManager::add_new_coins_script()
@@ -144,14 +304,7 @@ impl Storage for RedisStorage {
             "After add_new_coins. New total balance: {}, new coin count: {}",
             new_total_balance, new_coin_count
         );
-        self.metrics
-            .gas_station_available_gas_coin_count
-            .with_label_values(&[&self.sponsor_str])
-            .set(new_coin_count);
-        self.metrics
-            .gas_station_available_gas_total_balance
-            .with_label_values(&[&self.sponsor_str])
-            .set(new_total_balance);
+        self.update_coin_stats_metrics(new_total_balance, new_coin_count);
         self.metrics.num_successful_add_new_coins_requests.inc();
         Ok(())
     }
@@ -166,16 +319,162 @@ impl Storage for RedisStorage {
             .arg(now)
             .invoke_async(&mut conn)
             .await?;
-        // The script returns a list of comma separated coin ids.
-        let expired_coin_ids = expired_coin_strings
-            .iter()
-            .flat_map(|s| s.split(',').map(|id| ObjectID::from_str(id).unwrap()))
-            .collect();
+        let expired_coin_ids = parse_object_ids(&expired_coin_strings);
 
         self.metrics.num_successful_expire_coins_requests.inc();
         Ok(expired_coin_ids)
     }
 
+    async fn complete_execution(
+        &self,
+        reservation_id: ReservationID,
+        updated_coins: Vec<GasCoin>,
+    ) -> anyhow::Result<bool> {
+        self.metrics.num_complete_execution_requests.inc();
+        let formatted_coins = format_coins(&pool_coins(updated_coins));
+
+        let mut conn = self.conn_manager.clone();
+        let (new_total_balance, new_coin_count, lease_existed): (i64, i64, i64) =
+            ScriptManager::complete_execution_script()
+                .arg(self.sponsor_str.clone())
+                .arg(reservation_id)
+                .arg(serde_json::to_string(&formatted_coins)?)
+                .invoke_async(&mut conn)
+                .await?;
+
+        debug!(
+            ?reservation_id,
+            "After complete_execution. New total balance: {}, new coin count: {}",
+            new_total_balance,
+            new_coin_count
+        );
+        self.update_coin_stats_metrics(new_total_balance, new_coin_count);
+        self.metrics
+            .num_successful_complete_execution_requests
+            .inc();
+        Ok(lease_existed == 1)
+    }
+
+    async fn expire_execution_leases(&self) -> anyhow::Result<Vec<ObjectID>> {
+        self.metrics.num_expire_execution_leases_requests.inc();
+
+        let now = Utc::now().timestamp_millis() as u64;
+        let mut conn = self.conn_manager.clone();
+        let expired_coin_strings: Vec<String> = ScriptManager::expire_execution_leases_script()
+            .arg(self.sponsor_str.clone())
+            .arg(now)
+            .invoke_async(&mut conn)
+            .await?;
+        let expired_coin_ids = parse_object_ids(&expired_coin_strings);
+
+        self.metrics
+            .num_successful_expire_execution_leases_requests
+            .inc();
+        Ok(expired_coin_ids)
+    }
+
+    async fn reconcile_coins(
+        &self,
+        owned_coins: Vec<GasCoin>,
+        max_adopted_balance: u64,
+        dry_run: bool,
+    ) -> anyhow::Result<CoinReconciliation> {
+        self.metrics.num_reconcile_coins_requests.inc();
+        let owned_coins: HashMap<ObjectID, GasCoin> = pool_coins(owned_coins)
+            .into_iter()
+            .map(|coin| (coin.object_ref.0, coin))
+            .collect();
+        let mut conn = self.conn_manager.clone();
+        let tracked_coin_ids: Vec<String> = ScriptManager::get_tracked_coin_ids_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async(&mut conn)
+            .await?;
+        let tracked: HashSet<ObjectID> = parse_object_ids(&tracked_coin_ids).into_iter().collect();
+
+        // The pool is scanned in batches, so that other requests are not blocked for long. A scan
+        // may return a coin more than once, and coins added during the scan may or may not be
+        // returned.
+        let available_coins_key = format!("{}:available_gas_coins", self.sponsor_str);
+        let mut reconciliation = CoinReconciliation::default();
+        let mut scanned = HashSet::new();
+        let mut in_pool = HashSet::new();
+        let mut cursor = 0;
+        loop {
+            let (next_cursor, entries): (u64, Vec<String>) = redis::cmd("ZSCAN")
+                .arg(&available_coins_key)
+                .arg(cursor)
+                .arg("COUNT")
+                .arg(RECONCILE_BATCH_SIZE)
+                .query_async(&mut conn)
+                .await?;
+            let mut batch = ReconcileBatch::default();
+            // The entries alternate between coins and their scores.
+            for coin_str in entries.into_iter().step_by(2) {
+                if !scanned.insert(coin_str.clone()) {
+                    continue;
+                }
+                let coin = parse_coin(&coin_str);
+                let object_id = coin.object_ref.0;
+                if tracked.contains(&object_id) {
+                    reconciliation.duplicates += 1;
+                    batch.tracked.push(coin_str);
+                } else if !in_pool.insert(object_id) {
+                    reconciliation.duplicates += 1;
+                    batch.dropped.push(coin_str);
+                } else {
+                    match owned_coins.get(&object_id) {
+                        None => {
+                            reconciliation.removed += 1;
+                            batch.dropped.push(coin_str);
+                        }
+                        // The coin may have been released with a newer version after the owned
+                        // coins were queried.
+                        Some(owned_coin) if owned_coin.object_ref.1 > coin.object_ref.1 => {
+                            reconciliation.updated += 1;
+                            let owned_coin_str =
+                                format_coins(std::slice::from_ref(owned_coin)).remove(0);
+                            // Not to be mistaken for a duplicate if the scan returns it.
+                            scanned.insert(owned_coin_str.clone());
+                            batch.updated.push(coin_str);
+                            batch.updated.push(owned_coin_str);
+                        }
+                        Some(_) => (),
+                    }
+                }
+            }
+            if !dry_run {
+                self.apply_reconcile_batch(batch).await?;
+            }
+            cursor = next_cursor;
+            if cursor == 0 {
+                break;
+            }
+        }
+
+        let adopted_coins: Vec<GasCoin> = owned_coins
+            .into_values()
+            .filter(|coin| {
+                let object_id = coin.object_ref.0;
+                !in_pool.contains(&object_id)
+                    && !tracked.contains(&object_id)
+                    && coin.balance < max_adopted_balance
+            })
+            .collect();
+        reconciliation.adopted = adopted_coins.len() as u64;
+        if !dry_run {
+            for chunk in adopted_coins.chunks(RECONCILE_BATCH_SIZE) {
+                self.apply_reconcile_batch(ReconcileBatch {
+                    adopted: format_coins(chunk),
+                    ..Default::default()
+                })
+                .await?;
+            }
+        }
+
+        self.metrics.num_successful_reconcile_coins_requests.inc();
+        Ok(reconciliation)
+    }
+
     async fn init_coin_stats_at_startup(&self) -> anyhow::Result<(u64, u64)> {
         let mut conn = self.conn_manager.clone();
         let (available_coin_count, available_coin_total_balance): (i64, i64) =
@@ -189,20 +488,37 @@ impl Storage for RedisStorage {
             available_coin_count,
             available_coin_total_balance
         );
-        self.metrics
-            .gas_station_available_gas_coin_count
-            .with_label_values(&[&self.sponsor_str])
-            .set(available_coin_count);
-        self.metrics
-            .gas_station_available_gas_total_balance
-            .with_label_values(&[&self.sponsor_str])
-            .set(available_coin_total_balance);
+        self.update_coin_stats_metrics(available_coin_total_balance, available_coin_count);
         Ok((
             available_coin_count as u64,
             available_coin_total_balance as u64,
         ))
     }
 
+    async fn get_storage_version(&self) -> anyhow::Result<u64> {
+        let mut conn = self.conn_manager.clone();
+        let version = ScriptManager::check_storage_version_script()
+            .arg(self.sponsor_str.clone())
+            .arg(STORAGE_VERSION)
+            .invoke_async::<_, u64>(&mut conn)
+            .await?;
+        Ok(version)
+    }
+
+    async fn migrate_storage(&self) -> anyhow::Result<()> {
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::migrate_storage_script()
+            .arg(self.sponsor_str.clone())
+            .arg(STORAGE_VERSION)
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        info!(
+            sponsor_address=?self.sponsor_str,
+            "Migrated the storage to version {}", STORAGE_VERSION
+        );
+        Ok(())
+    }
+
     async fn is_initialized(&self) -> anyhow::Result<bool> {
         let mut conn = self.conn_manager.clone();
         let result = ScriptManager::get_is_initialized_script()
@@ -212,6 +528,55 @@ impl Storage for RedisStorage {
         Ok(result)
     }
 
+    async fn set_coin_denominations(
+        &self,
+        denominations: &[CoinDenomination],
+    ) -> anyhow::Result<()> {
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::set_coin_denominations_script()
+            .arg(self.sponsor_str.clone())
+            .arg(serde_json::to_string(denominations)?)
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_coin_denominations(&self) -> anyhow::Result<Vec<CoinDenomination>> {
+        let mut conn = self.conn_manager.clone();
+        let denominations = ScriptManager::get_coin_denominations_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, String>(&mut conn)
+            .await?;
+        Ok(serde_json::from_str(&denominations)?)
+    }
+
+    async fn add_budget_samples(&self, samples: &[(u64, u64)]) -> anyhow::Result<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.conn_manager.clone();
+        let mut script = ScriptManager::add_budget_samples_script().arg(self.sponsor_str.clone());
+        for (upper_bound, count) in samples {
+            script.arg(*upper_bound).arg(*count);
+        }
+        script.invoke_async::<_, ()>(&mut conn).await?;
+        Ok(())
+    }
+
+    async fn get_budget_samples(&self) -> anyhow::Result<Vec<(u64, u64)>> {
+        let mut conn = self.conn_manager.clone();
+        let values = ScriptManager::get_budget_samples_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, Vec<u64>>(&mut conn)
+            .await?;
+        let mut samples: Vec<_> = values
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect();
+        samples.sort_unstable();
+        Ok(samples)
+    }
+
     async fn acquire_init_lock(&self, lock_duration_sec: u64) -> anyhow::Result<bool> {
         let mut conn = self.conn_manager.clone();
         let cur_timestamp = Utc::now().timestamp() as u64;
@@ -228,6 +593,22 @@ impl Storage for RedisStorage {
         Ok(result)
     }
 
+    async fn renew_init_lock(&self, lock_duration_sec: u64) -> anyhow::Result<bool> {
+        let mut conn = self.conn_manager.clone();
+        let cur_timestamp = Utc::now().timestamp() as u64;
+        debug!(
+            "Renewing init lock at {} for {} seconds",
+            cur_timestamp, lock_duration_sec
+        );
+        let result = ScriptManager::renew_init_lock_script()
+            .arg(self.sponsor_str.clone())
+            .arg(cur_timestamp)
+            .arg(lock_duration_sec)
+            .invoke_async::<_, bool>(&mut conn)
+            .await?;
+        Ok(result)
+    }
+
     async fn release_init_lock(&self) -> anyhow::Result<()> {
         debug!("Releasing the init lock.");
         let mut conn = self.conn_manager.clone();
@@ -238,6 +619,162 @@ impl Storage for RedisStorage {
         Ok(())
     }
 
+    async fn set_draining(&self, draining: bool) -> anyhow::Result<()> {
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::set_draining_script()
+            .arg(self.sponsor_str.clone())
+            .arg(draining as u8)
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn is_draining(&self) -> anyhow::Result<bool> {
+        let mut conn = self.conn_manager.clone();
+        let result = ScriptManager::get_is_draining_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, bool>(&mut conn)
+            .await?;
+        Ok(result)
+    }
+
+    async fn get_reservation_count(&self) -> anyhow::Result<usize> {
+        let mut conn = self.conn_manager.clone();
+        let count = ScriptManager::get_reservation_count_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, usize>(&mut conn)
+            .await?;
+        Ok(count)
+    }
+
+    async fn mark_uninitialized(&self) -> anyhow::Result<()> {
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::mark_uninitialized_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        self.update_coin_stats_metrics(0, 0);
+        Ok(())
+    }
+
+    async fn delete_sponsor_data(&self) -> anyhow::Result<()> {
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::delete_sponsor_data_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        self.update_coin_stats_metrics(0, 0);
+        Ok(())
+    }
+
+    async fn add_notification(&self, content: &str, delay_ms: u64) -> anyhow::Result<u64> {
+        let due_time = Utc::now()
+            .add(Duration::from_millis(delay_ms))
+            .timestamp_millis() as u64;
+        let mut conn = self.conn_manager.clone();
+        let id = ScriptManager::add_notification_script()
+            .arg(self.sponsor_str.clone())
+            .arg(content)
+            .arg(due_time)
+            .invoke_async::<_, u64>(&mut conn)
+            .await?;
+        Ok(id)
+    }
+
+    async fn claim_notifications(
+        &self,
+        max_count: usize,
+        lease_duration_ms: u64,
+    ) -> anyhow::Result<Vec<OutboxNotification>> {
+        let now = Utc::now();
+        let lease_expiration_time = now
+            .add(Duration::from_millis(lease_duration_ms))
+            .timestamp_millis() as u64;
+        let mut conn = self.conn_manager.clone();
+        let claimed = ScriptManager::claim_notifications_script()
+            .arg(self.sponsor_str.clone())
+            .arg(now.timestamp_millis() as u64)
+            .arg(lease_expiration_time)
+            .arg(max_count)
+            .invoke_async::<_, Vec<String>>(&mut conn)
+            .await?;
+        // The script returns the id, attempts and content of each notification in a flat list.
+        claimed
+            .chunks_exact(3)
+            .map(|notification| -> anyhow::Result<_> {
+                Ok(OutboxNotification {
+                    id: notification[0].parse()?,
+                    attempts: notification[1].parse()?,
+                    content: notification[2].clone(),
+                })
+            })
+            .collect()
+    }
+
+    async fn complete_notification(&self, id: u64) -> anyhow::Result<()> {
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::complete_notification_script()
+            .arg(self.sponsor_str.clone())
+            .arg(id)
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn reschedule_notification(&self, id: u64, delay_ms: u64) -> anyhow::Result<()> {
+        let due_time = Utc::now()
+            .add(Duration::from_millis(delay_ms))
+            .timestamp_millis() as u64;
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::reschedule_notification_script()
+            .arg(self.sponsor_str.clone())
+            .arg(id)
+            .arg(due_time)
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_notification(&self, id: u64, content: &str) -> anyhow::Result<bool> {
+        let mut conn = self.conn_manager.clone();
+        let updated = ScriptManager::update_notification_script()
+            .arg(self.sponsor_str.clone())
+            .arg(id)
+            .arg(content)
+            .arg(Utc::now().timestamp_millis() as u64)
+            .invoke_async::<_, bool>(&mut conn)
+            .await?;
+        Ok(updated)
+    }
+
+    async fn get_notification_count(&self) -> anyhow::Result<usize> {
+        let mut conn = self.conn_manager.clone();
+        let count = ScriptManager::get_notification_count_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, usize>(&mut conn)
+            .await?;
+        Ok(count)
+    }
+
+    async fn dead_letter_notification(&self, id: u64) -> anyhow::Result<()> {
+        let mut conn = self.conn_manager.clone();
+        ScriptManager::dead_letter_notification_script()
+            .arg(self.sponsor_str.clone())
+            .arg(id)
+            .invoke_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_dead_notification_count(&self) -> anyhow::Result<usize> {
+        let mut conn = self.conn_manager.clone();
+        let count = ScriptManager::get_dead_notification_count_script()
+            .arg(self.sponsor_str.clone())
+            .invoke_async::<_, usize>(&mut conn)
+            .await?;
+        Ok(count)
+    }
+
     async fn check_health(&self) -> anyhow::Result<()> {
         let mut conn = self.conn_manager.clone();
         redis::cmd("PING")
@@ -286,11 +823,18 @@ impl Storage for RedisStorage {
 
 #[cfg(test)]
 mod tests {
-    use iota_types::base_types::{random_object_ref, IotaAddress};
+    use iota_types::base_types::{random_object_ref, IotaAddress, SequenceNumber};
 
     use crate::{
+        config::CoinSelectionStrategy,
         metrics::StorageMetrics,
-        storage::{redis::RedisStorage, Storage},
+        storage::{
+            redis::{
+                format_coins, ReconcileBatch, RedisStorage, MAX_POOL_COIN_BALANCE,
+                RECONCILE_BATCH_SIZE,
+            },
+            Storage, STORAGE_VERSION,
+        },
         types::GasCoin,
     };
 
@@ -354,10 +898,186 @@ mod tests {
         assert_eq!(total_balance, 1000);
     }
 
+    #[tokio::test]
+    async fn test_coin_selection_strategies() {
+        for (strategy, budget, expected_balances) in [
+            (CoinSelectionStrategy::BestFit, 8, vec![10]),
+            (CoinSelectionStrategy::BestFit, 70, vec![50, 10, 10]),
+            (CoinSelectionStrategy::LargestFirst, 8, vec![50]),
+            (CoinSelectionStrategy::SmallestFirst, 8, vec![1, 5, 10]),
+        ] {
+            let storage = RedisStorage::new(
+                "redis://127.0.0.1:6379",
+                IotaAddress::random_for_testing_only(),
+                strategy,
+                StorageMetrics::new_for_testing(),
+            )
+            .await;
+            storage.init_coin_stats_at_startup().await.unwrap();
+            let coins = [1, 5, 10, 10, 50]
+                .into_iter()
+                .map(|balance| GasCoin {
+                    balance,
+                    object_ref: random_object_ref(),
+                })
+                .collect();
+            storage.add_new_coins(coins).await.unwrap();
+
+            let (_, reserved) = storage.reserve_gas_coins(budget, 1000).await.unwrap();
+            let balances: Vec<_> = reserved.iter().map(|coin| coin.balance).collect();
+            assert_eq!(balances, expected_balances, "{:?}", strategy);
+            let reserved_balance: u64 = balances.iter().sum();
+            assert_eq!(
+                storage.get_available_coin_total_balance().await,
+                76 - reserved_balance
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_available_coins_from_list() {
+        let sponsor = IotaAddress::random_for_testing_only();
+        let storage = RedisStorage::new(
+            "redis://127.0.0.1:6379",
+            sponsor,
+            CoinSelectionStrategy::default(),
+            StorageMetrics::new_for_testing(),
+        )
+        .await;
+        // Older versions kept the available coins in a list.
+        let coins = vec![
+            GasCoin {
+                balance: 300,
+                object_ref: random_object_ref(),
+            },
+            GasCoin {
+                balance: 100,
+                object_ref: random_object_ref(),
+            },
+        ];
+        let mut conn = storage.conn_manager.clone();
+        let _: () = redis::cmd("RPUSH")
+            .arg(format!("{}:available_gas_coins", sponsor))
+            .arg(format_coins(&coins))
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+
+        // The pool isn't touched until all instances of older versions were stopped.
+        assert_eq!(storage.get_storage_version().await.unwrap(), 1);
+        storage.migrate_storage().await.unwrap();
+        assert_eq!(
+            storage.get_storage_version().await.unwrap(),
+            STORAGE_VERSION
+        );
+
+        let (coin_count, total_balance) = storage.init_coin_stats_at_startup().await.unwrap();
+        assert_eq!(coin_count, 2);
+        assert_eq!(total_balance, 400);
+        let (_, reserved) = storage.reserve_gas_coins(50, 1000).await.unwrap();
+        assert_eq!(reserved, vec![coins[1].clone()]);
+
+        // A new sponsor starts with the current version.
+        let storage = RedisStorage::new(
+            "redis://127.0.0.1:6379",
+            IotaAddress::random_for_testing_only(),
+            CoinSelectionStrategy::default(),
+            StorageMetrics::new_for_testing(),
+        )
+        .await;
+        assert_eq!(
+            storage.get_storage_version().await.unwrap(),
+            STORAGE_VERSION
+        );
+    }
+
+    #[tokio::test]
+    async fn test_coin_balance_above_score_precision() {
+        let storage = setup_storage().await;
+        let coins = vec![
+            GasCoin {
+                balance: MAX_POOL_COIN_BALANCE,
+                object_ref: random_object_ref(),
+            },
+            GasCoin {
+                balance: MAX_POOL_COIN_BALANCE + 1,
+                object_ref: random_object_ref(),
+            },
+        ];
+        storage.add_new_coins(coins).await.unwrap();
+        assert_eq!(storage.get_available_coin_count().await.unwrap(), 1);
+        assert_eq!(
+            storage.get_available_coin_total_balance().await,
+            MAX_POOL_COIN_BALANCE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_coins_in_batches() {
+        let storage = setup_storage().await;
+        let coins: Vec<_> = (0..RECONCILE_BATCH_SIZE * 2 + 1)
+            .map(|i| GasCoin {
+                balance: i as u64 + 1,
+                object_ref: random_object_ref(),
+            })
+            .collect();
+        storage.add_new_coins(coins.clone()).await.unwrap();
+        let (_, reserved) = storage.reserve_gas_coins(1, 1000).await.unwrap();
+        assert_eq!(reserved, vec![coins[0].clone()]);
+
+        // Every other coin is no longer owned, and the rest of the pool is unchanged.
+        let owned_coins: Vec<_> = coins.iter().step_by(2).cloned().collect();
+        let reconciliation = storage
+            .reconcile_coins(owned_coins.clone(), 1000, false)
+            .await
+            .unwrap();
+        assert_eq!(reconciliation.removed, RECONCILE_BATCH_SIZE as u64);
+        assert_eq!(reconciliation.adopted, 0);
+        assert_eq!(
+            storage.get_available_coin_count().await.unwrap(),
+            RECONCILE_BATCH_SIZE
+        );
+        let total_balance: u64 = owned_coins.iter().skip(1).map(|coin| coin.balance).sum();
+        assert_eq!(
+            storage.get_available_coin_total_balance().await,
+            total_balance
+        );
+        assert!(storage
+            .reconcile_coins(owned_coins, 1000, false)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_does_not_adopt_available_coin() {
+        let storage = setup_storage().await;
+        let coin = GasCoin {
+            balance: 10,
+            object_ref: random_object_ref(),
+        };
+        // The coin was released with a newer version after the owned coins were queried.
+        let mut released = coin.clone();
+        released.object_ref.1 = SequenceNumber::from_u64(coin.object_ref.1.value() + 1);
+        released.balance = 8;
+        storage.add_new_coins(vec![released]).await.unwrap();
+
+        storage
+            .apply_reconcile_batch(ReconcileBatch {
+                adopted: format_coins(&[coin]),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(storage.get_available_coin_count().await.unwrap(), 1);
+        assert_eq!(storage.get_available_coin_total_balance().await, 8);
+    }
+
     async fn setup_storage() -> RedisStorage {
         let storage = RedisStorage::new(
             "redis://127.0.0.1:6379",
             IotaAddress::ZERO,
+            CoinSelectionStrategy::default(),
             StorageMetrics::new_for_testing(),
         )
         .await;
@@ -2,18 +2,47 @@ use once_cell::sync::Lazy;
 use redis::Script;
 
 const RESERVE_GAS_COINS_SCRIPT: &str = include_str!("lua_scripts/reserve_gas_coins.lua");
+const RESERVE_DUST_COINS_SCRIPT: &str = include_str!("lua_scripts/reserve_dust_coins.lua");
 const ADD_NEW_COINS_SCRIPT: &str = include_str!("lua_scripts/add_new_coins.lua");
 const READY_FOR_EXECUTION_SCRIPT: &str = include_str!("lua_scripts/ready_for_execution.lua");
 const EXPIRE_COINS_SCRIPT: &str = include_str!("lua_scripts/expire_coins.lua");
+const COMPLETE_EXECUTION_SCRIPT: &str = include_str!("lua_scripts/complete_execution.lua");
+const EXPIRE_EXECUTION_LEASES_SCRIPT: &str =
+    include_str!("lua_scripts/expire_execution_leases.lua");
+const RECONCILE_COINS_SCRIPT: &str = include_str!("lua_scripts/reconcile_coins.lua");
+const GET_TRACKED_COIN_IDS_SCRIPT: &str = include_str!("lua_scripts/get_tracked_coin_ids.lua");
 const INIT_COIN_STATS_AT_STARTUP_SCRIPT: &str =
     include_str!("lua_scripts/init_coin_stats_at_startup.lua");
+const CHECK_STORAGE_VERSION_SCRIPT: &str = include_str!("lua_scripts/check_storage_version.lua");
+const MIGRATE_STORAGE_SCRIPT: &str = include_str!("lua_scripts/migrate_storage.lua");
 const GET_AVAILABLE_COIN_COUNT_SCRIPT: &str =
     include_str!("lua_scripts/get_available_coin_count.lua");
 const GET_IS_INITIALIZED_SCRIPT: &str = include_str!("lua_scripts/get_is_initialized.lua");
 const GET_AVAILABLE_COIN_TOTAL_BALANCE_SCRIPT: &str =
     include_str!("lua_scripts/get_available_coin_total_balance.lua");
+const SET_COIN_DENOMINATIONS_SCRIPT: &str = include_str!("lua_scripts/set_coin_denominations.lua");
+const GET_COIN_DENOMINATIONS_SCRIPT: &str = include_str!("lua_scripts/get_coin_denominations.lua");
+const ADD_BUDGET_SAMPLES_SCRIPT: &str = include_str!("lua_scripts/add_budget_samples.lua");
+const GET_BUDGET_SAMPLES_SCRIPT: &str = include_str!("lua_scripts/get_budget_samples.lua");
 const ACQUIRE_INIT_LOCK_SCRIPT: &str = include_str!("lua_scripts/acquire_init_lock.lua");
 const RELEASE_INIT_LOCK_SCRIPT: &str = include_str!("lua_scripts/release_init_lock.lua");
+const RENEW_INIT_LOCK_SCRIPT: &str = include_str!("lua_scripts/renew_init_lock.lua");
+const SET_DRAINING_SCRIPT: &str = include_str!("lua_scripts/set_draining.lua");
+const GET_IS_DRAINING_SCRIPT: &str = include_str!("lua_scripts/get_is_draining.lua");
+const GET_RESERVATION_COUNT_SCRIPT: &str = include_str!("lua_scripts/get_reservation_count.lua");
+const MARK_UNINITIALIZED_SCRIPT: &str = include_str!("lua_scripts/mark_uninitialized.lua");
+const DELETE_SPONSOR_DATA_SCRIPT: &str = include_str!("lua_scripts/delete_sponsor_data.lua");
+const ADD_NOTIFICATION_SCRIPT: &str = include_str!("lua_scripts/add_notification.lua");
+const CLAIM_NOTIFICATIONS_SCRIPT: &str = include_str!("lua_scripts/claim_notifications.lua");
+const COMPLETE_NOTIFICATION_SCRIPT: &str = include_str!("lua_scripts/complete_notification.lua");
+const RESCHEDULE_NOTIFICATION_SCRIPT: &str =
+    include_str!("lua_scripts/reschedule_notification.lua");
+const UPDATE_NOTIFICATION_SCRIPT: &str = include_str!("lua_scripts/update_notification.lua");
+const GET_NOTIFICATION_COUNT_SCRIPT: &str = include_str!("lua_scripts/get_notification_count.lua");
+const DEAD_LETTER_NOTIFICATION_SCRIPT: &str =
+    include_str!("lua_scripts/dead_letter_notification.lua");
+const GET_DEAD_NOTIFICATION_COUNT_SCRIPT: &str =
+    include_str!("lua_scripts/get_dead_notification_count.lua");
 
 #[cfg(test)]
 const GET_RESERVED_COIN_COUNT_SCRIPT: &str =
@@ -27,6 +56,11 @@ impl ScriptManager {
         Lazy::force(&SCRIPT)
     }
 
+    pub fn reserve_dust_coins_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(RESERVE_DUST_COINS_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
     pub fn add_new_coins_script() -> &'static Script {
         static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(ADD_NEW_COINS_SCRIPT));
         Lazy::force(&SCRIPT)
@@ -42,6 +76,26 @@ impl ScriptManager {
         Lazy::force(&SCRIPT)
     }
 
+    pub fn complete_execution_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(COMPLETE_EXECUTION_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn expire_execution_leases_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(EXPIRE_EXECUTION_LEASES_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn get_tracked_coin_ids_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(GET_TRACKED_COIN_IDS_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn reconcile_coins_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(RECONCILE_COINS_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
     pub fn init_coin_stats_at_startup_script() -> &'static Script {
         static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(INIT_COIN_STATS_AT_STARTUP_SCRIPT));
         Lazy::force(&SCRIPT)
@@ -57,22 +111,122 @@ impl ScriptManager {
         Lazy::force(&SCRIPT)
     }
 
+    pub fn check_storage_version_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(CHECK_STORAGE_VERSION_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn migrate_storage_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(MIGRATE_STORAGE_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
     pub fn get_available_coin_total_balance_script() -> &'static Script {
         static SCRIPT: Lazy<Script> =
             Lazy::new(|| Script::new(GET_AVAILABLE_COIN_TOTAL_BALANCE_SCRIPT));
         Lazy::force(&SCRIPT)
     }
 
+    pub fn set_coin_denominations_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(SET_COIN_DENOMINATIONS_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn get_coin_denominations_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(GET_COIN_DENOMINATIONS_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn add_budget_samples_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(ADD_BUDGET_SAMPLES_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn get_budget_samples_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(GET_BUDGET_SAMPLES_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
     pub fn acquire_init_lock_script() -> &'static Script {
         static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(ACQUIRE_INIT_LOCK_SCRIPT));
         Lazy::force(&SCRIPT)
     }
 
+    pub fn renew_init_lock_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(RENEW_INIT_LOCK_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
     pub fn release_init_lock_script() -> &'static Script {
         static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(RELEASE_INIT_LOCK_SCRIPT));
         Lazy::force(&SCRIPT)
     }
 
+    pub fn set_draining_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(SET_DRAINING_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn get_is_draining_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(GET_IS_DRAINING_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn get_reservation_count_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(GET_RESERVATION_COUNT_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn mark_uninitialized_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(MARK_UNINITIALIZED_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn delete_sponsor_data_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(DELETE_SPONSOR_DATA_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn add_notification_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(ADD_NOTIFICATION_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn claim_notifications_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(CLAIM_NOTIFICATIONS_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn complete_notification_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(COMPLETE_NOTIFICATION_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn reschedule_notification_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(RESCHEDULE_NOTIFICATION_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn update_notification_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(UPDATE_NOTIFICATION_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn get_notification_count_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(GET_NOTIFICATION_COUNT_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn dead_letter_notification_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(DEAD_LETTER_NOTIFICATION_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
+    pub fn get_dead_notification_count_script() -> &'static Script {
+        static SCRIPT: Lazy<Script> = Lazy::new(|| Script::new(GET_DEAD_NOTIFICATION_COUNT_SCRIPT));
+        Lazy::force(&SCRIPT)
+    }
+
     // This needs to be test only because it's really expensive to call in production.
     #[cfg(test)]
     pub fn get_reserved_coin_count_script() -> &'static Script {
@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::access_controller::AccessController;
-use crate::config::{CoinInitConfig, GasStationStorageConfig, DEFAULT_DAILY_GAS_USAGE_CAP};
+use crate::config::{
+    CoinInitConfig, ExecutionConfig, GasStationStorageConfig, DEFAULT_DAILY_GAS_USAGE_CAP,
+};
 use crate::gas_station::gas_station_core::GasStationContainer;
 use crate::gas_station_initializer::GasStationInitializer;
 use crate::iota_client::IotaClient;
 use crate::metrics::{GasStationCoreMetrics, GasStationRpcMetrics};
+use crate::rpc::browser_auth::BrowserAuth;
 use crate::rpc::GasStationServer;
 use crate::storage::connect_storage_for_testing;
 use crate::tracker::stats_tracker_storage::redis::connect_stats_storage;
@@ -54,6 +57,16 @@ pub async fn start_iota_cluster(init_gas_amounts: Vec<u64>) -> (TestCluster, Arc
 pub async fn start_gas_station(
     init_gas_amounts: Vec<u64>,
     target_init_coin_balance: u64,
+) -> (TestCluster, GasStationContainer) {
+    start_gas_station_with_signer(init_gas_amounts, target_init_coin_balance, |signer| signer).await
+}
+
+/// Like `start_gas_station`, but the gas station signs with the signer returned by
+/// `station_signer`, given the signer of the sponsor.
+pub async fn start_gas_station_with_signer(
+    init_gas_amounts: Vec<u64>,
+    target_init_coin_balance: u64,
+    station_signer: impl FnOnce(Arc<dyn TxSigner>) -> Arc<dyn TxSigner>,
 ) -> (TestCluster, GasStationContainer) {
     debug!("Starting Iota cluster..");
     let (test_cluster, signer) = start_iota_cluster(init_gas_amounts).await;
@@ -70,14 +83,17 @@ pub async fn start_gas_station(
             ..Default::default()
         },
         signer.clone(),
+        None,
     )
     .await;
     let station = GasStationContainer::new(
-        signer,
+        station_signer(signer),
         storage,
         iota_client,
         DEFAULT_DAILY_GAS_USAGE_CAP,
+        ExecutionConfig::default(),
         GasStationCoreMetrics::new_for_testing(),
+        None,
     )
     .await;
     (test_cluster, station)
@@ -100,6 +116,11 @@ pub async fn start_rpc_server_for_testing(
         Arc::new(ArcSwap::new(Arc::new(AccessController::default()))),
         new_stats_tracker_for_testing(signer_address).await,
         PathBuf::from_str(DEFAULT_TEST_CONFIG_PATH).unwrap(),
+        None,
+        None,
+        None,
+        None,
+        None,
     )
     .await;
     (test_cluster, container, server)
@@ -121,6 +142,39 @@ pub async fn start_rpc_server_for_testing_no_auth(
         Arc::new(ArcSwap::new(Arc::new(AccessController::default()))),
         new_stats_tracker_for_testing(signer_address).await,
         PathBuf::from_str(DEFAULT_TEST_CONFIG_PATH).unwrap(),
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    (test_cluster, container, server)
+}
+
+pub async fn start_rpc_server_for_testing_with_browser_auth(
+    init_gas_amounts: Vec<u64>,
+    target_init_balance: u64,
+    browser_auth: BrowserAuth,
+) -> (TestCluster, GasStationContainer, GasStationServer) {
+    let (test_cluster, container) = start_gas_station(init_gas_amounts, target_init_balance).await;
+    let localhost = localhost_for_testing();
+    let signer_address = container.get_signer_address();
+    std::env::set_var(AUTH_ENV_NAME, "some secret");
+
+    let server = GasStationServer::new(
+        container.get_gas_station_arc(),
+        localhost.parse().unwrap(),
+        get_available_port(&localhost),
+        GasStationRpcMetrics::new_for_testing(),
+        Arc::new(ArcSwap::new(Arc::new(AccessController::default()))),
+        new_stats_tracker_for_testing(signer_address).await,
+        PathBuf::from_str(DEFAULT_TEST_CONFIG_PATH).unwrap(),
+        None,
+        Some(browser_auth),
+        None,
+        None,
+        None,
     )
     .await;
     (test_cluster, container, server)
@@ -144,6 +198,11 @@ pub async fn start_rpc_server_for_testing_with_access_controller(
         Arc::new(ArcSwap::new(Arc::new(access_controller))),
         new_stats_tracker_for_testing(signer_address).await,
         PathBuf::from_str(DEFAULT_TEST_CONFIG_PATH).unwrap(),
+        None,
+        None,
+        None,
+        None,
+        None,
     )
     .await;
     (test_cluster, container, server)
@@ -89,7 +89,7 @@ pub async fn connect_stats_storage(
     sponsor_address: IotaAddress,
 ) -> RedisStatsTrackerStorage {
     let storage = match config {
-        GasStationStorageConfig::Redis { redis_url } => {
+        GasStationStorageConfig::Redis { redis_url, .. } => {
             RedisStatsTrackerStorage::new(redis_url, sponsor_address.to_string()).await
         }
     };
@@ -38,6 +38,13 @@ impl From<IotaGasCoin> for GasCoin {
     }
 }
 
+/// One of the balances new coins are split into, and the fraction of the new coins that get it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CoinDenomination {
+    pub balance: u64,
+    pub share: f64,
+}
+
 pub type ReservationID = u64;
 pub type ExpirationTimeMs = u64;
 pub type GasGroupKey = ObjectID;